@@ -0,0 +1,444 @@
+//! Document builder
+//!
+//! Fluent API to produce MML documents from code. The builder writes MML
+//! lines with the crate's escaping rules and parses them back with the
+//! configured [`ParserOptions`], so every built document round-trips through
+//! [`MMLParser::parse`] and obeys the same limits.
+
+use crate::error::{MMLError, MMLResult};
+use crate::escape::{escape_field, is_safe_leading_field};
+use crate::parser::{MMLParser, OrphanPolicy, ParserOptions};
+use crate::types::{MMLDocument, MMLTag, CODE_LANGUAGES};
+
+/// Builder for [`MMLDocument`]
+///
+/// # Example
+///
+/// ```rust
+/// use mml_parser::MMLDocumentBuilder;
+///
+/// let document = MMLDocumentBuilder::new()
+///     .title("Rapport d'urgence")
+///     .meta("Priorité", "CRITIQUE")
+///     .section("Situation actuelle", |s| {
+///         s.paragraph("Incendie déclaré à 14h30")
+///             .link("Carte secteur", "cartes/secteur7.png")
+///     })
+///     .build()
+///     .unwrap();
+///
+/// assert_eq!(document.sections[0].links.len(), 1);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MMLDocumentBuilder {
+    options: ParserOptions,
+    title: Option<String>,
+    header: LineWriter,
+    paragraphs: usize,
+    sections: Vec<LineWriter>,
+}
+
+impl MMLDocumentBuilder {
+    /// Create a builder using the default parser options
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a builder whose output is checked against custom options
+    pub fn with_options(options: ParserOptions) -> Self {
+        Self {
+            options,
+            ..Self::default()
+        }
+    }
+
+    /// Set the document title
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// Add a global metadata entry
+    pub fn meta(mut self, key: impl AsRef<str>, value: impl AsRef<str>) -> Self {
+        self.header.pair(MMLTag::Metadata, key.as_ref(), value.as_ref());
+        self
+    }
+
+    /// Add a global link
+    pub fn link(mut self, text: impl AsRef<str>, url: impl AsRef<str>) -> Self {
+        self.header.pair(MMLTag::Link, text.as_ref(), url.as_ref());
+        self
+    }
+
+    /// Add a document-level paragraph (placed per `orphan_policy`)
+    ///
+    /// An implicit section holds a single paragraph, so with
+    /// [`OrphanPolicy::CreateImplicitSection`] a second one fails.
+    pub fn paragraph(mut self, text: impl AsRef<str>) -> Self {
+        let text = text.as_ref();
+        if self.paragraphs > 0 && matches!(self.options.orphan_policy, OrphanPolicy::CreateImplicitSection(_)) {
+            self.header.fail(
+                MMLTag::Paragraph,
+                text,
+                "an implicit section holds a single paragraph",
+                "Join the text into one paragraph.",
+            );
+        }
+        self.paragraphs += 1;
+        self.header.text(MMLTag::Paragraph, text);
+        self
+    }
+
+    /// Add a document-level image (placed per `orphan_policy`)
+    pub fn image(mut self, description: impl AsRef<str>, url: impl AsRef<str>) -> Self {
+        self.header.pair(MMLTag::Image, description.as_ref(), url.as_ref());
+        self
+    }
+
+    /// Add a document-level code block (placed per `orphan_policy`)
+    pub fn code(mut self, language: Option<&str>, content: impl AsRef<str>) -> Self {
+        self.header.code(language, content.as_ref());
+        self
+    }
+
+    /// Add a document-level quote (placed per `orphan_policy`)
+    pub fn quote(mut self, text: impl AsRef<str>, attribution: Option<&str>) -> Self {
+        self.header.quote(text.as_ref(), attribution);
+        self
+    }
+
+    /// Add a section, filled by the given closure
+    pub fn section<F>(mut self, title: impl AsRef<str>, build: F) -> Self
+    where
+        F: FnOnce(SectionBuilder) -> SectionBuilder,
+    {
+        let mut section = SectionBuilder::default();
+        section.writer.text(MMLTag::Section, title.as_ref());
+        self.sections.push(build(section).writer);
+        self
+    }
+
+    /// Render the MML text the document is built from
+    pub fn to_mml(&self) -> MMLResult<String> {
+        let mut writer = LineWriter::default();
+        if let Some(title) = &self.title {
+            writer.text(MMLTag::Title, title);
+        }
+
+        let mut lines = writer.finish()?;
+        lines.extend(self.header.clone().finish()?);
+        for section in &self.sections {
+            lines.extend(section.clone().finish()?);
+        }
+
+        let mut mml = lines.join("\n");
+        mml.push('\n');
+        Ok(mml)
+    }
+
+    /// Build the document
+    ///
+    /// Fails on content that cannot be written as MML (line breaks,
+    /// unknown language names...) and on the parser's limits.
+    pub fn build(&self) -> MMLResult<MMLDocument> {
+        MMLParser::with_options(self.options.clone()).parse(&self.to_mml()?)
+    }
+}
+
+/// Builder for a single section, used through [`MMLDocumentBuilder::section`]
+#[derive(Debug, Clone, Default)]
+pub struct SectionBuilder {
+    writer: LineWriter,
+    has_paragraph: bool,
+}
+
+impl SectionBuilder {
+    /// Add a section metadata entry
+    pub fn meta(mut self, key: impl AsRef<str>, value: impl AsRef<str>) -> Self {
+        self.writer.pair(MMLTag::Metadata, key.as_ref(), value.as_ref());
+        self
+    }
+
+    /// Set the section paragraph
+    ///
+    /// A section holds a single paragraph: a second call fails.
+    pub fn paragraph(mut self, text: impl AsRef<str>) -> Self {
+        let text = text.as_ref();
+        if self.has_paragraph {
+            self.writer.fail(
+                MMLTag::Paragraph,
+                text,
+                "a section holds a single paragraph",
+                "Join the text into one paragraph.",
+            );
+        }
+        self.has_paragraph = true;
+        self.writer.text(MMLTag::Paragraph, text);
+        self
+    }
+
+    /// Add a link
+    pub fn link(mut self, text: impl AsRef<str>, url: impl AsRef<str>) -> Self {
+        self.writer.pair(MMLTag::Link, text.as_ref(), url.as_ref());
+        self
+    }
+
+    /// Add an image
+    pub fn image(mut self, description: impl AsRef<str>, url: impl AsRef<str>) -> Self {
+        self.writer.pair(MMLTag::Image, description.as_ref(), url.as_ref());
+        self
+    }
+
+    /// Add a code block, one `C:` line per line of `content`
+    pub fn code(mut self, language: Option<&str>, content: impl AsRef<str>) -> Self {
+        self.writer.code(language, content.as_ref());
+        self
+    }
+
+    /// Add a quote with an optional attribution
+    pub fn quote(mut self, text: impl AsRef<str>, attribution: Option<&str>) -> Self {
+        self.writer.quote(text.as_ref(), attribution);
+        self
+    }
+}
+
+/// Accumulates escaped MML lines and the first error met
+#[derive(Debug, Clone, Default)]
+struct LineWriter {
+    lines: Vec<String>,
+    last_tag: Option<MMLTag>,
+    error: Option<MMLError>,
+}
+
+impl LineWriter {
+    fn push(&mut self, tag: MMLTag, content: String) {
+        self.lines.push(format!("{}:{}", tag.to_str(), content));
+        self.last_tag = Some(tag);
+    }
+
+    fn fail(&mut self, tag: MMLTag, value: &str, reason: &str, suggestion: &str) {
+        if self.error.is_none() {
+            self.error = Some(MMLError::ValidationError {
+                field: tag.to_str().to_string(),
+                value: value.to_string(),
+                reason: reason.to_string(),
+                suggestion: suggestion.to_string(),
+            });
+        }
+    }
+
+    fn check_single_line(&mut self, tag: MMLTag, value: &str) -> bool {
+        if value.contains(['\n', '\r']) {
+            self.fail(tag, value, "line breaks are not allowed", "Split the text over several entries.");
+            return false;
+        }
+        true
+    }
+
+    /// The parser trims every field, and the end of code lines
+    fn check_trimmed(&mut self, tag: MMLTag, value: &str) -> bool {
+        let trimmed = if tag == MMLTag::Code { value.trim_end() } else { value.trim() };
+        if trimmed != value {
+            self.fail(tag, value, "surrounding whitespace is lost when parsing", "Trim the text.");
+            return false;
+        }
+        true
+    }
+
+    fn check_leading_field(&mut self, tag: MMLTag, value: &str) -> bool {
+        if !is_safe_leading_field(value) {
+            self.fail(tag, value, "a field followed by '|' cannot end with '\\'", "Remove the trailing backslash.");
+            return false;
+        }
+        true
+    }
+
+    fn text(&mut self, tag: MMLTag, text: &str) {
+        if self.check_single_line(tag, text) && self.check_trimmed(tag, text) {
+            self.push(tag, text.to_string());
+        }
+    }
+
+    fn pair(&mut self, tag: MMLTag, first: &str, second: &str) {
+        if self.check_single_line(tag, first)
+            && self.check_single_line(tag, second)
+            && self.check_trimmed(tag, first)
+            && self.check_trimmed(tag, second)
+            && self.check_leading_field(tag, first)
+        {
+            self.push(tag, format!("{}|{}", escape_field(first), escape_field(second)));
+        }
+    }
+
+    fn quote(&mut self, text: &str, attribution: Option<&str>) {
+        if !self.check_single_line(MMLTag::Quote, text) || !self.check_trimmed(MMLTag::Quote, text) {
+            return;
+        }
+        match attribution {
+            Some(source) => self.pair(MMLTag::Quote, text, source),
+            None => self.push(MMLTag::Quote, escape_field(text)),
+        }
+    }
+
+    fn code(&mut self, language: Option<&str>, content: &str) {
+        // The parser lowercases the prefix and only reads known names
+        if let Some(language) = language.filter(|language| !CODE_LANGUAGES.contains(language)) {
+            self.fail(
+                MMLTag::Code,
+                language,
+                "unknown language name",
+                "Use a lowercase name of CODE_LANGUAGES such as 'python' or 'c++'.",
+            );
+            return;
+        }
+
+        // A comment line keeps the block apart from a previous one, and
+        // from the language hint of a preceding `M:lang|...`
+        if matches!(self.last_tag, Some(MMLTag::Code | MMLTag::Metadata)) {
+            self.lines.push("#".to_string());
+        }
+
+        for (i, line) in content.split('\n').enumerate() {
+            let line = line.strip_suffix('\r').unwrap_or(line);
+            if !self.check_trimmed(MMLTag::Code, line) {
+                return;
+            }
+            let prefix = match (language, i) {
+                (Some(language), 0) => format!("{}|", language),
+                (None, 0) if line.contains('|') => "|".to_string(),
+                _ => String::new(),
+            };
+            self.push(MMLTag::Code, format!("{}{}", prefix, line));
+        }
+    }
+
+    fn finish(self) -> MMLResult<Vec<String>> {
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(self.lines),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MMLCode, MMLQuote};
+
+    fn incident_report() -> MMLDocumentBuilder {
+        MMLDocumentBuilder::new()
+            .title("Rapport d'urgence - Incendie")
+            .meta("ID", "INC-2025-001")
+            .section("Situation actuelle", |s| {
+                s.paragraph("Incendie de forêt déclaré à 14h30")
+                    .meta("Surface", "450 hectares")
+                    .link("Carte A|B", "cartes/incendie-secteur7.png")
+                    .code(Some("python"), "if power_failure:\n    switch_to_backup()")
+                    .quote("La rapidité | sauve des vies", None)
+            })
+            .meta("Priorité", "CRITIQUE")
+    }
+
+    #[test]
+    fn test_build_document() {
+        let doc = incident_report().build().unwrap();
+
+        assert_eq!(doc.title.as_deref(), Some("Rapport d'urgence - Incendie"));
+        assert_eq!(doc.metadata.get("Priorité"), Some(&"CRITIQUE".to_string()));
+        let section = &doc.sections[0];
+        assert_eq!(section.metadata.get("Surface"), Some(&"450 hectares".to_string()));
+        assert_eq!(section.links[0].text, "Carte A|B");
+        assert_eq!(
+            section.code[0],
+            MMLCode::new("if power_failure:\n    switch_to_backup()".to_string(), Some("python".to_string()))
+        );
+        assert_eq!(section.quotes[0], MMLQuote::new("La rapidité | sauve des vies".to_string(), None));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let doc = MMLDocumentBuilder::new()
+            .title("Commandes")
+            .section("Shell", |s| {
+                s.meta("lang", "sh")
+                    .code(None, "pwd")
+                    .code(Some("sh"), "ls")
+                    .code(None, "ls | wc -l")
+                    .paragraph("Exemples")
+            })
+            .build()
+            .unwrap();
+
+        let section = &doc.sections[0];
+        assert_eq!(section.title, "Shell");
+        assert_eq!(section.metadata.get("lang"), Some(&"sh".to_string()));
+        assert_eq!(
+            section.code,
+            vec![
+                MMLCode::new("pwd".to_string(), None),
+                MMLCode::new("ls".to_string(), Some("sh".to_string())),
+                MMLCode::new("ls | wc -l".to_string(), None),
+            ]
+        );
+        assert_eq!(section.content.as_deref(), Some("Exemples"));
+    }
+
+    #[test]
+    fn test_rejects_lossy_content() {
+        let result = MMLDocumentBuilder::new()
+            .section("S", |s| s.paragraph("premier").paragraph("second"))
+            .build();
+        assert!(matches!(result, Err(MMLError::ValidationError { .. })));
+
+        let result = MMLDocumentBuilder::new().section("S", |s| s.paragraph("  padded  ")).build();
+        assert!(matches!(result, Err(MMLError::ValidationError { .. })));
+
+        let result = MMLDocumentBuilder::new().link("Accueil ", "/").build();
+        assert!(matches!(result, Err(MMLError::ValidationError { .. })));
+
+        let result = MMLDocumentBuilder::new().section("S", |s| s.code(None, "x = 1  \ny = 2")).build();
+        assert!(matches!(result, Err(MMLError::ValidationError { .. })));
+
+        let options = ParserOptions {
+            orphan_policy: OrphanPolicy::CreateImplicitSection("Intro".to_string()),
+            ..ParserOptions::default()
+        };
+        let result = MMLDocumentBuilder::with_options(options).paragraph("a").paragraph("b").build();
+        assert!(matches!(result, Err(MMLError::ValidationError { .. })));
+    }
+
+    #[test]
+    fn test_rejects_line_breaks_and_bad_languages() {
+        let result = MMLDocumentBuilder::new().paragraph("ligne 1\nligne 2").build();
+        assert!(matches!(result, Err(MMLError::ValidationError { .. })));
+
+        let result = MMLDocumentBuilder::new()
+            .section("Code", |s| s.code(Some("c sharp"), "x"))
+            .build();
+        assert!(matches!(result, Err(MMLError::ValidationError { .. })));
+
+        let result = MMLDocumentBuilder::new().section("Code", |s| s.code(Some("ls"), "wc -l")).build();
+        assert!(matches!(result, Err(MMLError::ValidationError { .. })));
+
+        let result = MMLDocumentBuilder::new().meta("clé\\", "valeur").build();
+        assert!(matches!(result, Err(MMLError::ValidationError { .. })));
+    }
+
+    #[test]
+    fn test_enforces_parser_options() {
+        let options = ParserOptions {
+            max_sections: 1,
+            orphan_policy: OrphanPolicy::Error,
+            ..ParserOptions::default()
+        };
+
+        let result = MMLDocumentBuilder::with_options(options.clone())
+            .section("A", |s| s)
+            .section("B", |s| s)
+            .build();
+        assert!(matches!(result, Err(MMLError::LimitExceeded { .. })));
+
+        let result = MMLDocumentBuilder::with_options(options).paragraph("Orphelin").build();
+        assert!(matches!(result, Err(MMLError::StructureError { .. })));
+    }
+}
@@ -0,0 +1,303 @@
+//! Canonical form and fingerprint
+//!
+//! Two transmissions of the same document must get the same identity even
+//! when spacing, line order of metadata or Unicode composition differ. The
+//! canonical form is the document written back from its DOM with these rules:
+//!
+//! 1. Nodes are written in DOM order: `T:`, document metadata, paragraphs,
+//!    links, images, code, quotes, then each `H:` followed by its metadata,
+//!    paragraph, links, images, code and quotes.
+//! 2. Metadata is sorted by key (byte order of the normalized key), at
+//!    document and section level. The DOM does not keep the original order.
+//! 3. Every text is in a restricted NFC: a Latin base letter followed by
+//!    combining marks (within U+0300..U+0331) becomes the precomposed
+//!    letter of U+00C0..U+024F or U+1E00..U+1EFF, marks put in canonical
+//!    order. Every other character is kept as is, including Greek,
+//!    Cyrillic or Hangul sequences that full NFC composes and singletons
+//!    such as U+212B ANGSTROM SIGN, so their spellings fingerprint apart.
+//!    Outside code, leading and trailing whitespace is removed and inner
+//!    runs of whitespace become one space. Code keeps its indentation;
+//!    trailing spaces and blank trailing lines are removed.
+//! 4. `|` inside `M:`, `L:`, `IMG:` and `Q:` fields is escaped as `\|`.
+//! 5. Comments, blank lines, packets, `RULE:` and `HASH:` lines are not part
+//!    of the form. Lines end with `\n`, including the last one.
+//!
+//! The fingerprint is the SHA-256 of the UTF-8 canonical form, in lowercase
+//! hexadecimal, and is what a `HASH:SHA256:` line carries.
+
+use crate::error::{MMLError, MMLResult};
+use crate::ser::write_document;
+use crate::types::*;
+use crate::unicode::nfc;
+
+impl MMLDocument {
+    /// Canonical MML text of the document
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use mml_parser::parse_mml;
+    ///
+    /// let a = parse_mml("T:Alerte   crue\nM:Zone|Nord\nM:Niveau|3").unwrap();
+    /// let b = parse_mml("T: Alerte crue\nM:Niveau|3\nM:Zone|Nord").unwrap();
+    ///
+    /// assert_eq!(a.canonical_mml(), "T:Alerte crue\nM:Niveau|3\nM:Zone|Nord\n");
+    /// assert_eq!(a.fingerprint(), b.fingerprint());
+    /// ```
+    pub fn canonical_mml(&self) -> String {
+        write_document(&canonicalize(self))
+    }
+
+    /// SHA-256 of the canonical form, in lowercase hexadecimal
+    pub fn fingerprint(&self) -> String {
+        sha256(self.canonical_mml().as_bytes())
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+
+    /// `HASH:` line to append to the document
+    pub fn hash_line(&self) -> String {
+        format!("HASH:SHA256:{}", self.fingerprint())
+    }
+}
+
+/// Check the `HASH:SHA256:` line of an MML text against its content
+///
+/// The content is compared in canonical form, so whitespace changes or
+/// reordered metadata do not break verification.
+///
+/// # Example
+///
+/// ```rust
+/// use mml_parser::{canonical::verify_hash, parse_mml};
+///
+/// let text = "T:Document officiel\nH:Section 1\nP:Contenu authentifié";
+/// let signed = format!("{}\n{}", text, parse_mml(text).unwrap().hash_line());
+///
+/// assert!(verify_hash(&signed).unwrap());
+/// assert!(verify_hash(&signed.replace("P:Contenu", "P:  Contenu")).unwrap());
+/// assert!(!verify_hash(&signed.replace("authentifié", "modifié")).unwrap());
+/// ```
+pub fn verify_hash(input: &str) -> MMLResult<bool> {
+    let hash = input
+        .lines()
+        .map(str::trim)
+        .find_map(|line| line.strip_prefix("HASH:"))
+        .ok_or_else(|| MMLError::ValidationError {
+            field: "HASH".to_string(),
+            value: String::new(),
+            reason: "the document has no HASH: line".to_string(),
+            suggestion: "Append the line given by MMLDocument::hash_line().".to_string(),
+        })?;
+
+    let expected = match hash.split_once(':') {
+        Some((algorithm, value)) if algorithm.trim().eq_ignore_ascii_case("SHA256") => value.trim(),
+        _ => {
+            return Err(MMLError::ValidationError {
+                field: "HASH".to_string(),
+                value: hash.to_string(),
+                reason: "only SHA256 hashes are supported".to_string(),
+                suggestion: "Write the line as 'HASH:SHA256:<hex>'.".to_string(),
+            })
+        }
+    };
+
+    let document = crate::parse_mml(input)?;
+    Ok(document.fingerprint().eq_ignore_ascii_case(expected))
+}
+
+/// Normalized copy of a document
+fn canonicalize(document: &MMLDocument) -> MMLDocument {
+    MMLDocument {
+        title: document.title.as_deref().map(text),
+        metadata: document.metadata.iter().map(|(k, v)| (text(k), text(v))).collect(),
+        sections: document.sections.iter().map(canonical_section).collect(),
+        links: document.links.iter().map(link).collect(),
+        content: document.content.iter().map(|p| text(p)).collect(),
+        images: document.images.iter().map(image).collect(),
+        code: document.code.iter().map(code).collect(),
+        quotes: document.quotes.iter().map(quote).collect(),
+        ..MMLDocument::default()
+    }
+}
+
+fn canonical_section(section: &MMLSection) -> MMLSection {
+    MMLSection {
+        title: text(&section.title),
+        content: section.content.as_deref().map(text),
+        metadata: section.metadata.iter().map(|(k, v)| (text(k), text(v))).collect(),
+        links: section.links.iter().map(link).collect(),
+        images: section.images.iter().map(image).collect(),
+        code: section.code.iter().map(code).collect(),
+        quotes: section.quotes.iter().map(quote).collect(),
+    }
+}
+
+fn text(value: &str) -> String {
+    nfc(&value.split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
+fn link(link: &MMLLink) -> MMLLink {
+    MMLLink::new(text(&link.text), text(&link.url))
+}
+
+fn image(image: &MMLImage) -> MMLImage {
+    MMLImage::new(text(&image.description), text(&image.url))
+}
+
+fn code(code: &MMLCode) -> MMLCode {
+    let lines: Vec<String> = code.lines().map(|line| nfc(line.trim_end())).collect();
+    let end = lines.iter().rposition(|line| !line.is_empty()).map_or(0, |i| i + 1);
+    MMLCode::new(lines[..end].join("\n"), code.language.as_deref().map(text))
+}
+
+fn quote(quote: &MMLQuote) -> MMLQuote {
+    MMLQuote::new(text(&quote.content), quote.attribution.as_deref().map(text))
+}
+
+/// SHA-256 (FIPS 180-4)
+fn sha256(data: &[u8]) -> [u8; 32] {
+    const K: [u32; 64] = [
+        0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5, 0xd807aa98,
+        0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174, 0xe49b69c1, 0xefbe4786,
+        0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da, 0x983e5152, 0xa831c66d, 0xb00327c8,
+        0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967, 0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13,
+        0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85, 0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819,
+        0xd6990624, 0xf40e3585, 0x106aa070, 0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a,
+        0x5b9cca4f, 0x682e6ff3, 0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7,
+        0xc67178f2,
+    ];
+    let mut state: [u32; 8] = [
+        0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+    ];
+
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&((data.len() as u64) * 8).to_be_bytes());
+
+    for block in message.chunks_exact(64) {
+        let mut w = [0u32; 64];
+        for (i, word) in block.chunks_exact(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..64 {
+            let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+            let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+            w[i] = w[i - 16].wrapping_add(s0).wrapping_add(w[i - 7]).wrapping_add(s1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = state;
+        for i in 0..64 {
+            let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let choice = (e & f) ^ (!e & g);
+            let t1 = h
+                .wrapping_add(s1)
+                .wrapping_add(choice)
+                .wrapping_add(K[i])
+                .wrapping_add(w[i]);
+            let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let majority = (a & b) ^ (a & c) ^ (b & c);
+            let t2 = s0.wrapping_add(majority);
+            h = g;
+            g = f;
+            f = e;
+            e = d.wrapping_add(t1);
+            d = c;
+            c = b;
+            b = a;
+            a = t1.wrapping_add(t2);
+        }
+        for (value, add) in state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+            *value = value.wrapping_add(add);
+        }
+    }
+
+    let mut digest = [0u8; 32];
+    for (chunk, value) in digest.chunks_exact_mut(4).zip(state) {
+        chunk.copy_from_slice(&value.to_be_bytes());
+    }
+    digest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_mml;
+
+    fn hex(data: &[u8]) -> String {
+        sha256(data).iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    #[test]
+    fn test_sha256_vectors() {
+        assert_eq!(
+            hex(b""),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(
+            hex(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(
+            hex(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"),
+            "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"
+        );
+    }
+
+    #[test]
+    fn test_canonical_form() {
+        let document = parse_mml(
+            "T:Rapport\u{a0} d'e\u{301}tat\nM:Zone|Nord  \\|  Est\n\n# commentaire\nH:Situation\nM:B|2\nM:A|1\n\
+             P:Feu   actif\nC:python|def f():\nC:    return 1   \nQ:Tenir|Chef",
+        )
+        .unwrap();
+
+        assert_eq!(
+            document.canonical_mml(),
+            "T:Rapport d'état\nM:Zone|Nord \\| Est\nH:Situation\nM:A|1\nM:B|2\nP:Feu actif\n\
+             C:python|def f():\nC:    return 1\nQ:Tenir|Chef\n"
+        );
+    }
+
+    #[test]
+    fn test_fingerprint_ignores_harmless_changes() {
+        let original = parse_mml("T:Alerte\nM:Priorité|HAUTE\nM:Zone|B7\nH:Actions\nP:Évacuer le secteur").unwrap();
+        let retransmitted = parse_mml(
+            "T:  Alerte\nM:Zone|B7\nM:Priorite\u{301}|HAUTE\n\nH:Actions\nP:E\u{301}vacuer  le secteur\nHASH:SHA256:00",
+        )
+        .unwrap();
+        let changed = parse_mml("T:Alerte\nM:Priorité|BASSE\nM:Zone|B7\nH:Actions\nP:Évacuer le secteur").unwrap();
+
+        assert_eq!(original.fingerprint().len(), 64);
+        assert_eq!(original.fingerprint(), retransmitted.fingerprint());
+        assert_ne!(original.fingerprint(), changed.fingerprint());
+    }
+
+    #[test]
+    fn test_composition_is_latin_only() {
+        // Full NFC would give U+03AC, U+D55C and U+00C5; rule 3 keeps them
+        for (precomposed, other) in [("ά", "α\u{301}"), ("한", "\u{1112}\u{1161}\u{11ab}"), ("Å", "\u{212b}")] {
+            let a = parse_mml(&format!("T:{}", precomposed)).unwrap();
+            let b = parse_mml(&format!("T:{}", other)).unwrap();
+            assert_eq!(b.canonical_mml(), format!("T:{}\n", other));
+            assert_ne!(a.fingerprint(), b.fingerprint());
+        }
+    }
+
+    #[test]
+    fn test_verify_hash_errors() {
+        assert!(matches!(
+            verify_hash("T:Sans empreinte"),
+            Err(MMLError::ValidationError { .. })
+        ));
+        assert!(matches!(
+            verify_hash("T:Doc\nHASH:MD5:abc"),
+            Err(MMLError::ValidationError { .. })
+        ));
+        assert!(!verify_hash("T:Doc\nHASH:SHA256:ceci-est-un-exemple").unwrap());
+    }
+}
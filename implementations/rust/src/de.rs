@@ -0,0 +1,515 @@
+//! Deserialize user types from MML
+//!
+//! [`from_str`] parses a document and maps it onto any `Deserialize` type.
+//! Struct fields are looked up, in order, as:
+//!
+//! - `title`: the `T:` line (or the section title, inside a section)
+//! - a metadata key (`M:key|value`), exactly as written, so accented keys
+//!   need `#[serde(rename = "Priorité")]`
+//! - `sections`: every `H:` block, as a sequence
+//! - `content`: the paragraphs (`Vec<String>` at document level, the
+//!   paragraph of a section)
+//! - `links`, `images`, `code`, `quotes`: the DOM nodes, with the field
+//!   names of [`MMLLink`], [`MMLImage`], [`MMLCode`] and [`MMLQuote`]
+//! - a section title: that `H:` block, or all blocks with that title when
+//!   the field is a sequence
+//!
+//! Metadata values are text; numbers and booleans are read with the
+//! lenient rules of [`MetadataValue`], so `surface: f64` accepts
+//! `450 hectares` and `chiffrement: bool` accepts `oui`. Missing fields
+//! follow the usual serde rules (`Option` is `None`, `#[serde(default)]`).
+
+use crate::error::{MMLError, MMLResult};
+use crate::metadata::MetadataValue;
+use crate::types::*;
+use serde::de::value::BorrowedStrDeserializer;
+use serde::de::{
+    self, Deserialize, DeserializeOwned, DeserializeSeed, IntoDeserializer, MapAccess, SeqAccess, Visitor,
+};
+use serde::forward_to_deserialize_any;
+use std::fmt;
+
+/// Deserialize a value from MML text
+///
+/// # Example
+///
+/// ```rust
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct IncidentReport {
+///     title: String,
+///     #[serde(rename = "Priorité")]
+///     priority: String,
+///     sections: Vec<Section>,
+/// }
+///
+/// #[derive(Deserialize)]
+/// struct Section {
+///     title: String,
+///     content: Option<String>,
+///     #[serde(rename = "Surface")]
+///     surface: Option<f64>,
+/// }
+///
+/// let report: IncidentReport = mml_parser::from_str(
+///     "T:Incendie\nM:Priorité|CRITIQUE\nH:Situation\nP:Feu actif\nM:Surface|450 hectares",
+/// )
+/// .unwrap();
+///
+/// assert_eq!(report.priority, "CRITIQUE");
+/// assert_eq!(report.sections[0].surface, Some(450.0));
+/// ```
+pub fn from_str<T: DeserializeOwned>(input: &str) -> MMLResult<T> {
+    let document = crate::parse_mml(input)?;
+    from_document(&document)
+}
+
+/// Deserialize a value from a parsed document
+///
+/// Borrowed fields (`&str`) may point into the document.
+pub fn from_document<'a, T: Deserialize<'a>>(document: &'a MMLDocument) -> MMLResult<T> {
+    T::deserialize(Node::Document(document))
+}
+
+impl de::Error for MMLError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        MMLError::ParseError {
+            message: msg.to_string(),
+            context: "deserialization".to_string(),
+        }
+    }
+}
+
+/// Part of a document being deserialized
+#[derive(Debug, Clone)]
+enum Node<'a> {
+    Document(&'a MMLDocument),
+    /// Sections sharing a title: one struct, or a sequence of them
+    Sections(Vec<&'a MMLSection>),
+    Text(&'a str),
+    Absent,
+    Seq(Vec<Node<'a>>),
+    Fields(Vec<(&'a str, Node<'a>)>),
+}
+
+impl<'a> Node<'a> {
+    fn text(value: Option<&'a String>) -> Self {
+        value.map_or(Node::Absent, |value| Node::Text(value))
+    }
+
+    fn link(link: &'a MMLLink) -> Self {
+        Node::Fields(vec![("text", Node::Text(&link.text)), ("url", Node::Text(&link.url))])
+    }
+
+    fn image(image: &'a MMLImage) -> Self {
+        Node::Fields(vec![
+            ("description", Node::Text(&image.description)),
+            ("url", Node::Text(&image.url)),
+        ])
+    }
+
+    fn code(code: &'a MMLCode) -> Self {
+        Node::Fields(vec![
+            ("content", Node::Text(&code.content)),
+            ("language", Node::text(code.language.as_ref())),
+        ])
+    }
+
+    fn quote(quote: &'a MMLQuote) -> Self {
+        Node::Fields(vec![
+            ("content", Node::Text(&quote.content)),
+            ("attribution", Node::text(quote.attribution.as_ref())),
+        ])
+    }
+
+    /// Entries of a document or section, for the requested field names
+    ///
+    /// Without field names (maps), only the title and metadata are given.
+    fn entries(&self, fields: Option<&[&'static str]>) -> MMLResult<Vec<(&'a str, Node<'a>)>> {
+        let (document, section) = match self {
+            Node::Document(document) => (Some(*document), None),
+            Node::Sections(sections) => match sections.first() {
+                Some(section) => (None, Some(*section)),
+                None => return Err(de::Error::custom("no section to read")),
+            },
+            _ => unreachable!("only documents and sections have entries"),
+        };
+        let title = match (document, section) {
+            (Some(document), _) => document.title.as_deref(),
+            (_, Some(section)) => Some(section.title.as_str()),
+            _ => None,
+        };
+        let metadata = document.map_or_else(|| &section.unwrap().metadata, |d| &d.metadata);
+
+        let Some(fields) = fields else {
+            let mut entries: Vec<_> = metadata.iter().map(|(k, v)| (k.as_str(), Node::Text(v))).collect();
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+            if let Some(title) = title {
+                entries.insert(0, ("title", Node::Text(title)));
+            }
+            return Ok(entries);
+        };
+
+        let mut entries = Vec::new();
+        for &field in fields {
+            let node = if field == "title" {
+                title.map(Node::Text)
+            } else if let Some(value) = metadata.get(field) {
+                Some(Node::Text(value))
+            } else {
+                match (document, section) {
+                    (Some(document), _) => Self::document_field(document, field),
+                    (_, Some(section)) => Self::section_field(section, field),
+                    _ => None,
+                }
+            };
+            if let Some(node) = node {
+                entries.push((field, node));
+            }
+        }
+        Ok(entries)
+    }
+
+    fn document_field(document: &'a MMLDocument, field: &str) -> Option<Self> {
+        let node = match field {
+            "sections" => Node::Seq(document.sections.iter().map(|s| Node::Sections(vec![s])).collect()),
+            "content" => Node::Seq(document.content.iter().map(|p| Node::Text(p)).collect()),
+            "links" => Node::Seq(document.links.iter().map(Node::link).collect()),
+            "images" => Node::Seq(document.images.iter().map(Node::image).collect()),
+            "code" => Node::Seq(document.code.iter().map(Node::code).collect()),
+            "quotes" => Node::Seq(document.quotes.iter().map(Node::quote).collect()),
+            _ => {
+                let sections: Vec<_> = document.sections.iter().filter(|s| s.title == field).collect();
+                if sections.is_empty() {
+                    return None;
+                }
+                Node::Sections(sections)
+            }
+        };
+        Some(node)
+    }
+
+    fn section_field(section: &'a MMLSection, field: &str) -> Option<Self> {
+        let node = match field {
+            "content" => Node::text(section.content.as_ref()),
+            "links" => Node::Seq(section.links.iter().map(Node::link).collect()),
+            "images" => Node::Seq(section.images.iter().map(Node::image).collect()),
+            "code" => Node::Seq(section.code.iter().map(Node::code).collect()),
+            "quotes" => Node::Seq(section.quotes.iter().map(Node::quote).collect()),
+            _ => return None,
+        };
+        Some(node)
+    }
+
+    fn number(&self) -> MMLResult<f64> {
+        match self {
+            Node::Text(text) => {
+                let value = MetadataValue::new(text);
+                value
+                    .as_number()
+                    .or_else(|| value.as_quantity().map(|q| q.value))
+                    .ok_or_else(|| de::Error::invalid_value(de::Unexpected::Str(text), &"a number"))
+            }
+            other => Err(other.unexpected("a number")),
+        }
+    }
+
+    fn unexpected(&self, expected: &str) -> MMLError {
+        let found = match self {
+            Node::Document(_) => "a document",
+            Node::Sections(_) => "a section",
+            Node::Text(_) => "text",
+            Node::Absent => "nothing",
+            Node::Seq(_) => "a sequence",
+            Node::Fields(_) => "a node",
+        };
+        de::Error::custom(format!("expected {}, found {}", expected, found))
+    }
+}
+
+macro_rules! deserialize_integer {
+    ($method:ident, $visit:ident, $ty:ty) => {
+        fn $method<V: Visitor<'de>>(self, visitor: V) -> MMLResult<V::Value> {
+            if let Node::Text(text) = &self {
+                if let Ok(value) = text.trim().parse::<$ty>() {
+                    return visitor.$visit(value);
+                }
+            }
+            let number = self.number()?;
+            if number.fract() != 0.0 || number < <$ty>::MIN as f64 || number > <$ty>::MAX as f64 {
+                return Err(de::Error::invalid_value(
+                    de::Unexpected::Float(number),
+                    &stringify!($ty),
+                ));
+            }
+            visitor.$visit(number as $ty)
+        }
+    };
+}
+
+impl<'de> de::Deserializer<'de> for Node<'de> {
+    type Error = MMLError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> MMLResult<V::Value> {
+        match self {
+            Node::Text(text) => visitor.visit_borrowed_str(text),
+            Node::Absent => visitor.visit_none(),
+            Node::Seq(items) => visitor.visit_seq(NodeSeq(items.into_iter())),
+            Node::Fields(entries) => visitor.visit_map(NodeMap::new(entries)),
+            node @ (Node::Document(_) | Node::Sections(_)) => visitor.visit_map(NodeMap::new(node.entries(None)?)),
+        }
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> MMLResult<V::Value> {
+        match self {
+            Node::Text(text) => match MetadataValue::new(text).as_bool() {
+                Some(value) => visitor.visit_bool(value),
+                None => Err(de::Error::invalid_value(de::Unexpected::Str(text), &"a yes/no value")),
+            },
+            other => Err(other.unexpected("a yes/no value")),
+        }
+    }
+
+    deserialize_integer!(deserialize_i8, visit_i8, i8);
+    deserialize_integer!(deserialize_i16, visit_i16, i16);
+    deserialize_integer!(deserialize_i32, visit_i32, i32);
+    deserialize_integer!(deserialize_i64, visit_i64, i64);
+    deserialize_integer!(deserialize_u8, visit_u8, u8);
+    deserialize_integer!(deserialize_u16, visit_u16, u16);
+    deserialize_integer!(deserialize_u32, visit_u32, u32);
+    deserialize_integer!(deserialize_u64, visit_u64, u64);
+
+    fn deserialize_f32<V: Visitor<'de>>(self, visitor: V) -> MMLResult<V::Value> {
+        visitor.visit_f32(self.number()? as f32)
+    }
+
+    fn deserialize_f64<V: Visitor<'de>>(self, visitor: V) -> MMLResult<V::Value> {
+        visitor.visit_f64(self.number()?)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> MMLResult<V::Value> {
+        match self {
+            Node::Absent => visitor.visit_none(),
+            other => visitor.visit_some(other),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> MMLResult<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> MMLResult<V::Value> {
+        match self {
+            Node::Seq(items) => visitor.visit_seq(NodeSeq(items.into_iter())),
+            Node::Sections(sections) => {
+                let items: Vec<_> = sections.into_iter().map(|s| Node::Sections(vec![s])).collect();
+                visitor.visit_seq(NodeSeq(items.into_iter()))
+            }
+            other => Err(other.unexpected("a sequence")),
+        }
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, visitor: V) -> MMLResult<V::Value> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> MMLResult<V::Value> {
+        match self {
+            node @ (Node::Document(_) | Node::Sections(_)) => {
+                visitor.visit_map(NodeMap::new(node.entries(Some(fields))?))
+            }
+            other => other.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> MMLResult<V::Value> {
+        match self {
+            Node::Text(text) => visitor.visit_enum(text.trim().into_deserializer()),
+            other => Err(other.unexpected("a unit variant name")),
+        }
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> MMLResult<V::Value> {
+        visitor.visit_unit()
+    }
+
+    forward_to_deserialize_any! {
+        i128 u128 char str string bytes byte_buf unit unit_struct
+        tuple_struct map identifier
+    }
+}
+
+struct NodeSeq<'a>(std::vec::IntoIter<Node<'a>>);
+
+impl<'de> SeqAccess<'de> for NodeSeq<'de> {
+    type Error = MMLError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> MMLResult<Option<T::Value>> {
+        self.0.next().map(|node| seed.deserialize(node)).transpose()
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.0.len())
+    }
+}
+
+struct NodeMap<'a> {
+    entries: std::vec::IntoIter<(&'a str, Node<'a>)>,
+    value: Option<Node<'a>>,
+}
+
+impl<'a> NodeMap<'a> {
+    fn new(entries: Vec<(&'a str, Node<'a>)>) -> Self {
+        Self {
+            entries: entries.into_iter(),
+            value: None,
+        }
+    }
+}
+
+impl<'de> MapAccess<'de> for NodeMap<'de> {
+    type Error = MMLError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> MMLResult<Option<K::Value>> {
+        match self.entries.next() {
+            Some((key, value)) => {
+                self.value = Some(value);
+                seed.deserialize(BorrowedStrDeserializer::new(key)).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> MMLResult<V::Value> {
+        let value = self
+            .value
+            .take()
+            .ok_or_else(|| <MMLError as de::Error>::custom("value requested before key"))?;
+        seed.deserialize(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use std::collections::HashMap;
+
+    const REPORT: &str = "T:Rapport d'urgence - Incendie\nM:ID|INC-2025-001\nM:Priorité|CRITIQUE\n\
+                          M:Effectifs|45\nM:Chiffrement|oui\n\
+                          H:Situation actuelle\nP:Incendie de forêt déclaré à 14h30\n\
+                          M:Surface|450 hectares\nM:Vent|25 km/h NNE\nL:Carte secteur|cartes/secteur7.png\n\
+                          H:Moyens engagés\nM:Effectifs|45\nC:python|deploy()";
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    enum Priority {
+        #[serde(rename = "CRITIQUE")]
+        Critical,
+        #[serde(rename = "NORMALE")]
+        Normal,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct IncidentReport {
+        title: String,
+        #[serde(rename = "ID")]
+        id: String,
+        #[serde(rename = "Priorité")]
+        priority: Priority,
+        #[serde(rename = "Effectifs")]
+        staff: u32,
+        #[serde(rename = "Chiffrement")]
+        encrypted: bool,
+        #[serde(rename = "Absent")]
+        missing: Option<String>,
+        sections: Vec<Section>,
+        #[serde(rename = "Situation actuelle")]
+        situation: Situation,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Section {
+        title: String,
+        content: Option<String>,
+        #[serde(default)]
+        code: Vec<MMLCode>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Situation {
+        #[serde(rename = "Surface")]
+        surface: f64,
+        #[serde(rename = "Vent")]
+        wind: String,
+        links: Vec<MMLLink>,
+    }
+
+    #[test]
+    fn test_deserialize_report() {
+        let report: IncidentReport = from_str(REPORT).unwrap();
+
+        assert_eq!(report.title, "Rapport d'urgence - Incendie");
+        assert_eq!(report.id, "INC-2025-001");
+        assert_eq!(report.priority, Priority::Critical);
+        assert_eq!(report.staff, 45);
+        assert!(report.encrypted);
+        assert_eq!(report.missing, None);
+
+        assert_eq!(report.sections.len(), 2);
+        assert_eq!(report.sections[0].title, "Situation actuelle");
+        assert_eq!(
+            report.sections[0].content.as_deref(),
+            Some("Incendie de forêt déclaré à 14h30")
+        );
+        assert_eq!(report.sections[1].code[0].language.as_deref(), Some("python"));
+
+        assert_eq!(report.situation.surface, 450.0);
+        assert_eq!(report.situation.wind, "25 km/h NNE");
+        assert_eq!(report.situation.links[0].url, "cartes/secteur7.png");
+    }
+
+    #[test]
+    fn test_deserialize_map_and_borrowed() {
+        let metadata: HashMap<String, String> = from_str(REPORT).unwrap();
+        assert_eq!(metadata["title"], "Rapport d'urgence - Incendie");
+        assert_eq!(metadata["Priorité"], "CRITIQUE");
+
+        #[derive(Deserialize)]
+        struct Header<'a> {
+            #[serde(rename = "ID")]
+            id: &'a str,
+        }
+        let document = crate::parse_mml(REPORT).unwrap();
+        let header: Header<'_> = from_document(&document).unwrap();
+        assert_eq!(header.id, "INC-2025-001");
+    }
+
+    #[test]
+    fn test_deserialize_errors() {
+        #[derive(Debug, Deserialize)]
+        struct Strict {
+            #[serde(rename = "Priorité")]
+            _priority: u32,
+        }
+        assert!(matches!(from_str::<Strict>(REPORT), Err(MMLError::ParseError { .. })));
+
+        #[derive(Debug, Deserialize)]
+        struct Required {
+            #[serde(rename = "Bilan")]
+            _bilan: String,
+        }
+        let error = from_str::<Required>(REPORT).unwrap_err();
+        assert!(error.to_string().contains("missing field `Bilan`"));
+    }
+}
@@ -0,0 +1,537 @@
+//! Structural diff
+//!
+//! [`diff`] compares two documents node by node rather than line by line, so
+//! reordering metadata or re-wrapping a file does not show up as a change.
+//!
+//! Sections are matched by title, metadata by key, links by text, images
+//! by description, code blocks by language and quotes by attribution; a
+//! matched pair whose other field differs is reported as modified. When a
+//! title (or link text...) appears several times, the n-th occurrence in
+//! the old document is matched with the n-th in the new one.
+//! Paragraphs are compared as whole strings and are only added or removed.
+
+use crate::escape::escape_field;
+use crate::query::MMLNode;
+use crate::types::{MMLCode, MMLDocument, MMLImage, MMLLink, MMLQuote, MMLSection};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+/// A single change between two versions of a value
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "change", content = "value", rename_all = "lowercase")]
+pub enum Change<T> {
+    /// Present only in the new document
+    Added(T),
+    /// Present only in the old document
+    Removed(T),
+    /// Present in both, with different values
+    Modified { old: T, new: T },
+}
+
+impl<T> Change<T> {
+    /// Kind of change, without the values
+    pub fn kind(&self) -> ChangeKind {
+        match self {
+            Change::Added(_) => ChangeKind::Added,
+            Change::Removed(_) => ChangeKind::Removed,
+            Change::Modified { .. } => ChangeKind::Modified,
+        }
+    }
+}
+
+/// Kind of a [`Change`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Modified,
+}
+
+impl ChangeKind {
+    /// Marker used in the text rendering (`+`, `-`, `~`)
+    pub fn marker(self) -> char {
+        match self {
+            ChangeKind::Added => '+',
+            ChangeKind::Removed => '-',
+            ChangeKind::Modified => '~',
+        }
+    }
+}
+
+/// Changes within a section
+///
+/// Added and removed sections carry no detail: their content is in the
+/// document they come from.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SectionDiff {
+    /// Section title, used to match sections
+    pub title: String,
+    /// Whether the section was added, removed or modified
+    pub kind: ChangeKind,
+    /// Metadata changes, by key
+    pub metadata: BTreeMap<String, Change<String>>,
+    /// Change of the section paragraph
+    pub content: Option<Change<String>>,
+    /// Link changes
+    pub links: Vec<Change<MMLLink>>,
+    /// Image changes
+    pub images: Vec<Change<MMLImage>>,
+    /// Code block changes
+    pub code: Vec<Change<MMLCode>>,
+    /// Quote changes
+    pub quotes: Vec<Change<MMLQuote>>,
+}
+
+/// Differences between two documents, as returned by [`diff`]
+///
+/// `Display` renders a human-readable summary using MML lines prefixed with
+/// `+`, `-` or `~`; the type also serializes to JSON with serde.
+///
+/// # Example
+///
+/// ```rust
+/// use mml_parser::{diff, parse_mml};
+///
+/// let old = parse_mml("T:Sitrep\nM:Statut|En cours\nH:Situation\nP:Incendie").unwrap();
+/// let new = parse_mml("T:Sitrep\nM:Statut|Maîtrisé\nH:Situation\nP:Incendie").unwrap();
+///
+/// let changes = diff(&old, &new);
+/// assert_eq!(changes.to_string(), "~ M:Statut|En cours -> Maîtrisé\n");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DocumentDiff {
+    /// Title change
+    pub title: Option<Change<String>>,
+    /// Global metadata changes, by key
+    pub metadata: BTreeMap<String, Change<String>>,
+    /// Paragraphs added or removed before the first section
+    pub content: Vec<Change<String>>,
+    /// Global link changes
+    pub links: Vec<Change<MMLLink>>,
+    /// Images changes before the first section
+    pub images: Vec<Change<MMLImage>>,
+    /// Code block changes before the first section
+    pub code: Vec<Change<MMLCode>>,
+    /// Quote changes before the first section
+    pub quotes: Vec<Change<MMLQuote>>,
+    /// Section changes, in the new document's order, removed sections last
+    pub sections: Vec<SectionDiff>,
+}
+
+impl DocumentDiff {
+    /// True when both documents have the same structure
+    pub fn is_empty(&self) -> bool {
+        self.title.is_none()
+            && self.metadata.is_empty()
+            && self.content.is_empty()
+            && self.links.is_empty()
+            && self.images.is_empty()
+            && self.code.is_empty()
+            && self.quotes.is_empty()
+            && self.sections.is_empty()
+    }
+}
+
+/// Compare two documents
+pub fn diff(old: &MMLDocument, new: &MMLDocument) -> DocumentDiff {
+    DocumentDiff {
+        title: diff_option(&old.title, &new.title),
+        metadata: diff_metadata(&old.metadata, &new.metadata),
+        content: diff_matched(&old.content, &new.content, |p| p.as_str()),
+        links: diff_matched(&old.links, &new.links, |l| l.text.as_str()),
+        images: diff_matched(&old.images, &new.images, |i| i.description.as_str()),
+        code: diff_matched(&old.code, &new.code, code_key),
+        quotes: diff_matched(&old.quotes, &new.quotes, quote_key),
+        sections: diff_sections(&old.sections, &new.sections),
+    }
+}
+
+pub(crate) fn diff_option(old: &Option<String>, new: &Option<String>) -> Option<Change<String>> {
+    match (old, new) {
+        (None, Some(value)) => Some(Change::Added(value.clone())),
+        (Some(value), None) => Some(Change::Removed(value.clone())),
+        (Some(old), Some(new)) if old != new => Some(Change::Modified {
+            old: old.clone(),
+            new: new.clone(),
+        }),
+        _ => None,
+    }
+}
+
+pub(crate) fn diff_metadata(
+    old: &HashMap<String, String>,
+    new: &HashMap<String, String>,
+) -> BTreeMap<String, Change<String>> {
+    let mut changes = BTreeMap::new();
+    for (key, old_value) in old {
+        match new.get(key) {
+            None => {
+                changes.insert(key.clone(), Change::Removed(old_value.clone()));
+            }
+            Some(new_value) if new_value != old_value => {
+                changes.insert(
+                    key.clone(),
+                    Change::Modified {
+                        old: old_value.clone(),
+                        new: new_value.clone(),
+                    },
+                );
+            }
+            Some(_) => {}
+        }
+    }
+    for (key, new_value) in new {
+        if !old.contains_key(key) {
+            changes.insert(key.clone(), Change::Added(new_value.clone()));
+        }
+    }
+    changes
+}
+
+/// Pair up the items of `new` with those of `old` sharing the same key
+///
+/// Returns, for each item of `new`, the index of its match in `old`, and
+/// for each item of `old`, whether it was matched.
+pub(crate) fn match_by_key<T>(old: &[T], new: &[T], key: impl Fn(&T) -> &str) -> (Vec<Option<usize>>, Vec<bool>) {
+    let mut unmatched: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (i, item) in old.iter().enumerate().rev() {
+        unmatched.entry(key(item)).or_default().push(i);
+    }
+
+    let mut used = vec![false; old.len()];
+    let matches = new
+        .iter()
+        .map(|item| {
+            let index = unmatched.get_mut(key(item)).and_then(|indices| indices.pop());
+            if let Some(i) = index {
+                used[i] = true;
+            }
+            index
+        })
+        .collect();
+    (matches, used)
+}
+
+pub(crate) fn diff_matched<T: Clone + PartialEq>(old: &[T], new: &[T], key: impl Fn(&T) -> &str) -> Vec<Change<T>> {
+    let (matches, used) = match_by_key(old, new, key);
+    let mut changes = Vec::new();
+
+    for (item, matched) in new.iter().zip(matches) {
+        match matched {
+            None => changes.push(Change::Added(item.clone())),
+            Some(i) if old[i] != *item => changes.push(Change::Modified {
+                old: old[i].clone(),
+                new: item.clone(),
+            }),
+            Some(_) => {}
+        }
+    }
+    for (item, used) in old.iter().zip(used) {
+        if !used {
+            changes.push(Change::Removed(item.clone()));
+        }
+    }
+    changes
+}
+
+fn diff_sections(old: &[MMLSection], new: &[MMLSection]) -> Vec<SectionDiff> {
+    let (matches, used) = match_by_key(old, new, |s| s.title.as_str());
+    let mut sections = Vec::new();
+
+    for (section, matched) in new.iter().zip(matches) {
+        match matched {
+            None => sections.push(SectionDiff::bare(section, ChangeKind::Added)),
+            Some(i) => {
+                let changes = diff_section(&old[i], section);
+                if !changes.is_unchanged() {
+                    sections.push(changes);
+                }
+            }
+        }
+    }
+    for (section, used) in old.iter().zip(used) {
+        if !used {
+            sections.push(SectionDiff::bare(section, ChangeKind::Removed));
+        }
+    }
+    sections
+}
+
+fn diff_section(old: &MMLSection, new: &MMLSection) -> SectionDiff {
+    SectionDiff {
+        title: new.title.clone(),
+        kind: ChangeKind::Modified,
+        metadata: diff_metadata(&old.metadata, &new.metadata),
+        content: diff_option(&old.content, &new.content),
+        links: diff_matched(&old.links, &new.links, |l| l.text.as_str()),
+        images: diff_matched(&old.images, &new.images, |i| i.description.as_str()),
+        code: diff_matched(&old.code, &new.code, code_key),
+        quotes: diff_matched(&old.quotes, &new.quotes, quote_key),
+    }
+}
+
+fn code_key(code: &MMLCode) -> &str {
+    code.language.as_deref().unwrap_or_default()
+}
+
+fn quote_key(quote: &MMLQuote) -> &str {
+    quote.attribution.as_deref().unwrap_or_default()
+}
+
+impl SectionDiff {
+    fn bare(section: &MMLSection, kind: ChangeKind) -> Self {
+        Self {
+            title: section.title.clone(),
+            kind,
+            metadata: BTreeMap::new(),
+            content: None,
+            links: Vec::new(),
+            images: Vec::new(),
+            code: Vec::new(),
+            quotes: Vec::new(),
+        }
+    }
+
+    fn is_unchanged(&self) -> bool {
+        self.kind == ChangeKind::Modified
+            && self.metadata.is_empty()
+            && self.content.is_none()
+            && self.links.is_empty()
+            && self.images.is_empty()
+            && self.code.is_empty()
+            && self.quotes.is_empty()
+    }
+}
+
+impl fmt::Display for DocumentDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(change) = &self.title {
+            write_change(f, "", "T", change, |title| title.clone())?;
+        }
+        write_body(f, "", &self.metadata, &self.content, &self.links, &self.images)?;
+        write_nodes(f, "", &self.code, &self.quotes)?;
+
+        for section in &self.sections {
+            writeln!(f, "{} H:{}", section.kind.marker(), section.title)?;
+            if let Some(change) = &section.content {
+                write_change(f, "  ", "P", change, |text| text.clone())?;
+            }
+            write_body(f, "  ", &section.metadata, &[], &section.links, &section.images)?;
+            write_nodes(f, "  ", &section.code, &section.quotes)?;
+        }
+        Ok(())
+    }
+}
+
+fn write_body(
+    f: &mut fmt::Formatter<'_>,
+    indent: &str,
+    metadata: &BTreeMap<String, Change<String>>,
+    content: &[Change<String>],
+    links: &[Change<MMLLink>],
+    images: &[Change<MMLImage>],
+) -> fmt::Result {
+    for (key, change) in metadata {
+        let key = escape_field(key);
+        match change {
+            Change::Modified { old, new } => {
+                writeln!(f, "{}~ M:{}|{} -> {}", indent, key, escape_field(old), escape_field(new))?
+            }
+            Change::Added(value) | Change::Removed(value) => {
+                writeln!(f, "{}{} M:{}|{}", indent, change.kind().marker(), key, escape_field(value))?
+            }
+        }
+    }
+    for change in content {
+        write_change(f, indent, "P", change, |text| text.clone())?;
+    }
+    for change in links {
+        write_change(f, indent, "L", change, |l| format!("{}|{}", escape_field(&l.text), escape_field(&l.url)))?;
+    }
+    for change in images {
+        write_change(f, indent, "IMG", change, |i| {
+            format!("{}|{}", escape_field(&i.description), escape_field(&i.url))
+        })?;
+    }
+    Ok(())
+}
+
+/// Code blocks and quotes, which may span several `C:` lines
+fn write_nodes(
+    f: &mut fmt::Formatter<'_>,
+    indent: &str,
+    code: &[Change<MMLCode>],
+    quotes: &[Change<MMLQuote>],
+) -> fmt::Result {
+    for change in code {
+        write_node_change(f, indent, change, MMLNode::Code)?;
+    }
+    for change in quotes {
+        write_node_change(f, indent, change, MMLNode::Quote)?;
+    }
+    Ok(())
+}
+
+fn write_node_change<'a, T>(
+    f: &mut fmt::Formatter<'_>,
+    indent: &str,
+    change: &'a Change<T>,
+    node: impl Fn(&'a T) -> MMLNode<'a>,
+) -> fmt::Result {
+    let mut write = |marker: char, value: &'a T| {
+        for line in node(value).to_string().lines() {
+            writeln!(f, "{}{} {}", indent, marker, line)?;
+        }
+        Ok(())
+    };
+    match change {
+        Change::Modified { old, new } => {
+            write('-', old)?;
+            write('+', new)
+        }
+        Change::Added(value) | Change::Removed(value) => write(change.kind().marker(), value),
+    }
+}
+
+fn write_change<T>(
+    f: &mut fmt::Formatter<'_>,
+    indent: &str,
+    tag: &str,
+    change: &Change<T>,
+    line: impl Fn(&T) -> String,
+) -> fmt::Result {
+    match change {
+        Change::Modified { old, new } => {
+            writeln!(f, "{}- {}:{}", indent, tag, line(old))?;
+            writeln!(f, "{}+ {}:{}", indent, tag, line(new))
+        }
+        Change::Added(value) | Change::Removed(value) => {
+            writeln!(f, "{}{} {}:{}", indent, change.kind().marker(), tag, line(value))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_mml;
+
+    const OLD: &str = "T:Sitrep\nM:Statut|En cours\nM:Équipe|Alpha\nP:Incendie signalé\n\
+                       H:Situation actuelle\nM:Surface|450 hectares\nP:Feu actif\n\
+                       L:Carte|cartes/v1.png\nH:Ressources\nP:3 camions";
+    const NEW: &str = "T:Sitrep\nM:Statut|Maîtrisé\nM:Bilan|Aucun blessé\nP:Incendie signalé\n\
+                       H:Situation actuelle\nM:Surface|600 hectares\nP:Feu actif\n\
+                       L:Carte|cartes/v2.png\nIMG:Photo|photo.jpg\nH:Renforts\nP:Canadairs";
+
+    #[test]
+    fn test_identical_documents() {
+        let doc = parse_mml(OLD).unwrap();
+        let changes = diff(&doc, &doc);
+        assert!(changes.is_empty());
+        assert_eq!(changes.to_string(), "");
+    }
+
+    #[test]
+    fn test_structural_changes() {
+        let changes = diff(&parse_mml(OLD).unwrap(), &parse_mml(NEW).unwrap());
+
+        assert_eq!(changes.title, None);
+        assert_eq!(changes.metadata.get("Bilan"), Some(&Change::Added("Aucun blessé".to_string())));
+        assert_eq!(changes.metadata.get("Équipe"), Some(&Change::Removed("Alpha".to_string())));
+        assert_eq!(changes.metadata["Statut"].kind(), ChangeKind::Modified);
+        assert!(changes.content.is_empty());
+
+        let kinds: Vec<_> = changes.sections.iter().map(|s| (s.title.as_str(), s.kind)).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ("Situation actuelle", ChangeKind::Modified),
+                ("Renforts", ChangeKind::Added),
+                ("Ressources", ChangeKind::Removed),
+            ]
+        );
+
+        let situation = &changes.sections[0];
+        assert_eq!(situation.content, None);
+        assert_eq!(situation.links.len(), 1);
+        assert_eq!(situation.links[0].kind(), ChangeKind::Modified);
+        assert_eq!(situation.images[0].kind(), ChangeKind::Added);
+    }
+
+    #[test]
+    fn test_text_rendering() {
+        let changes = diff(&parse_mml(OLD).unwrap(), &parse_mml(NEW).unwrap());
+
+        assert_eq!(
+            changes.to_string(),
+            "+ M:Bilan|Aucun blessé\n\
+             ~ M:Statut|En cours -> Maîtrisé\n\
+             - M:Équipe|Alpha\n\
+             ~ H:Situation actuelle\n\
+             \x20 ~ M:Surface|450 hectares -> 600 hectares\n\
+             \x20 - L:Carte|cartes/v1.png\n\
+             \x20 + L:Carte|cartes/v2.png\n\
+             \x20 + IMG:Photo|photo.jpg\n\
+             + H:Renforts\n\
+             - H:Ressources\n"
+        );
+    }
+
+    #[test]
+    fn test_json_rendering() {
+        let changes = diff(&parse_mml(OLD).unwrap(), &parse_mml(NEW).unwrap());
+        let json = serde_json::to_value(&changes).unwrap();
+
+        assert_eq!(json["metadata"]["Statut"]["change"], "modified");
+        assert_eq!(json["metadata"]["Statut"]["value"]["new"], "Maîtrisé");
+        assert_eq!(json["sections"][1]["kind"], "added");
+
+        let back: DocumentDiff = serde_json::from_value(json).unwrap();
+        assert_eq!(back, changes);
+    }
+
+    #[test]
+    fn test_code_and_quote_changes() {
+        let changes = diff(
+            &parse_mml("H:A\nQ:one\nC:x").unwrap(),
+            &parse_mml("H:A\nQ:two\nC:bash|y\nC:z").unwrap(),
+        );
+        assert!(!changes.is_empty());
+
+        let section = &changes.sections[0];
+        assert_eq!(section.quotes[0].kind(), ChangeKind::Modified);
+        let kinds: Vec<_> = section.code.iter().map(Change::kind).collect();
+        assert_eq!(kinds, vec![ChangeKind::Added, ChangeKind::Removed]);
+
+        assert_eq!(
+            changes.to_string(),
+            "~ H:A\n\
+             \x20 + C:bash|y\n\
+             \x20 + C:z\n\
+             \x20 - C:x\n\
+             \x20 - Q:one\n\
+             \x20 + Q:two\n"
+        );
+        let json = serde_json::to_value(&changes).unwrap();
+        assert_eq!(json["sections"][0]["quotes"][0]["value"]["new"]["content"], "two");
+
+        let document = parse_mml("C:x").unwrap();
+        assert_eq!(diff(&document, &parse_mml("C:y").unwrap()).code.len(), 1);
+    }
+
+    #[test]
+    fn test_repeated_titles_match_in_order() {
+        let old = parse_mml("H:Note\nP:A\nH:Note\nP:B").unwrap();
+        let new = parse_mml("H:Note\nP:A\nH:Note\nP:C\nH:Note\nP:D").unwrap();
+        let changes = diff(&old, &new);
+
+        assert_eq!(changes.sections.len(), 2);
+        assert_eq!(
+            changes.sections[0].content,
+            Some(Change::Modified { old: "B".to_string(), new: "C".to_string() })
+        );
+        assert_eq!(changes.sections[1].kind, ChangeKind::Added);
+    }
+}
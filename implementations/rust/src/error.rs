@@ -88,6 +88,18 @@ pub enum MMLError {
         suggestion: String,
     },
 
+    /// Renderer whose output could not be written
+    RenderError {
+        operation: String,
+        error: String,
+    },
+
+    /// Patch operation that does not match the document it is applied to
+    PatchConflict {
+        operation: String,
+        reason: String,
+    },
+
     /// Generic parsing error
     ParseError {
         message: String,
@@ -133,6 +145,12 @@ impl fmt::Display for MMLError {
                 write!(f, "Validation error for field '{}', value '{}': {}. {}",
                        field, value, reason, suggestion)
             }
+            MMLError::RenderError { operation, error } => {
+                write!(f, "Render error while {}: {}", operation, error)
+            }
+            MMLError::PatchConflict { operation, reason } => {
+                write!(f, "Patch conflict on '{}': {}", operation, reason)
+            }
             MMLError::ParseError { message, context } => {
                 write!(f, "Parse error: {} (context: {})", message, context)
             }
@@ -151,13 +169,13 @@ impl From<ParseIntError> for MMLError {
     }
 }
 
-#[cfg(feature = "std")]
-impl From<std::io::Error> for MMLError {
-    fn from(err: std::io::Error) -> Self {
-        MMLError::IoError {
-            operation: "file operation".to_string(),
-            path: "unknown".to_string(),
-            error: err.to_string(),
+/// `fmt::Error` carries no detail: the sink a renderer writes to refused
+/// the text
+impl From<std::fmt::Error> for MMLError {
+    fn from(_: std::fmt::Error) -> Self {
+        MMLError::RenderError {
+            operation: "formatting the output".to_string(),
+            error: "the output sink refused the text".to_string(),
         }
     }
 }
@@ -188,6 +206,7 @@ impl MMLError {
             MMLError::StructureError { .. } => false,
             MMLError::LimitExceeded { .. } => false,
             MMLError::IoError { .. } => false,
+            MMLError::RenderError { .. } => false,
             MMLError::EncodingError { .. } => false,
             _ => true,
         }
@@ -200,6 +219,7 @@ impl MMLError {
             MMLError::StructureError { .. } => ErrorSeverity::High,
             MMLError::LimitExceeded { .. } => ErrorSeverity::High,
             MMLError::IoError { .. } => ErrorSeverity::High,
+            MMLError::RenderError { .. } => ErrorSeverity::High,
             MMLError::EncodingError { .. } => ErrorSeverity::Medium,
             MMLError::UnknownTag { .. } => ErrorSeverity::Low,
             _ => ErrorSeverity::Medium,
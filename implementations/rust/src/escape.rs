@@ -0,0 +1,83 @@
+//! Field escaping
+//!
+//! `M:`, `L:`, `IMG:` and `Q:` lines separate their fields with `|`. A literal
+//! `|` inside a field is written `\|` (chapter 3.6); any other backslash is
+//! kept as is. Code lines are never escaped.
+
+/// Field separator inside `M:`, `L:`, `IMG:` and `Q:` lines
+pub const FIELD_SEPARATOR: char = '|';
+
+/// Escape a field so it can be written between separators
+///
+/// # Example
+///
+/// ```rust
+/// use mml_parser::escape::escape_field;
+///
+/// assert_eq!(escape_field("A|B"), "A\\|B");
+/// ```
+pub fn escape_field(field: &str) -> String {
+    field.replace(FIELD_SEPARATOR, "\\|")
+}
+
+/// Split line content on unescaped separators, unescaping each field
+///
+/// # Example
+///
+/// ```rust
+/// use mml_parser::escape::split_fields;
+///
+/// assert_eq!(split_fields("Description|Texte avec \\| caractère"),
+///            vec!["Description", "Texte avec | caractère"]);
+/// ```
+pub fn split_fields(content: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut current = String::new();
+    let mut chars = content.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&FIELD_SEPARATOR) => {
+                current.push(FIELD_SEPARATOR);
+                chars.next();
+            }
+            FIELD_SEPARATOR => fields.push(std::mem::take(&mut current)),
+            _ => current.push(c),
+        }
+    }
+    fields.push(current);
+
+    fields
+}
+
+/// Check that a field survives a write/read cycle when followed by a separator
+///
+/// A trailing backslash would turn the separator into an escaped `|`.
+pub fn is_safe_leading_field(field: &str) -> bool {
+    !field.ends_with('\\')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape_round_trip() {
+        let fields = ["a|b", "C:\\chemin", "||"];
+        let line = fields.iter().map(|f| escape_field(f)).collect::<Vec<_>>().join("|");
+        assert_eq!(split_fields(&line), fields);
+    }
+
+    #[test]
+    fn test_split_without_separator() {
+        assert_eq!(split_fields("texte"), vec!["texte"]);
+        assert_eq!(split_fields(""), vec![""]);
+        assert_eq!(split_fields("a|"), vec!["a", ""]);
+    }
+
+    #[test]
+    fn test_trailing_backslash() {
+        assert!(is_safe_leading_field("clé"));
+        assert!(!is_safe_leading_field("clé\\"));
+    }
+}
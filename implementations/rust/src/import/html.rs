@@ -0,0 +1,699 @@
+//! HTML to MML (chapitre 5.6)
+//!
+//! A small tokenizer reads the page; no DOM is built and malformed markup
+//! is tolerated. `<title>` gives `T:`, or the first `<h1>` when there is
+//! none; other headings give `H:`, `<h3>` carrying `M:Niveau|2`. Scripts and
+//! styles are stripped silently; embedded media, forms and tables are
+//! dropped and reported.
+
+use super::{decode_entity, Conversion, DocumentSink, LossKind};
+use crate::types::*;
+
+/// Elements whose content is never read
+const SILENT: &[&str] = &["script", "style", "noscript", "template"];
+
+/// Elements dropped with their content, and reported
+const DROPPED: &[&str] = &["svg", "iframe", "video", "audio", "canvas", "object", "select", "math"];
+
+/// Elements that end the current paragraph
+const BLOCKS: &[&str] = &[
+    "p",
+    "div",
+    "section",
+    "article",
+    "header",
+    "footer",
+    "main",
+    "aside",
+    "nav",
+    "li",
+    "dl",
+    "dt",
+    "dd",
+    "figure",
+    "figcaption",
+    "form",
+    "body",
+    "address",
+    "details",
+    "summary",
+];
+
+/// Convert an HTML page
+pub fn from_html(input: &str) -> Conversion {
+    let mut converter = Converter::default();
+    for (token, line) in Tokenizer::new(input) {
+        converter.token(token, line);
+    }
+    converter.flush(0);
+    converter.sink.finish()
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Start {
+        name: String,
+        attributes: Vec<(String, String)>,
+    },
+    End {
+        name: String,
+    },
+    Text(String),
+}
+
+/// Splits a page into tags and text, with the line each token starts on
+struct Tokenizer<'a> {
+    input: &'a str,
+    /// ASCII-lowercased copy, same byte offsets
+    lower: String,
+    position: usize,
+    line: usize,
+    /// Raw text element being read (`script`, `style`, `title`, `textarea`)
+    raw: Option<String>,
+}
+
+impl<'a> Tokenizer<'a> {
+    fn new(input: &'a str) -> Self {
+        Self {
+            input,
+            lower: input.to_ascii_lowercase(),
+            position: 0,
+            line: 1,
+            raw: None,
+        }
+    }
+
+    fn advance(&mut self, to: usize) -> &'a str {
+        let consumed = &self.input[self.position..to];
+        self.line += consumed.matches('\n').count();
+        self.position = to;
+        consumed
+    }
+
+    fn skip_past(&mut self, pattern: &str) {
+        let end = self.input[self.position..]
+            .find(pattern)
+            .map_or(self.input.len(), |offset| self.position + offset + pattern.len());
+        self.advance(end);
+    }
+
+    fn start_tag(&mut self) -> Token {
+        let rest = &self.input[self.position + 1..];
+        let name_length = rest
+            .find(|c: char| c.is_ascii_whitespace() || c == '>' || c == '/')
+            .unwrap_or(rest.len());
+        let name = rest[..name_length].to_ascii_lowercase();
+        self.advance(self.position + 1 + name_length);
+
+        let mut attributes = Vec::new();
+        loop {
+            let rest = &self.input[self.position..];
+            let trimmed = rest.trim_start_matches(|c: char| c.is_ascii_whitespace() || c == '/');
+            self.advance(self.position + rest.len() - trimmed.len());
+            if trimmed.is_empty() {
+                break;
+            }
+            if trimmed.starts_with('>') {
+                self.advance(self.position + 1);
+                break;
+            }
+
+            let length = trimmed
+                .find(|c: char| c.is_ascii_whitespace() || c == '=' || c == '>' || c == '/')
+                .unwrap_or(trimmed.len())
+                .max(1);
+            let key = trimmed[..length].to_ascii_lowercase();
+            self.advance(self.position + length);
+
+            let rest = &self.input[self.position..];
+            let after = rest.trim_start();
+            let mut value = String::new();
+            if let Some(after_equals) = after.strip_prefix('=') {
+                let unquoted = after_equals.trim_start();
+                let start = self.position + rest.len() - unquoted.len();
+                let (raw, end) = match unquoted.chars().next() {
+                    Some(quote @ ('"' | '\'')) => {
+                        let close = unquoted[1..].find(quote).map_or(unquoted.len(), |i| i + 1);
+                        (&unquoted[1..close], start + (close + 1).min(unquoted.len()))
+                    }
+                    _ => {
+                        let close = unquoted
+                            .find(|c: char| c.is_ascii_whitespace() || c == '>')
+                            .unwrap_or(unquoted.len());
+                        (&unquoted[..close], start + close)
+                    }
+                };
+                value = decode_text(raw);
+                self.advance(end);
+            }
+            attributes.push((key, value));
+        }
+
+        if matches!(name.as_str(), "script" | "style" | "title" | "textarea") {
+            self.raw = Some(name.clone());
+        }
+        Token::Start { name, attributes }
+    }
+}
+
+impl Iterator for Tokenizer<'_> {
+    type Item = (Token, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.position >= self.input.len() {
+                return None;
+            }
+            let line = self.line;
+
+            if let Some(name) = self.raw.take() {
+                let close = format!("</{}", name);
+                let end = self.lower[self.position..]
+                    .find(&close)
+                    .map_or(self.input.len(), |offset| self.position + offset);
+                let text = self.advance(end);
+                if text.is_empty() {
+                    continue;
+                }
+                let text = if name == "title" || name == "textarea" {
+                    decode_text(text)
+                } else {
+                    text.to_string()
+                };
+                return Some((Token::Text(text), line));
+            }
+
+            let rest = &self.input[self.position..];
+            if rest.starts_with("<!--") {
+                self.skip_past("-->");
+            } else if rest.starts_with("<!") || rest.starts_with("<?") {
+                self.skip_past(">");
+            } else if rest.starts_with("</") && rest[2..].starts_with(|c: char| c.is_ascii_alphabetic()) {
+                let length = rest[2..]
+                    .find(|c: char| c.is_ascii_whitespace() || c == '>')
+                    .unwrap_or(rest.len() - 2);
+                let name = rest[2..2 + length].to_ascii_lowercase();
+                self.skip_past(">");
+                return Some((Token::End { name }, line));
+            } else if rest.starts_with('<') && rest[1..].starts_with(|c: char| c.is_ascii_alphabetic()) {
+                return Some((self.start_tag(), line));
+            } else {
+                let first = rest.chars().next().map_or(1, char::len_utf8);
+                let end = rest[first..]
+                    .find('<')
+                    .map_or(self.input.len(), |offset| self.position + first + offset);
+                let text = self.advance(end);
+                return Some((Token::Text(decode_text(text)), line));
+            }
+        }
+    }
+}
+
+/// Replace character references
+fn decode_text(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(index) = rest.find('&') {
+        decoded.push_str(&rest[..index]);
+        rest = &rest[index..];
+        match decode_entity(rest) {
+            Some((c, length)) => {
+                decoded.push(c);
+                rest = &rest[length..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+/// Element whose text is being collected
+#[derive(Default)]
+enum Capture {
+    #[default]
+    None,
+    Title,
+    Heading(usize),
+    Code(Option<String>),
+    Quote,
+}
+
+#[derive(Default)]
+struct Converter {
+    sink: DocumentSink,
+    capture: Capture,
+    /// Text of the current paragraph, heading, title, code or quote
+    text: String,
+    line: usize,
+    in_head: bool,
+    /// Skipped element and its nesting depth
+    skip: Option<(String, usize)>,
+    link: Option<(String, usize)>,
+    /// Links and images of the current block, added once it is written
+    links: Vec<MMLLink>,
+    images: Vec<MMLImage>,
+    /// Text of the links of the current block
+    link_text: String,
+    emphasis: bool,
+    inline_code: bool,
+    list_depth: usize,
+    list_items: Vec<String>,
+    list_line: usize,
+    table_depth: usize,
+    table_rows: usize,
+    table_line: usize,
+    quote_depth: usize,
+    attribution: Option<String>,
+    citing: bool,
+    figure_depth: usize,
+    /// Whether the current figure holds a quote, whose caption is then its
+    /// attribution
+    figure_quote: bool,
+    /// Descriptions of the images of the current figure, not repeated from
+    /// its caption
+    figure_images: Vec<String>,
+}
+
+impl Converter {
+    fn token(&mut self, token: Token, line: usize) {
+        if let Some((name, depth)) = &mut self.skip {
+            match &token {
+                Token::Start { name: start, .. } if start == name => *depth += 1,
+                Token::End { name: end } if end == name => {
+                    *depth -= 1;
+                    if *depth == 0 {
+                        self.skip = None;
+                    }
+                }
+                _ => {}
+            }
+            return;
+        }
+
+        match token {
+            Token::Text(text) => self.text(&text, line),
+            Token::Start { name, attributes } => self.start(&name, &attributes, line),
+            Token::End { name } => self.end(&name, line),
+        }
+    }
+
+    fn text(&mut self, text: &str, line: usize) {
+        if (self.in_head && !matches!(self.capture, Capture::Title)) || self.table_depth > 0 {
+            return;
+        }
+        if self.citing {
+            self.attribution.get_or_insert_with(String::new).push_str(text);
+            return;
+        }
+        if self.line == 0 && !text.trim().is_empty() {
+            self.line = line;
+        }
+        self.text.push_str(text);
+    }
+
+    fn start(&mut self, name: &str, attributes: &[(String, String)], line: usize) {
+        let attribute = |key: &str| {
+            attributes
+                .iter()
+                .find(|(k, _)| k == key)
+                .map(|(_, value)| value.trim().to_string())
+        };
+
+        if SILENT.contains(&name) {
+            self.skip = Some((name.to_string(), 1));
+            return;
+        }
+        if DROPPED.contains(&name) {
+            self.sink.loss(LossKind::Html, line, format!("<{}> dropped", name));
+            self.skip = Some((name.to_string(), 1));
+            return;
+        }
+
+        match name {
+            "head" => self.in_head = true,
+            "title" => self.capture = Capture::Title,
+            "meta" => {
+                if let (Some(key), Some(value)) = (attribute("name"), attribute("content")) {
+                    if key != "viewport" && !value.is_empty() {
+                        self.sink.metadata(metadata_key(&key), collapse(&value));
+                    }
+                }
+            }
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" if !matches!(self.capture, Capture::Code(_)) => {
+                self.flush(line);
+                self.capture = Capture::Heading(name[1..].parse().unwrap_or(2));
+            }
+            "pre" => {
+                self.flush(line);
+                self.capture = Capture::Code(None);
+            }
+            "code" => match &mut self.capture {
+                Capture::Code(language) => {
+                    *language = attribute("class").and_then(|class| {
+                        class
+                            .split_whitespace()
+                            .find_map(|c| c.strip_prefix("language-").or_else(|| c.strip_prefix("lang-")))
+                            .map(str::to_string)
+                    })
+                }
+                _ => self.inline_code = true,
+            },
+            "blockquote" => {
+                if self.quote_depth == 0 {
+                    self.flush(line);
+                    self.capture = Capture::Quote;
+                    self.attribution = None;
+                }
+                self.quote_depth += 1;
+            }
+            "cite" | "footer" if self.quote_depth > 0 => self.citing = true,
+            "a" => {
+                if let Some(href) = attribute("href") {
+                    self.link = Some((href, self.text.len()));
+                }
+            }
+            "img" => {
+                if let Some(src) = attribute("src").filter(|src| !src.is_empty()) {
+                    let alt = collapse(&attribute("alt").unwrap_or_default());
+                    if self.figure_depth > 0 {
+                        self.figure_images.push(alt.clone());
+                    }
+                    self.images.push(MMLImage::new(alt, src));
+                }
+            }
+            "b" | "strong" | "i" | "em" | "u" | "s" | "del" | "mark" => self.emphasis = true,
+            "br" => self.text.push(' '),
+            "hr" => {
+                self.flush(line);
+                self.sink.loss(LossKind::Rule, line, "horizontal rule dropped");
+            }
+            "ul" | "ol" => {
+                self.flush(line);
+                if self.list_depth == 0 {
+                    self.list_line = line;
+                }
+                self.list_depth += 1;
+            }
+            "table" => {
+                self.flush(line);
+                if self.table_depth == 0 {
+                    self.table_line = line;
+                    self.table_rows = 0;
+                }
+                self.table_depth += 1;
+            }
+            "tr" if self.table_depth > 0 => self.table_rows += 1,
+            "figure" => {
+                self.flush(line);
+                self.figure_depth += 1;
+            }
+            "input" | "button" | "textarea" => {
+                self.sink
+                    .loss(LossKind::Html, line, format!("form control <{}> dropped", name));
+                if name == "textarea" {
+                    self.skip = Some((name.to_string(), 1));
+                }
+            }
+            _ if BLOCKS.contains(&name) => {
+                if matches!(self.capture, Capture::Quote) {
+                    self.text.push(' ');
+                } else {
+                    self.flush(line);
+                }
+                if name == "body" {
+                    self.in_head = false;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, name: &str, line: usize) {
+        match name {
+            "head" => self.in_head = false,
+            "title" => {
+                let title = collapse(&self.text);
+                if !title.is_empty() && !self.sink.has_title() {
+                    self.sink.title(title);
+                }
+                self.text.clear();
+                self.capture = Capture::None;
+            }
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                if let Capture::Heading(level) = self.capture {
+                    let text = collapse(&self.text);
+                    if level == 1 && !self.sink.has_title() && !self.sink.has_sections() {
+                        self.sink.title(text);
+                    } else if !text.is_empty() {
+                        self.sink.section(text, level.saturating_sub(1).max(1));
+                    }
+                    self.text.clear();
+                    self.capture = Capture::None;
+                    self.link_text.clear();
+                    self.emit_nodes();
+                }
+            }
+            "pre" => {
+                if let Capture::Code(language) = std::mem::take(&mut self.capture) {
+                    let content = self
+                        .text
+                        .strip_prefix('\n')
+                        .unwrap_or(&self.text)
+                        .trim_end()
+                        .to_string();
+                    if !content.is_empty() {
+                        self.sink.code(MMLCode::new(content, language), line);
+                    }
+                    self.text.clear();
+                }
+            }
+            "code" => {}
+            "blockquote" => {
+                self.quote_depth = self.quote_depth.saturating_sub(1);
+                if self.quote_depth == 0 && matches!(self.capture, Capture::Quote) {
+                    let content = collapse(&self.text);
+                    let attribution = self.attribution.take().map(|source| collapse(&source));
+                    if !content.is_empty() {
+                        self.sink.quote(MMLQuote::new(content, attribution));
+                        self.figure_quote = self.figure_depth > 0;
+                    }
+                    self.text.clear();
+                    self.capture = Capture::None;
+                    self.emit_nodes();
+                }
+            }
+            "cite" | "footer" if self.citing => self.citing = false,
+            "a" => {
+                if let Some((href, start)) = self.link.take() {
+                    let text = collapse(self.text.get(start..).unwrap_or_default());
+                    if !href.starts_with('#') && !href.to_ascii_lowercase().starts_with("javascript:") {
+                        let text = if text.is_empty() { href.clone() } else { text };
+                        self.link_text.push_str(&text);
+                        self.link_text.push(' ');
+                        self.links.push(MMLLink::new(text, href));
+                    }
+                }
+            }
+            "ul" | "ol" => {
+                self.flush(line);
+                self.list_depth = self.list_depth.saturating_sub(1);
+                if self.list_depth == 0 && !self.list_items.is_empty() {
+                    let items = std::mem::take(&mut self.list_items);
+                    let message = format!("list of {} items flattened into a paragraph", items.len());
+                    self.sink.loss(LossKind::List, self.list_line, message);
+                    let paragraph: Vec<String> = items.iter().map(|item| format!("• {}", item)).collect();
+                    self.sink.paragraph(paragraph.join(" "), self.list_line);
+                }
+            }
+            "table" => {
+                self.table_depth = self.table_depth.saturating_sub(1);
+                if self.table_depth == 0 {
+                    let message = format!("table of {} rows dropped", self.table_rows);
+                    self.sink.loss(LossKind::Table, self.table_line, message);
+                }
+            }
+            "figcaption" if self.figure_quote => {
+                let caption = collapse(&self.text);
+                let source = caption.trim_start_matches(['—', '–', '-', ' ']);
+                if !source.is_empty() {
+                    self.sink.attribute_last_quote(source.to_string());
+                }
+                self.text.clear();
+            }
+            "figcaption" => {
+                let caption = collapse(&self.text);
+                if self.figure_images.contains(&caption) {
+                    self.text.clear();
+                }
+                self.flush(line);
+            }
+            "figure" => {
+                self.flush(line);
+                self.figure_depth = self.figure_depth.saturating_sub(1);
+                self.figure_quote = false;
+                self.figure_images.clear();
+            }
+            _ if BLOCKS.contains(&name) && matches!(self.capture, Capture::None) => self.flush(line),
+            _ => {}
+        }
+    }
+
+    /// Write the text collected so far as a paragraph, or as a list item
+    fn flush(&mut self, line: usize) {
+        if !matches!(self.capture, Capture::None) {
+            return;
+        }
+        let text = collapse(&self.text);
+        let only_links = !self.links.is_empty() && text == collapse(&self.link_text);
+        let line = if self.line > 0 { self.line } else { line };
+
+        if !text.is_empty() && !only_links {
+            if self.emphasis {
+                self.sink.loss(LossKind::Emphasis, line, "emphasis kept as plain text");
+            }
+            if self.inline_code {
+                self.sink
+                    .loss(LossKind::InlineCode, line, "inline code kept as plain text");
+            }
+            if self.list_depth > 0 {
+                self.list_items.push(text);
+            } else {
+                self.sink.paragraph(text, line);
+            }
+        }
+
+        self.text.clear();
+        self.link_text.clear();
+        self.emphasis = false;
+        self.inline_code = false;
+        self.line = 0;
+        self.emit_nodes();
+    }
+
+    fn emit_nodes(&mut self) {
+        for link in std::mem::take(&mut self.links) {
+            self.sink.link(link);
+        }
+        for image in std::mem::take(&mut self.images) {
+            self.sink.image(image);
+        }
+    }
+}
+
+/// MML key of a `<meta name>`, in the French of the specification
+fn metadata_key(name: &str) -> String {
+    match name.to_ascii_lowercase().as_str() {
+        "author" => "Auteur".to_string(),
+        "date" => "Date".to_string(),
+        "description" => "Description".to_string(),
+        "keywords" => "Mots-clés".to_string(),
+        _ => name.to_string(),
+    }
+}
+
+/// Collapse runs of whitespace into single spaces
+fn collapse(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chapter_example() {
+        let conversion = from_html(
+            r#"<!DOCTYPE html>
+<html>
+<head>
+  <title>Rapport d'urgence</title>
+  <meta name="author" content="Dr. Smith">
+  <meta name="date" content="2025-11-15">
+</head>
+<body>
+  <h1>Situation actuelle</h1>
+  <p>Le secteur Alpha signale 3 blessés.</p>
+  <p>&Eacute;tat : 2 stables, 1 critique.</p>
+
+  <h2>Ressources nécessaires</h2>
+  <p>Équipe médicale supplémentaire requise.</p>
+
+  <blockquote>
+    N'oubliez pas : la rapidité sauve des vies.
+  </blockquote>
+</body>
+</html>"#,
+        );
+
+        assert_eq!(
+            conversion.to_mml(),
+            "T:Rapport d'urgence\nM:Auteur|Dr. Smith\nM:Date|2025-11-15\nH:Situation actuelle\nP:Le secteur Alpha signale 3 blessés. État : 2 stables, 1 critique.\nH:Ressources nécessaires\nP:Équipe médicale supplémentaire requise.\nQ:N'oubliez pas : la rapidité sauve des vies.\n"
+        );
+        assert_eq!(conversion.losses.len(), 1);
+        assert_eq!(conversion.losses[0].kind, LossKind::Paragraph);
+        assert_eq!(conversion.losses[0].line, 11);
+    }
+
+    #[test]
+    fn test_nodes_and_stripping() {
+        let conversion = from_html(
+            r#"<h1>Plan</h1><script>var x = "<h2>faux</h2>";</script><style>p{}</style>
+<h3>Accès</h3>
+<p>Voir <a href="https://example.org/carte">la carte</a> &amp; <b>suivre</b> le balisage.</p>
+<ul><li><a href="tel:112">Urgences</a></li></ul>
+<figure><img src="plan.png" alt="Plan du site"><figcaption>Plan du site</figcaption></figure>
+<pre><code class="language-bash">ls &lt;dir&gt;
+  echo ok
+</code></pre>
+<figure><blockquote><p>Tenir</p></blockquote><figcaption>— <cite>Chef</cite></figcaption></figure>
+<table><tr><td>1</td></tr><tr><td>2</td></tr></table>
+<iframe src="x"><p>fallback</p></iframe>"#,
+        );
+
+        assert_eq!(
+            conversion.to_mml(),
+            "T:Plan\nH:Accès\nM:Niveau|2\nP:Voir la carte & suivre le balisage.\nL:la carte|https://example.org/carte\nL:Urgences|tel:112\nIMG:Plan du site|plan.png\nC:bash|ls <dir>\nC:  echo ok\nQ:Tenir|Chef\n"
+        );
+        let kinds: Vec<LossKind> = conversion.losses.iter().map(|loss| loss.kind).collect();
+        assert_eq!(kinds, vec![LossKind::Emphasis, LossKind::Table, LossKind::Html]);
+    }
+
+    #[test]
+    fn test_consecutive_pre_blocks_stay_apart() {
+        let conversion = from_html("<title>Aide</title><h1>Script</h1><pre>ls</pre><pre><code class=\"language-sh\">pwd</code></pre>");
+        assert_eq!(conversion.document.sections[0].code.len(), 2);
+
+        let reparsed = crate::parse_mml(&conversion.to_mml()).unwrap();
+        assert_eq!(reparsed.sections, conversion.document.sections);
+    }
+
+    #[test]
+    fn test_tokenizer() {
+        let tokens: Vec<Token> = Tokenizer::new("<A HREF='x?a=1&amp;b=2' title=t>Lien</a><!-- note --><br/>")
+            .map(|(token, _)| token)
+            .collect();
+
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Start {
+                    name: "a".to_string(),
+                    attributes: vec![
+                        ("href".to_string(), "x?a=1&b=2".to_string()),
+                        ("title".to_string(), "t".to_string()),
+                    ],
+                },
+                Token::Text("Lien".to_string()),
+                Token::End { name: "a".to_string() },
+                Token::Start {
+                    name: "br".to_string(),
+                    attributes: Vec::new(),
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,751 @@
+//! Markdown to MML
+//!
+//! The first `#` heading becomes `T:`, later headings `H:`; a `###` heading
+//! carries `M:Niveau|2`, the reverse of the Markdown renderer. Links and
+//! images inside a paragraph become `L:` and `IMG:` nodes of the section,
+//! the link text staying in the paragraph. A blockquote whose last
+//! paragraph starts with `—` keeps it as the quote attribution.
+
+use super::{decode_entity, Conversion, DocumentSink, LossKind};
+use crate::types::*;
+
+/// Convert a Markdown document
+pub fn from_markdown(input: &str) -> Conversion {
+    let lines: Vec<&str> = input.lines().collect();
+    let mut sink = DocumentSink::default();
+    let mut i = 0;
+
+    if lines.first().map(|line| line.trim_end()) == Some("---") {
+        if let Some(end) = lines[1..]
+            .iter()
+            .position(|line| matches!(line.trim_end(), "---" | "..."))
+        {
+            front_matter(&mut sink, &lines[1..=end]);
+            i = end + 2;
+        }
+    }
+
+    let mut paragraph = Paragraph::default();
+
+    while i < lines.len() {
+        let line = lines[i];
+        let trimmed = line.trim();
+        let number = i + 1;
+
+        if !paragraph.lines.is_empty() && is_setext_underline(trimmed) {
+            let level = if trimmed.starts_with('=') { 1 } else { 2 };
+            let text = paragraph.lines.join(" ");
+            paragraph.lines.clear();
+            heading(&mut sink, level, &text, paragraph.line);
+            i += 1;
+            continue;
+        }
+
+        if trimmed.is_empty() {
+            paragraph.flush(&mut sink);
+            i += 1;
+            continue;
+        }
+
+        if let Some((fence, info)) = opening_fence(line) {
+            paragraph.flush(&mut sink);
+            let mut content = Vec::new();
+            i += 1;
+            while i < lines.len() && !closes_fence(lines[i], &fence) {
+                content.push(lines[i]);
+                i += 1;
+            }
+            i += 1;
+            let language = info.split_whitespace().next().map(str::to_string);
+            sink.code(MMLCode::new(content.join("\n"), language), number);
+            continue;
+        }
+
+        if paragraph.lines.is_empty() && indented_code(line).is_some() {
+            let mut content = Vec::new();
+            while i < lines.len() {
+                match indented_code(lines[i]) {
+                    Some(code) => content.push(code),
+                    None if lines[i].trim().is_empty() => content.push(""),
+                    None => break,
+                }
+                i += 1;
+            }
+            while content.last() == Some(&"") {
+                content.pop();
+            }
+            sink.code(MMLCode::new(content.join("\n"), None), number);
+            continue;
+        }
+
+        if let Some((level, text)) = atx_heading(trimmed) {
+            paragraph.flush(&mut sink);
+            heading(&mut sink, level, text, number);
+            i += 1;
+            continue;
+        }
+
+        if is_rule(trimmed) {
+            paragraph.flush(&mut sink);
+            sink.loss(LossKind::Rule, number, "horizontal rule dropped");
+            i += 1;
+            continue;
+        }
+
+        if trimmed.starts_with('>') {
+            paragraph.flush(&mut sink);
+            let start = i;
+            while i < lines.len() && lines[i].trim_start().starts_with('>') {
+                i += 1;
+            }
+            quote(&mut sink, &lines[start..i], number);
+            continue;
+        }
+
+        if trimmed.contains('|') && lines.get(i + 1).is_some_and(|next| is_table_delimiter(next.trim())) {
+            paragraph.flush(&mut sink);
+            let start = i;
+            while i < lines.len() && lines[i].contains('|') && !lines[i].trim().is_empty() {
+                i += 1;
+            }
+            let message = format!("table of {} rows dropped", i - start - 1);
+            sink.loss(LossKind::Table, number, message);
+            continue;
+        }
+
+        if list_item(trimmed).is_some() {
+            paragraph.flush(&mut sink);
+            let mut items: Vec<String> = Vec::new();
+            while i < lines.len() && !lines[i].trim().is_empty() {
+                match list_item(lines[i].trim()) {
+                    Some(item) => items.push(item.to_string()),
+                    None => {
+                        if let Some(last) = items.last_mut() {
+                            last.push(' ');
+                            last.push_str(lines[i].trim());
+                        }
+                    }
+                }
+                i += 1;
+            }
+            list(&mut sink, &items, number);
+            continue;
+        }
+
+        if is_html_block(trimmed) {
+            paragraph.flush(&mut sink);
+            while i < lines.len() && !lines[i].trim().is_empty() {
+                i += 1;
+            }
+            sink.loss(LossKind::Html, number, "HTML block dropped");
+            continue;
+        }
+
+        if paragraph.lines.is_empty() {
+            paragraph.line = number;
+        }
+        paragraph
+            .lines
+            .push(trimmed.strip_suffix('\\').unwrap_or(trimmed).trim_end());
+        i += 1;
+    }
+
+    paragraph.flush(&mut sink);
+    sink.finish()
+}
+
+/// Lines of the paragraph being read
+#[derive(Default)]
+struct Paragraph<'a> {
+    lines: Vec<&'a str>,
+    line: usize,
+}
+
+impl Paragraph<'_> {
+    fn flush(&mut self, sink: &mut DocumentSink) {
+        if self.lines.is_empty() {
+            return;
+        }
+        let text = inline(sink, &self.lines.join(" "), self.line);
+        sink.paragraph(text, self.line);
+        self.lines.clear();
+    }
+}
+
+fn heading(sink: &mut DocumentSink, level: usize, text: &str, line: usize) {
+    let text = inline_collect(sink, text, line);
+    if level == 1 && !sink.has_title() && !sink.has_sections() {
+        sink.title(text.text.clone());
+    } else {
+        sink.section(text.text.clone(), level.saturating_sub(1).max(1));
+    }
+    text.emit(sink);
+}
+
+fn front_matter(sink: &mut DocumentSink, lines: &[&str]) {
+    let mut skipping = false;
+
+    for (offset, line) in lines.iter().enumerate() {
+        let number = offset + 2;
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+        if skipping && (line.starts_with(' ') || line.starts_with('\t') || line.starts_with('-')) {
+            continue;
+        }
+        skipping = false;
+
+        match yaml_entry(line) {
+            Some((key, value)) if !value.is_empty() && !value.starts_with('[') && !value.starts_with('{') => {
+                sink.metadata(key, yaml_scalar(&value))
+            }
+            Some((key, value)) => {
+                skipping = value.is_empty();
+                sink.loss(
+                    LossKind::FrontMatter,
+                    number,
+                    format!("front-matter entry '{}' is not a plain value", key),
+                );
+            }
+            None => sink.loss(LossKind::FrontMatter, number, "front-matter line not understood"),
+        }
+    }
+}
+
+/// Split `key: value`, the key possibly quoted
+fn yaml_entry(line: &str) -> Option<(String, String)> {
+    let line = line.trim_end();
+    let (key, rest) = if line.starts_with('"') || line.starts_with('\'') {
+        let quote = line.chars().next()?;
+        let mut escaped = false;
+        let end = line[1..].char_indices().find(|&(_, c)| {
+            let found = c == quote && !escaped;
+            escaped = quote == '"' && c == '\\' && !escaped;
+            found
+        })?;
+        let end = end.0 + 2;
+        (yaml_scalar(&line[..end]), &line[end..])
+    } else {
+        let colon = line
+            .find(": ")
+            .or_else(|| line.strip_suffix(':').map(|key| key.len()))?;
+        (line[..colon].trim().to_string(), &line[colon..])
+    };
+    let value = rest.trim_start().strip_prefix(':')?.trim();
+    Some((key, value.to_string()))
+}
+
+/// Value of a plain or quoted YAML scalar
+fn yaml_scalar(value: &str) -> String {
+    if let Some(inner) = value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')) {
+        return inner.replace("''", "'");
+    }
+    let Some(inner) = value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) else {
+        return value.to_string();
+    };
+
+    let mut unquoted = String::new();
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unquoted.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => unquoted.push('\t'),
+            Some('n') => unquoted.push(' '),
+            Some('u') => {
+                let hex: String = chars.by_ref().take(4).collect();
+                unquoted.extend(u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32));
+            }
+            Some(other) => unquoted.push(other),
+            None => {}
+        }
+    }
+    unquoted
+}
+
+fn quote(sink: &mut DocumentSink, lines: &[&str], line: usize) {
+    let mut paragraphs: Vec<Vec<&str>> = vec![Vec::new()];
+    for text in lines {
+        let text = text.trim_start()[1..].trim();
+        match paragraphs.last_mut() {
+            Some(last) if text.is_empty() && !last.is_empty() => paragraphs.push(Vec::new()),
+            Some(_) if text.is_empty() => {}
+            Some(last) => last.push(text),
+            None => {}
+        }
+    }
+    let mut paragraphs: Vec<String> = paragraphs
+        .iter()
+        .filter(|p| !p.is_empty())
+        .map(|p| p.join(" "))
+        .collect();
+
+    let attribution = match paragraphs.last() {
+        Some(last) if paragraphs.len() > 1 => ["— ", "-- ", "– "]
+            .iter()
+            .find_map(|dash| last.strip_prefix(dash))
+            .map(|source| inline(sink, source.trim(), line)),
+        _ => None,
+    };
+    if attribution.is_some() {
+        paragraphs.pop();
+    }
+    if paragraphs.len() > 1 {
+        sink.loss(LossKind::Paragraph, line, "quote paragraphs joined");
+    }
+    let content = inline(sink, &paragraphs.join(" "), line);
+    sink.quote(MMLQuote::new(content, attribution));
+}
+
+/// Items that are a single link or image become `L:` or `IMG:`; the others
+/// are flattened into one paragraph
+fn list(sink: &mut DocumentSink, items: &[String], line: usize) {
+    let mut flattened = Vec::new();
+
+    for item in items {
+        let text = inline_collect(sink, item, line);
+        let image_only = text.text.is_empty() && text.images.len() == 1 && text.links.is_empty();
+        let link_only = text.links.len() == 1 && text.images.is_empty() && text.text == text.links[0].text;
+        if !image_only && !link_only {
+            flattened.push(format!("• {}", text.text));
+        }
+        text.emit(sink);
+    }
+
+    if !flattened.is_empty() {
+        let message = format!("list of {} items flattened into a paragraph", flattened.len());
+        sink.loss(LossKind::List, line, message);
+        sink.paragraph(flattened.join(" "), line);
+    }
+}
+
+/// Inline text with the links and images it contained
+struct Inline {
+    text: String,
+    links: Vec<MMLLink>,
+    images: Vec<MMLImage>,
+}
+
+impl Inline {
+    fn emit(self, sink: &mut DocumentSink) {
+        self.links.into_iter().for_each(|link| sink.link(link));
+        self.images.into_iter().for_each(|image| sink.image(image));
+    }
+}
+
+/// Plain text of inline Markdown, sending its links and images to the sink
+fn inline(sink: &mut DocumentSink, text: &str, line: usize) -> String {
+    let inline = inline_collect(sink, text, line);
+    let text = inline.text.clone();
+    inline.emit(sink);
+    text
+}
+
+/// Character of the plain text; literal ones (escaped, code) are never
+/// read as emphasis markers
+#[derive(Clone, Copy)]
+struct Char {
+    c: char,
+    literal: bool,
+}
+
+fn inline_collect(sink: &mut DocumentSink, text: &str, line: usize) -> Inline {
+    let source: Vec<char> = text.chars().collect();
+    let mut chars: Vec<Char> = Vec::new();
+    let mut links = Vec::new();
+    let mut images = Vec::new();
+    let mut code_span = false;
+    let mut i = 0;
+
+    while i < source.len() {
+        let c = source[i];
+        match c {
+            '\\' if source.get(i + 1).is_some_and(|next| next.is_ascii_punctuation()) => {
+                chars.push(Char {
+                    c: source[i + 1],
+                    literal: true,
+                });
+                i += 2;
+            }
+            '`' => {
+                let run = source[i..].iter().take_while(|&&c| c == '`').count();
+                match find_run(&source, i + run, '`', run) {
+                    Some(end) => {
+                        let code: String = source[i + run..end].iter().collect();
+                        let code = code
+                            .strip_prefix(' ')
+                            .and_then(|c| c.strip_suffix(' '))
+                            .unwrap_or(&code);
+                        chars.extend(code.chars().map(|c| Char { c, literal: true }));
+                        code_span = true;
+                        i = end + run;
+                    }
+                    None => {
+                        chars.extend(std::iter::repeat_n(Char { c, literal: true }, run));
+                        i += run;
+                    }
+                }
+            }
+            '!' | '[' => match link_at(&source, if c == '!' { i + 1 } else { i }) {
+                Some((label, url, end)) if c == '!' => {
+                    let alt = inline_collect(sink, &label, line).text;
+                    images.push(MMLImage::new(alt, url));
+                    i = end;
+                }
+                Some((label, url, end)) => {
+                    let label = inline_collect(sink, &label, line);
+                    chars.extend(label.text.chars().map(|c| Char { c, literal: true }));
+                    links.push(MMLLink::new(label.text, url));
+                    images.extend(label.images);
+                    i = end;
+                }
+                None => {
+                    chars.push(Char { c, literal: true });
+                    i += 1;
+                }
+            },
+            '<' => {
+                let end = source[i..].iter().position(|&c| c == '>').map(|end| i + end);
+                let inner: String = end.map(|end| source[i + 1..end].iter().collect()).unwrap_or_default();
+                if end.is_some() && !inner.contains(' ') && (inner.contains(':') || inner.contains('@')) {
+                    let url = if inner.contains(':') {
+                        inner.clone()
+                    } else {
+                        format!("mailto:{}", inner)
+                    };
+                    chars.extend(inner.chars().map(|c| Char { c, literal: true }));
+                    links.push(MMLLink::new(inner, url));
+                    i = end.unwrap_or(i) + 1;
+                } else if end.is_some() && inner.starts_with(|c: char| c.is_ascii_alphabetic() || c == '/') {
+                    sink.loss(LossKind::Html, line, format!("inline HTML <{}> dropped", inner));
+                    i = end.unwrap_or(i) + 1;
+                } else {
+                    chars.push(Char { c, literal: true });
+                    i += 1;
+                }
+            }
+            '&' => {
+                let rest: String = source[i..].iter().take(12).collect();
+                match decode_entity(&rest) {
+                    Some((decoded, length)) => {
+                        chars.push(Char {
+                            c: decoded,
+                            literal: true,
+                        });
+                        i += length;
+                    }
+                    None => {
+                        chars.push(Char { c, literal: true });
+                        i += 1;
+                    }
+                }
+            }
+            _ => {
+                chars.push(Char { c, literal: false });
+                i += 1;
+            }
+        }
+    }
+
+    if code_span {
+        sink.loss(LossKind::InlineCode, line, "inline code kept as plain text");
+    }
+    if strip_emphasis(&mut chars) {
+        sink.loss(LossKind::Emphasis, line, "emphasis kept as plain text");
+    }
+
+    let text: String = chars.iter().map(|c| c.c).collect();
+    Inline {
+        text: text.split_whitespace().collect::<Vec<_>>().join(" "),
+        links,
+        images,
+    }
+}
+
+/// Position of the next run of exactly `length` `c` characters
+fn find_run(source: &[char], from: usize, c: char, length: usize) -> Option<usize> {
+    let mut i = from;
+    while i < source.len() {
+        if source[i] == c {
+            let run = source[i..].iter().take_while(|&&x| x == c).count();
+            if run == length {
+                return Some(i);
+            }
+            i += run;
+        } else {
+            i += 1;
+        }
+    }
+    None
+}
+
+/// `[label](destination "title")` starting at `start`: label, URL, end
+fn link_at(source: &[char], start: usize) -> Option<(String, String, usize)> {
+    if source.get(start) != Some(&'[') {
+        return None;
+    }
+    let mut depth = 0;
+    let mut close = None;
+    let mut i = start;
+    while i < source.len() {
+        match source[i] {
+            '\\' => i += 1,
+            '[' => depth += 1,
+            ']' => {
+                depth -= 1;
+                if depth == 0 {
+                    close = Some(i);
+                    break;
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    let close = close?;
+    if source.get(close + 1) != Some(&'(') {
+        return None;
+    }
+
+    let mut depth = 0;
+    let mut end = None;
+    for (offset, &c) in source[close + 1..].iter().enumerate() {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    end = Some(close + 1 + offset);
+                    break;
+                }
+            }
+            _ => {}
+        }
+    }
+    let end = end?;
+
+    let label: String = source[start + 1..close].iter().collect();
+    let destination: String = source[close + 2..end].iter().collect();
+    let destination = destination.trim();
+    let url = match destination.strip_prefix('<') {
+        Some(rest) => rest.split('>').next().unwrap_or_default(),
+        None => destination.split_whitespace().next().unwrap_or_default(),
+    };
+    Some((label, url.to_string(), end + 1))
+}
+
+/// Remove paired `*`, `_` and `~~` delimiter runs; whether any was removed
+fn strip_emphasis(chars: &mut Vec<Char>) -> bool {
+    let mut runs = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i].c;
+        if chars[i].literal || !matches!(c, '*' | '_' | '~') {
+            i += 1;
+            continue;
+        }
+        let length = chars[i..].iter().take_while(|x| x.c == c && !x.literal).count();
+        let before = if i == 0 { ' ' } else { chars[i - 1].c };
+        let after = chars.get(i + length).map_or(' ', |x| x.c);
+        let left = !after.is_whitespace();
+        let right = !before.is_whitespace();
+        let (opens, closes) = match c {
+            '_' => (left && !before.is_alphanumeric(), right && !after.is_alphanumeric()),
+            '~' if length != 2 => (false, false),
+            _ => (left, right),
+        };
+        runs.push((i, length, c, opens, closes));
+        i += length;
+    }
+
+    let mut removed = vec![false; chars.len()];
+    let mut openers: Vec<(usize, usize, char)> = Vec::new();
+    for (start, length, c, opens, closes) in runs {
+        let matching = openers.iter().rposition(|&(_, l, oc)| oc == c && l == length);
+        match matching {
+            Some(index) if closes => {
+                let (open, _, _) = openers[index];
+                openers.truncate(index);
+                removed[open..open + length].iter_mut().for_each(|r| *r = true);
+                removed[start..start + length].iter_mut().for_each(|r| *r = true);
+            }
+            _ if opens => openers.push((start, length, c)),
+            _ => {}
+        }
+    }
+
+    let any = removed.contains(&true);
+    let mut index = 0;
+    chars.retain(|_| {
+        index += 1;
+        !removed[index - 1]
+    });
+    any
+}
+
+fn atx_heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    if !(1..=6).contains(&level) {
+        return None;
+    }
+    let rest = &line[level..];
+    if !rest.is_empty() && !rest.starts_with(' ') && !rest.starts_with('\t') {
+        return None;
+    }
+    let text = rest.trim();
+    let text = match text.trim_end_matches('#') {
+        stripped if stripped.is_empty() || stripped.ends_with(' ') => stripped.trim_end(),
+        _ => text,
+    };
+    Some((level, text))
+}
+
+fn is_setext_underline(line: &str) -> bool {
+    !line.is_empty() && (line.chars().all(|c| c == '=') || line.chars().all(|c| c == '-'))
+}
+
+fn is_rule(line: &str) -> bool {
+    let marks: String = line.chars().filter(|c| !c.is_whitespace()).collect();
+    marks.len() >= 3
+        && ["-", "*", "_"]
+            .iter()
+            .any(|mark| marks.chars().all(|c| c.to_string() == *mark))
+}
+
+fn opening_fence(line: &str) -> Option<(String, &str)> {
+    let trimmed = line.trim_start();
+    let mark = trimmed.chars().next().filter(|&c| c == '`' || c == '~')?;
+    let length = trimmed.chars().take_while(|&c| c == mark).count();
+    let info = trimmed[length..].trim();
+    if length < 3 || (mark == '`' && info.contains('`')) {
+        return None;
+    }
+    Some((trimmed[..length].to_string(), info))
+}
+
+fn closes_fence(line: &str, fence: &str) -> bool {
+    let trimmed = line.trim();
+    let mark = fence.chars().next().unwrap_or('`');
+    trimmed.len() >= fence.len() && trimmed.chars().all(|c| c == mark)
+}
+
+fn indented_code(line: &str) -> Option<&str> {
+    line.strip_prefix("    ")
+        .or_else(|| line.strip_prefix('\t'))
+        .filter(|code| !code.trim().is_empty())
+}
+
+fn is_table_delimiter(line: &str) -> bool {
+    let cells: Vec<&str> = line.trim_matches('|').split('|').map(str::trim).collect();
+    line.contains('-')
+        && cells.iter().all(|cell| {
+            let cell = cell.trim_start_matches(':').trim_end_matches(':');
+            !cell.is_empty() && cell.chars().all(|c| c == '-')
+        })
+}
+
+/// Text of a `-`, `*`, `+` or `1.` list item
+fn list_item(line: &str) -> Option<&str> {
+    if let Some(rest) = line.strip_prefix(['-', '*', '+']) {
+        return rest.strip_prefix(' ').map(str::trim);
+    }
+    let digits = line.chars().take_while(|c| c.is_ascii_digit()).count();
+    if digits == 0 || digits > 9 {
+        return None;
+    }
+    line[digits..]
+        .strip_prefix(['.', ')'])
+        .and_then(|rest| rest.strip_prefix(' '))
+        .map(str::trim)
+}
+
+fn is_html_block(line: &str) -> bool {
+    let Some(rest) = line.strip_prefix('<') else {
+        return false;
+    };
+    let is_autolink = rest
+        .split('>')
+        .next()
+        .is_some_and(|inner| !inner.contains(' ') && inner.contains(':'));
+    !is_autolink && rest.starts_with(|c: char| c.is_ascii_alphabetic() || c == '/' || c == '!')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_structure() {
+        let conversion = from_markdown(
+            "---\nAuteur: Équipe A\n\"Priorité\": \"Haute \\\"1\\\"\"\n---\n\n# Évacuation\n\nIntro du plan.\n\n## Consignes\n\nSortir par [l'escalier B](https://example.org/plan).\n\n- [Carte](carte.html)\n- ![Plan](plan.png)\n\n```bash\nalerte --zone 3\n```\n\n> Ne jamais revenir en arrière.\n>\n> — Chef de secteur\n\n### Détails\n\nRassemblement\nau parking.\n",
+        );
+
+        assert_eq!(
+            conversion.to_mml(),
+            "T:Évacuation\nM:Auteur|Équipe A\nM:Priorité|Haute \"1\"\nP:Intro du plan.\nH:Consignes\nP:Sortir par l'escalier B.\nL:l'escalier B|https://example.org/plan\nL:Carte|carte.html\nIMG:Plan|plan.png\nC:bash|alerte --zone 3\nQ:Ne jamais revenir en arrière.|Chef de secteur\nH:Détails\nM:Niveau|2\nP:Rassemblement au parking.\n"
+        );
+        assert!(conversion.is_lossless());
+    }
+
+    #[test]
+    fn test_loss_report() {
+        let conversion = from_markdown(
+            "Titre\n=====\n\nUn **point** _clé_ et `code`.\n\n| A | B |\n|---|---|\n| 1 | 2 |\n\n---\n\n- un\n- deux\n\nsnake_case et 2 * 3 restent.\n",
+        );
+        let kinds: Vec<LossKind> = conversion.losses.iter().map(|loss| loss.kind).collect();
+
+        assert_eq!(
+            kinds,
+            vec![
+                LossKind::InlineCode,
+                LossKind::Emphasis,
+                LossKind::Table,
+                LossKind::Rule,
+                LossKind::List,
+            ]
+        );
+        assert_eq!(conversion.losses[2].line, 6);
+        assert_eq!(conversion.losses[2].message, "table of 2 rows dropped");
+        assert_eq!(
+            conversion.document.content,
+            vec!["Un point clé et code.", "• un • deux", "snake_case et 2 * 3 restent."]
+        );
+        assert_eq!(conversion.document.title.as_deref(), Some("Titre"));
+    }
+
+    #[test]
+    fn test_consecutive_fences_stay_apart() {
+        let conversion = from_markdown("## Script\n\n```bash\nls\n```\n\n```\nx|y\n\nz\n```\n");
+        assert!(conversion.is_lossless());
+        assert_eq!(conversion.document.sections[0].code.len(), 2);
+
+        let reparsed = crate::parse_mml(&conversion.to_mml()).unwrap();
+        assert_eq!(reparsed.sections, conversion.document.sections);
+    }
+
+    #[test]
+    fn test_unknown_code_language() {
+        let conversion = from_markdown("## Script\n\n```Rust\nfn main() {}\n```\n\n```zig\nx|y\n```\n");
+        let code = &conversion.document.sections[0].code;
+        assert_eq!(code[0].language.as_deref(), Some("rust"));
+        assert_eq!(code[1].language, None);
+        assert_eq!(conversion.losses[0].kind, LossKind::CodeLanguage);
+        assert_eq!(conversion.losses[0].line, 7);
+
+        let reparsed = crate::parse_mml(&conversion.to_mml()).unwrap();
+        assert_eq!(reparsed.sections, conversion.document.sections);
+    }
+
+    #[test]
+    fn test_paragraphs_join_in_sections() {
+        let conversion = from_markdown("## Étape\n\nPremier.\n\nSecond \\*littéral\\*.\n\n    code indenté\n");
+        let section = &conversion.document.sections[0];
+
+        assert_eq!(section.content.as_deref(), Some("Premier. Second *littéral*."));
+        assert_eq!(section.code[0].content, "code indenté");
+        assert_eq!(conversion.losses.len(), 1);
+        assert_eq!(conversion.losses[0].kind, LossKind::Paragraph);
+    }
+}
@@ -0,0 +1,259 @@
+//! Conversion of other formats into MML
+//!
+//! Importers never fail: whatever MML cannot represent is listed in
+//! [`Conversion::losses`] with the line it came from, so that a sender can
+//! decide whether the document is still fit to go out.
+//!
+//! # Example
+//!
+//! ```rust
+//! use mml_parser::import::{from_markdown, LossKind};
+//!
+//! let conversion = from_markdown("# Procédure\n\n## Étape 1\n\nCouper le **gaz**.\n");
+//! assert_eq!(conversion.to_mml(), "T:Procédure\nH:Étape 1\nP:Couper le gaz.\n");
+//! assert_eq!(conversion.losses[0].kind, LossKind::Emphasis);
+//! ```
+
+use crate::ser::write_document;
+use crate::types::*;
+use serde::Serialize;
+use std::fmt;
+
+pub mod html;
+pub mod markdown;
+
+pub use html::from_html;
+pub use markdown::from_markdown;
+
+/// Construct that could not be carried over to MML
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LossKind {
+    /// Table, dropped
+    Table,
+    /// Bold, italic or strikethrough, kept as plain text
+    Emphasis,
+    /// Inline code, kept as plain text
+    InlineCode,
+    /// List item, kept as a paragraph line
+    List,
+    /// Further paragraph of a section, joined to the first one (a section
+    /// has a single `P:`)
+    Paragraph,
+    /// Raw HTML, dropped
+    Html,
+    /// Horizontal rule, dropped
+    Rule,
+    /// Front-matter entry that is not a plain `key: value`, dropped
+    FrontMatter,
+    /// Code language outside [`CODE_LANGUAGES`], dropped since a `C:` prefix
+    /// cannot name it
+    CodeLanguage,
+}
+
+/// One construct lost during a conversion
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Loss {
+    /// What was lost
+    pub kind: LossKind,
+    /// Line of the source (1-based)
+    pub line: usize,
+    /// Human-readable description
+    pub message: String,
+}
+
+impl fmt::Display for Loss {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+/// Result of an import
+#[derive(Debug, Clone)]
+pub struct Conversion {
+    /// Converted document
+    pub document: MMLDocument,
+    /// Constructs that MML cannot represent, in source order
+    pub losses: Vec<Loss>,
+}
+
+impl Conversion {
+    /// Write the converted document as MML
+    pub fn to_mml(&self) -> String {
+        write_document(&self.document)
+    }
+
+    /// Whether nothing was lost
+    pub fn is_lossless(&self) -> bool {
+        self.losses.is_empty()
+    }
+}
+
+/// Collects nodes into the document or its last section
+#[derive(Default)]
+pub(crate) struct DocumentSink {
+    document: MMLDocument,
+    losses: Vec<Loss>,
+}
+
+impl DocumentSink {
+    pub(crate) fn loss(&mut self, kind: LossKind, line: usize, message: impl Into<String>) {
+        self.losses.push(Loss {
+            kind,
+            line,
+            message: message.into(),
+        });
+    }
+
+    pub(crate) fn has_title(&self) -> bool {
+        self.document.title.is_some()
+    }
+
+    pub(crate) fn has_sections(&self) -> bool {
+        !self.document.sections.is_empty()
+    }
+
+    pub(crate) fn title(&mut self, title: String) {
+        self.document.title = Some(title);
+    }
+
+    pub(crate) fn section(&mut self, title: String, level: usize) {
+        let mut section = MMLSection::new(title);
+        if level > 1 {
+            section.metadata.insert("Niveau".to_string(), level.to_string());
+        }
+        self.document.sections.push(section);
+    }
+
+    pub(crate) fn metadata(&mut self, key: String, value: String) {
+        match self.document.sections.last_mut() {
+            Some(section) => section.metadata.insert(key, value),
+            None => self.document.metadata.insert(key, value),
+        };
+    }
+
+    pub(crate) fn paragraph(&mut self, text: String, line: usize) {
+        if text.is_empty() {
+            return;
+        }
+        let Some(section) = self.document.sections.last_mut() else {
+            self.document.content.push(text);
+            return;
+        };
+        match &mut section.content {
+            Some(content) => {
+                content.push(' ');
+                content.push_str(&text);
+                let message = format!("paragraph joined to the previous one in section '{}'", section.title);
+                self.loss(LossKind::Paragraph, line, message);
+            }
+            None => section.content = Some(text),
+        }
+    }
+
+    pub(crate) fn link(&mut self, link: MMLLink) {
+        match self.document.sections.last_mut() {
+            Some(section) => section.links.push(link),
+            None => self.document.links.push(link),
+        }
+    }
+
+    pub(crate) fn image(&mut self, image: MMLImage) {
+        match self.document.sections.last_mut() {
+            Some(section) => section.images.push(image),
+            None => self.document.images.push(image),
+        }
+    }
+
+    pub(crate) fn code(&mut self, mut code: MMLCode, line: usize) {
+        if let Some(language) = code.language.take() {
+            if MMLCode::is_known_language(&language) {
+                code.language = Some(language.to_lowercase());
+            } else {
+                self.loss(LossKind::CodeLanguage, line, format!("code language '{}' dropped", language));
+            }
+        }
+        match self.document.sections.last_mut() {
+            Some(section) => section.code.push(code),
+            None => self.document.code.push(code),
+        }
+    }
+
+    pub(crate) fn quote(&mut self, quote: MMLQuote) {
+        match self.document.sections.last_mut() {
+            Some(section) => section.quotes.push(quote),
+            None => self.document.quotes.push(quote),
+        }
+    }
+
+    /// Set the source of the last quote written, if it has none
+    pub(crate) fn attribute_last_quote(&mut self, source: String) {
+        let quotes = match self.document.sections.last_mut() {
+            Some(section) => &mut section.quotes,
+            None => &mut self.document.quotes,
+        };
+        if let Some(quote) = quotes.last_mut().filter(|quote| quote.attribution.is_none()) {
+            quote.attribution = Some(source);
+        }
+    }
+
+    pub(crate) fn finish(self) -> Conversion {
+        Conversion {
+            document: self.document,
+            losses: self.losses,
+        }
+    }
+}
+
+/// Named character references understood by the importers
+const ENTITIES: &[(&str, char)] = &[
+    ("amp", '&'),
+    ("lt", '<'),
+    ("gt", '>'),
+    ("quot", '"'),
+    ("apos", '\''),
+    ("nbsp", '\u{a0}'),
+    ("laquo", '«'),
+    ("raquo", '»'),
+    ("ndash", '–'),
+    ("mdash", '—'),
+    ("hellip", '…'),
+    ("rsquo", '’'),
+    ("lsquo", '‘'),
+    ("rdquo", '”'),
+    ("ldquo", '“'),
+    ("euro", '€'),
+    ("deg", '°'),
+    ("copy", '©'),
+    ("agrave", 'à'),
+    ("acirc", 'â'),
+    ("ccedil", 'ç'),
+    ("eacute", 'é'),
+    ("egrave", 'è'),
+    ("ecirc", 'ê'),
+    ("euml", 'ë'),
+    ("icirc", 'î'),
+    ("iuml", 'ï'),
+    ("ocirc", 'ô'),
+    ("ugrave", 'ù'),
+    ("ucirc", 'û'),
+    ("Eacute", 'É'),
+    ("Agrave", 'À'),
+];
+
+/// Decode the character reference at the start of `text` (`&amp;`,
+/// `&#233;`, `&#xE9;`): the character and the number of chars read
+pub(crate) fn decode_entity(text: &str) -> Option<(char, usize)> {
+    let body = text.strip_prefix('&')?;
+    let end = body.find(';')?;
+    let name = &body[..end];
+    let decoded = match name.strip_prefix('#') {
+        Some(number) => match number.strip_prefix(['x', 'X']) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok(),
+            None => number.parse().ok(),
+        }
+        .and_then(char::from_u32)?,
+        None => ENTITIES.iter().find(|(entity, _)| *entity == name)?.1,
+    };
+    Some((decoded, name.chars().count() + 2))
+}
@@ -0,0 +1,604 @@
+//! JSON DOM of annexe D
+//!
+//! `serde_json::to_string(&document)` follows the Rust structs. This module
+//! writes and reads the layout of annexe D instead: `type` discriminators,
+//! section `children`, no statistics, so that every implementation exchanges
+//! the same JSON.
+//!
+//! Schema v1.0 has no place for document-level paragraphs, images, code or
+//! quotes, for quote attributions or for packets; they are left out and
+//! listed by [`omissions`], and [`to_json`] refuses them when validating.
+//! Values are never rewritten: a code language outside the schema list is
+//! written as is and reported by [`validate_json`].
+
+use crate::error::{MMLError, MMLResult};
+use crate::query::MMLNode;
+use crate::types::*;
+use crate::validation::ValidationReport;
+use regex::Regex;
+use serde_json::{json, Map, Value};
+
+/// JSON Schema of the DOM (annexe D, v1.0)
+pub const DOM_SCHEMA: &str = include_str!("../schema/mml-dom-v1.0.json");
+
+/// Options for [`to_json`]
+#[derive(Debug, Clone, Default)]
+pub struct JsonOptions {
+    /// Indent the output
+    pub pretty: bool,
+    /// Check the output against [`DOM_SCHEMA`] and fail on the first error
+    pub validate: bool,
+}
+
+/// Build the annexe D representation of a document
+///
+/// # Example
+///
+/// ```rust
+/// use mml_parser::{json::to_json_value, parse_mml};
+///
+/// let document = parse_mml("T:Rapport\nH:Situation\nP:Incendie en cours").unwrap();
+/// let value = to_json_value(&document);
+///
+/// assert_eq!(value["type"], "document");
+/// assert_eq!(value["sections"][0]["children"][0]["type"], "paragraph");
+/// ```
+pub fn to_json_value(document: &MMLDocument) -> Value {
+    let mut root = Map::new();
+    root.insert("type".to_string(), json!("document"));
+    if let Some(title) = &document.title {
+        root.insert("title".to_string(), json!(title));
+    }
+    root.insert("metadata".to_string(), metadata_value(&document.metadata));
+    root.insert(
+        "sections".to_string(),
+        Value::Array(document.sections.iter().map(section_value).collect()),
+    );
+    if !document.links.is_empty() {
+        root.insert(
+            "links".to_string(),
+            Value::Array(
+                document
+                    .links
+                    .iter()
+                    .map(|link| node_value(&MMLNode::Link(link)))
+                    .collect(),
+            ),
+        );
+    }
+    Value::Object(root)
+}
+
+/// Content of a document that [`to_json_value`] leaves out
+///
+/// Packets are transport envelopes and are not listed.
+pub fn omissions(document: &MMLDocument) -> Vec<String> {
+    let mut omitted = Vec::new();
+    for (count, name) in [
+        (document.content.len(), "paragraph"),
+        (document.images.len(), "image"),
+        (document.code.len(), "code block"),
+        (document.quotes.len(), "quote"),
+    ] {
+        if count > 0 {
+            omitted.push(format!("{} document-level {}(s) before the first section", count, name));
+        }
+    }
+    for section in &document.sections {
+        for quote in section.quotes.iter().filter(|quote| quote.attribution.is_some()) {
+            omitted.push(format!(
+                "attribution of quote '{}' in section '{}'",
+                quote.content, section.title
+            ));
+        }
+    }
+    omitted
+}
+
+/// Write a document as annexe D JSON
+///
+/// With `validate`, content the schema cannot hold (see [`omissions`]) is
+/// an error rather than silently dropped.
+pub fn to_json(document: &MMLDocument, options: &JsonOptions) -> MMLResult<String> {
+    let value = to_json_value(document);
+    if options.validate {
+        validate_json(&value).into_result()?;
+        if let Some(omission) = omissions(document).into_iter().next() {
+            return Err(MMLError::ValidationError {
+                field: "document".to_string(),
+                value: omission,
+                reason: "not representable in DOM schema v1.0".to_string(),
+                suggestion: "Move the content into a section, or convert without schema validation".to_string(),
+            });
+        }
+    }
+    let json = if options.pretty {
+        serde_json::to_string_pretty(&value)
+    } else {
+        serde_json::to_string(&value)
+    };
+    json.map_err(|e| MMLError::ParseError {
+        message: e.to_string(),
+        context: "JSON".to_string(),
+    })
+}
+
+/// Read a document from annexe D JSON
+///
+/// As with `P:` lines, a later paragraph of a section replaces an earlier one.
+pub fn from_json(input: &str) -> MMLResult<MMLDocument> {
+    let value: Value = serde_json::from_str(input).map_err(|e| MMLError::ParseError {
+        message: e.to_string(),
+        context: "JSON".to_string(),
+    })?;
+    from_json_value(&value)
+}
+
+/// Read a document from an annexe D value
+pub fn from_json_value(value: &Value) -> MMLResult<MMLDocument> {
+    let root = node_object(value, "document", "")?;
+    let mut document = MMLDocument {
+        title: optional_string(root, "title", "")?,
+        metadata: metadata_from(root, "")?,
+        ..MMLDocument::default()
+    };
+
+    for (i, section) in array(root, "sections", "")?.iter().enumerate() {
+        document
+            .sections
+            .push(section_from(section, &format!("/sections/{}", i))?);
+    }
+    for (i, link) in array(root, "links", "")?.iter().enumerate() {
+        let path = format!("/links/{}", i);
+        let link = node_object(link, "link", &path)?;
+        document
+            .links
+            .push(MMLLink::new(string(link, "text", &path)?, string(link, "url", &path)?));
+    }
+
+    Ok(document)
+}
+
+/// Check a value against [`DOM_SCHEMA`]
+///
+/// Each error names the JSON pointer of the offending value.
+///
+/// # Example
+///
+/// ```rust
+/// use mml_parser::json::validate_json;
+/// use serde_json::json;
+///
+/// let report = validate_json(&json!({"type": "document", "metadata": {}, "sections": []}));
+/// assert!(report.is_valid());
+///
+/// let report = validate_json(&json!({"type": "document", "metadata": {}, "sections": [{"type": "invalid_type"}]}));
+/// assert!(!report.is_valid());
+/// ```
+pub fn validate_json(value: &Value) -> ValidationReport {
+    lazy_static::lazy_static! {
+        static ref SCHEMA: Value = serde_json::from_str(DOM_SCHEMA).expect("embedded DOM schema is valid JSON");
+    }
+
+    let mut errors = Vec::new();
+    SchemaCheck { root: &SCHEMA }.check(&SCHEMA, value, "", &mut errors);
+
+    let mut report = ValidationReport::new();
+    for error in errors {
+        report.error(None, error);
+    }
+    report
+}
+
+fn metadata_value(metadata: &std::collections::HashMap<String, String>) -> Value {
+    let mut entries: Vec<_> = metadata.iter().collect();
+    entries.sort();
+    Value::Object(entries.into_iter().map(|(k, v)| (k.clone(), json!(v))).collect())
+}
+
+fn section_value(section: &MMLSection) -> Value {
+    let children: Vec<Value> = MMLNode::Section(section)
+        .children()
+        .iter()
+        .filter(|child| !matches!(child, MMLNode::Metadata { .. }))
+        .map(node_value)
+        .collect();
+    json!({
+        "type": "section",
+        "title": section.title,
+        "metadata": metadata_value(&section.metadata),
+        "children": children,
+    })
+}
+
+fn node_value(node: &MMLNode<'_>) -> Value {
+    match node {
+        MMLNode::Paragraph(content) => json!({"type": "paragraph", "content": content}),
+        MMLNode::Link(link) => json!({"type": "link", "text": link.text, "url": link.url}),
+        MMLNode::Image(image) => json!({"type": "image", "description": image.description, "url": image.url}),
+        MMLNode::Code(code) => match &code.language {
+            Some(language) => json!({"type": "code", "content": code.content, "language": language}),
+            None => json!({"type": "code", "content": code.content}),
+        },
+        MMLNode::Quote(quote) => json!({"type": "quote", "content": quote.content}),
+        _ => unreachable!("only section children are written as nodes"),
+    }
+}
+
+fn section_from(value: &Value, path: &str) -> MMLResult<MMLSection> {
+    let object = node_object(value, "section", path)?;
+    let mut section = MMLSection::new(string(object, "title", path)?);
+    section.metadata = metadata_from(object, path)?;
+
+    for (i, child) in array(object, "children", path)?.iter().enumerate() {
+        let path = format!("{}/children/{}", path, i);
+        let child_type = child.get("type").and_then(Value::as_str).unwrap_or_default();
+        let child = node_object(child, child_type, &path)?;
+        match child_type {
+            "paragraph" => section.content = Some(string(child, "content", &path)?),
+            "link" => section.links.push(MMLLink::new(
+                string(child, "text", &path)?,
+                string(child, "url", &path)?,
+            )),
+            "image" => section.images.push(MMLImage::new(
+                string(child, "description", &path)?,
+                string(child, "url", &path)?,
+            )),
+            "code" => section.code.push(MMLCode::new(
+                string(child, "content", &path)?,
+                optional_string(child, "language", &path)?,
+            )),
+            "quote" => section
+                .quotes
+                .push(MMLQuote::new(string(child, "content", &path)?, None)),
+            other => return Err(structure_error(&path, &format!("unknown node type '{}'", other))),
+        }
+    }
+
+    Ok(section)
+}
+
+fn node_object<'v>(value: &'v Value, expected: &str, path: &str) -> MMLResult<&'v Map<String, Value>> {
+    let object = value
+        .as_object()
+        .ok_or_else(|| structure_error(path, "expected an object"))?;
+    match object.get("type").and_then(Value::as_str) {
+        Some(found) if found == expected => Ok(object),
+        Some(found) => Err(structure_error(
+            path,
+            &format!("expected type '{}', found '{}'", expected, found),
+        )),
+        None => Err(structure_error(path, "missing 'type'")),
+    }
+}
+
+fn metadata_from(object: &Map<String, Value>, path: &str) -> MMLResult<std::collections::HashMap<String, String>> {
+    let Some(metadata) = object.get("metadata") else {
+        return Ok(Default::default());
+    };
+    let metadata = metadata
+        .as_object()
+        .ok_or_else(|| structure_error(&format!("{}/metadata", path), "expected an object"))?;
+    metadata
+        .iter()
+        .map(|(key, value)| match value.as_str() {
+            Some(value) => Ok((key.clone(), value.to_string())),
+            None => Err(structure_error(
+                &format!("{}/metadata/{}", path, key),
+                "expected a string",
+            )),
+        })
+        .collect()
+}
+
+fn array<'v>(object: &'v Map<String, Value>, key: &str, path: &str) -> MMLResult<&'v [Value]> {
+    match object.get(key) {
+        None => Ok(&[]),
+        Some(Value::Array(items)) => Ok(items),
+        Some(_) => Err(structure_error(&format!("{}/{}", path, key), "expected an array")),
+    }
+}
+
+fn string(object: &Map<String, Value>, key: &str, path: &str) -> MMLResult<String> {
+    optional_string(object, key, path)?.ok_or_else(|| structure_error(&format!("{}/{}", path, key), "missing string"))
+}
+
+fn optional_string(object: &Map<String, Value>, key: &str, path: &str) -> MMLResult<Option<String>> {
+    match object.get(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(value)) => Ok(Some(value.clone())),
+        Some(_) => Err(structure_error(&format!("{}/{}", path, key), "expected a string")),
+    }
+}
+
+fn structure_error(path: &str, message: &str) -> MMLError {
+    MMLError::StructureError {
+        message: format!("{}: {}", pointer(path), message),
+        suggestion: "Follow the DOM layout of annexe D".to_string(),
+    }
+}
+
+fn pointer(path: &str) -> &str {
+    if path.is_empty() {
+        "/"
+    } else {
+        path
+    }
+}
+
+/// Validator for the JSON Schema keywords used by [`DOM_SCHEMA`]
+struct SchemaCheck<'s> {
+    root: &'s Value,
+}
+
+impl SchemaCheck<'_> {
+    fn check(&self, schema: &Value, value: &Value, path: &str, errors: &mut Vec<MMLError>) {
+        if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+            match self.resolve(reference) {
+                Some(target) => self.check(target, value, path, errors),
+                None => errors.push(schema_error(
+                    path,
+                    value,
+                    &format!("unresolved reference {}", reference),
+                )),
+            }
+            return;
+        }
+
+        if let Some(expected) = schema.get("const") {
+            if value != expected {
+                errors.push(schema_error(path, value, &format!("expected {}", expected)));
+            }
+        }
+        if let Some(Value::Array(allowed)) = schema.get("enum") {
+            if !allowed.contains(value) {
+                errors.push(schema_error(
+                    path,
+                    value,
+                    &format!("expected one of {}", Value::Array(allowed.clone())),
+                ));
+            }
+        }
+        if let Some(expected) = schema.get("type").and_then(Value::as_str) {
+            if !has_type(value, expected) {
+                errors.push(schema_error(path, value, &format!("expected {}", expected)));
+                return;
+            }
+        }
+
+        if let Some(text) = value.as_str() {
+            if let Some(min) = schema.get("minLength").and_then(Value::as_u64) {
+                if (text.chars().count() as u64) < min {
+                    errors.push(schema_error(path, value, &format!("shorter than {} characters", min)));
+                }
+            }
+            if let Some(pattern) = schema.get("pattern").and_then(Value::as_str) {
+                if !Regex::new(pattern).is_ok_and(|re| re.is_match(text)) {
+                    errors.push(schema_error(path, value, &format!("does not match {}", pattern)));
+                }
+            }
+        }
+
+        if let Some(object) = value.as_object() {
+            self.check_object(schema, object, value, path, errors);
+        }
+
+        if let (Some(items), Some(array)) = (schema.get("items"), value.as_array()) {
+            for (i, item) in array.iter().enumerate() {
+                self.check(items, item, &format!("{}/{}", path, i), errors);
+            }
+        }
+
+        if let Some(Value::Array(branches)) = schema.get("oneOf") {
+            self.check_one_of(branches, value, path, errors);
+        }
+    }
+
+    fn check_object(
+        &self,
+        schema: &Value,
+        object: &Map<String, Value>,
+        value: &Value,
+        path: &str,
+        errors: &mut Vec<MMLError>,
+    ) {
+        let properties = schema.get("properties").and_then(Value::as_object);
+        let patterns: Vec<(Regex, &Value)> = schema
+            .get("patternProperties")
+            .and_then(Value::as_object)
+            .map(|patterns| {
+                patterns
+                    .iter()
+                    .filter_map(|(pattern, sub)| Regex::new(pattern).ok().map(|re| (re, sub)))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        if let Some(Value::Array(required)) = schema.get("required") {
+            for name in required.iter().filter_map(Value::as_str) {
+                if !object.contains_key(name) {
+                    errors.push(schema_error(
+                        path,
+                        value,
+                        &format!("missing required property '{}'", name),
+                    ));
+                }
+            }
+        }
+
+        for (key, item) in object {
+            let item_path = format!("{}/{}", path, key);
+            let mut known = false;
+            if let Some(sub) = properties.and_then(|p| p.get(key)) {
+                self.check(sub, item, &item_path, errors);
+                known = true;
+            }
+            for (re, sub) in &patterns {
+                if re.is_match(key) {
+                    self.check(sub, item, &item_path, errors);
+                    known = true;
+                }
+            }
+            if !known && schema.get("additionalProperties") == Some(&Value::Bool(false)) {
+                errors.push(schema_error(&item_path, item, "property not allowed"));
+            }
+        }
+    }
+
+    /// Exactly one branch must match; otherwise report the closest branch
+    fn check_one_of(&self, branches: &[Value], value: &Value, path: &str, errors: &mut Vec<MMLError>) {
+        let results: Vec<Vec<MMLError>> = branches
+            .iter()
+            .map(|branch| {
+                let mut branch_errors = Vec::new();
+                self.check(branch, value, path, &mut branch_errors);
+                branch_errors
+            })
+            .collect();
+
+        match results.iter().filter(|r| r.is_empty()).count() {
+            1 => {}
+            0 => match results.into_iter().min_by_key(Vec::len) {
+                Some(closest) => errors.extend(closest),
+                None => errors.push(schema_error(path, value, "no allowed node type")),
+            },
+            _ => errors.push(schema_error(path, value, "matches several node types")),
+        }
+    }
+
+    fn resolve(&self, reference: &str) -> Option<&Value> {
+        let pointer = reference.strip_prefix('#')?;
+        self.root.pointer(pointer)
+    }
+}
+
+fn has_type(value: &Value, expected: &str) -> bool {
+    match expected {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+fn schema_error(path: &str, value: &Value, reason: &str) -> MMLError {
+    MMLError::ValidationError {
+        field: pointer(path).to_string(),
+        value: value.to_string(),
+        reason: reason.to_string(),
+        suggestion: "See the DOM schema in annexe D".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_mml;
+
+    const REPORT: &str = "T:Rapport d'urgence\nM:auteur|Dr. Smith\nM:date|2025-11-15\nL:Accueil|index.mml\n\
+                          H:Situation\nM:Niveau|3\nP:Incendie en cours\nL:Carte du secteur|carte-incendie.png\n\
+                          IMG:Vue aérienne|vue.jpg\nC:python|print('ok')\nQ:Tenir la ligne|Chef";
+
+    #[test]
+    fn test_dom_layout() {
+        let mut document = parse_mml(REPORT).unwrap();
+        let value = to_json_value(&document);
+
+        assert_eq!(value["type"], "document");
+        assert_eq!(value["title"], "Rapport d'urgence");
+        assert!(value.get("stats").is_none());
+        assert_eq!(value["links"][0]["type"], "link");
+
+        let section = &value["sections"][0];
+        assert_eq!(section["type"], "section");
+        assert_eq!(section["metadata"]["Niveau"], "3");
+        let types: Vec<_> = section["children"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|c| c["type"].clone())
+            .collect();
+        assert_eq!(types, vec!["paragraph", "link", "image", "code", "quote"]);
+        assert_eq!(section["children"][3]["language"], "python");
+        assert!(section["children"][4].get("attribution").is_none());
+
+        assert!(validate_json(&value).is_valid());
+        let options = JsonOptions {
+            pretty: true,
+            validate: true,
+        };
+        assert!(to_json(&document, &options).is_err());
+        document.sections[0].quotes[0].attribution = None;
+        assert!(to_json(&document, &options).is_ok());
+    }
+
+    #[test]
+    fn test_omissions() {
+        let document = parse_mml("T:Alerte\nP:Évacuer\nIMG:Plan|plan.png\nH:Consignes\nQ:Tenir|Chef").unwrap();
+        assert_eq!(
+            omissions(&document),
+            vec![
+                "1 document-level paragraph(s) before the first section",
+                "1 document-level image(s) before the first section",
+                "attribution of quote 'Tenir' in section 'Consignes'",
+            ]
+        );
+        assert!(matches!(
+            to_json(&document, &JsonOptions { pretty: false, validate: true }),
+            Err(MMLError::ValidationError { .. })
+        ));
+        assert!(omissions(&parse_mml(REPORT.replace("|Chef", "").as_str()).unwrap()).is_empty());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut document = parse_mml(REPORT).unwrap();
+        let back = from_json(&to_json(&document, &JsonOptions::default()).unwrap()).unwrap();
+
+        document.quotes.clear();
+        document.sections[0].quotes[0].attribution = None;
+        assert_eq!(back.title, document.title);
+        assert_eq!(back.metadata, document.metadata);
+        assert_eq!(back.links, document.links);
+        assert_eq!(back.sections, document.sections);
+    }
+
+    #[test]
+    fn test_validation_errors() {
+        let document = parse_mml("T:Outil\nH:Code\nC:rust|fn main() {}").unwrap();
+        let report = validate_json(&to_json_value(&document));
+        assert_eq!(report.errors.len(), 1);
+        assert!(matches!(
+            &report.errors[0].error,
+            MMLError::ValidationError { field, .. } if field == "/sections/0/children/0/language"
+        ));
+        assert!(to_json(
+            &document,
+            &JsonOptions {
+                pretty: false,
+                validate: true
+            }
+        )
+        .is_err());
+
+        let invalid = json!({
+            "type": "document",
+            "title": "Test",
+            "metadata": {},
+            "sections": [{"type": "invalid_type", "title": "Section", "metadata": {}, "children": []}],
+            "stats": {}
+        });
+        let report = validate_json(&invalid);
+        assert_eq!(report.errors.len(), 2);
+
+        assert!(matches!(
+            from_json_value(&invalid),
+            Err(MMLError::StructureError { .. })
+        ));
+        assert!(matches!(from_json("{"), Err(MMLError::ParseError { .. })));
+    }
+}
@@ -32,13 +32,42 @@
 //! }
 //! ```
 
+pub mod builder;
+pub mod canonical;
+pub mod de;
+pub mod diff;
 pub mod error;
+pub mod escape;
+pub mod import;
+pub mod json;
+pub mod merge;
+pub mod metadata;
+pub mod morse;
 pub mod parser;
+pub mod patch;
+pub mod profile;
+pub mod query;
+pub mod render;
+pub mod ser;
 pub mod types;
+mod unicode;
+pub mod validation;
+pub mod visit;
 
+pub use builder::{MMLDocumentBuilder, SectionBuilder};
+pub use de::{from_document, from_str};
+pub use diff::{diff, Change, ChangeKind, DocumentDiff, SectionDiff};
 pub use error::{MMLError, MMLResult};
-pub use parser::MMLParser;
+pub use merge::{merge, MergeConflict, MergeResult};
+pub use metadata::{Coordinate, MetadataKind, MetadataSchema, MetadataValue, Quantity, Timestamp, TypedValue};
+pub use parser::{MMLParser, OrphanPolicy, ParserOptions};
+pub use patch::{MMLPatch, PatchAction, PatchNode, PatchOperation};
+pub use profile::{Profile, ProfileViolation, ViolationKind};
+pub use query::{MMLNode, Selector};
+pub use render::Renderer;
+pub use ser::{to_document, to_string};
 pub use types::*;
+pub use validation::{ValidationIssue, ValidationReport};
 
 /// Version of the MML specification supported
 pub const MML_VERSION: &str = "1.0";
@@ -64,16 +93,16 @@ pub fn parse_mml(input: &str) -> MMLResult<MMLDocument> {
     parser.parse(input)
 }
 
-/// Validate MML syntax without creating a document
+/// Validate an MML document (syntax, limits and structure)
 ///
 /// # Example
 ///
 /// ```rust
 /// use mml_parser::validate_mml;
 ///
-/// assert!(validate_mml("T:Valid\nP:Document").is_ok());
+/// assert!(validate_mml("T:Valid\nP:Document").is_valid());
 /// ```
-pub fn validate_mml(input: &str) -> MMLResult<()> {
+pub fn validate_mml(input: &str) -> ValidationReport {
     let parser = MMLParser::new();
     parser.validate(input)
 }
@@ -97,12 +126,12 @@ mod tests {
     #[test]
     fn test_validate_valid_document() {
         let mml = "T:Valid\nP:Document";
-        assert!(validate_mml(mml).is_ok());
+        assert!(validate_mml(mml).is_valid());
     }
 
     #[test]
     fn test_validate_invalid_document() {
         let mml = "INVALID:Document";
-        assert!(validate_mml(mml).is_err());
+        assert!(!validate_mml(mml).is_valid());
     }
 }
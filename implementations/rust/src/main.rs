@@ -1,10 +1,15 @@
 //! MML CLI - Command Line Interface for MML processing
 
 use clap::{Parser, Subcommand};
-use mml_parser::{MMLParser, OutputFormat, parse_mml};
+use mml_parser::import;
+use mml_parser::json::JsonOptions;
+use mml_parser::morse;
+use mml_parser::render::{self, HtmlOptions, HtmlRenderer, HtmlTheme, JsonRenderer, TextOptions, TextRenderer};
+use mml_parser::visit::{walk, Visit};
+use mml_parser::{MMLParser, Profile, Renderer, Selector};
 use std::fs;
 use std::io::{self, Read};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process;
 
 #[derive(Parser)]
@@ -27,6 +32,10 @@ enum Commands {
         /// Strict validation (fail on any error)
         #[arg(long)]
         strict: bool,
+
+        /// Profile (RULE: lines) the document must follow
+        #[arg(long)]
+        profile: Option<String>,
     },
 
     /// Convert MML to other formats
@@ -42,6 +51,41 @@ enum Commands {
         /// Output format
         #[arg(short, long, value_enum, default_value = "html")]
         format: OutputFormat,
+
+        /// Check JSON output against the DOM schema (annexe D)
+        #[arg(long)]
+        validate_schema: bool,
+
+        /// Embedded CSS theme (HTML)
+        #[arg(long, value_enum)]
+        theme: Option<Theme>,
+
+        /// Inline local images for offline use (HTML)
+        #[arg(long)]
+        single_file: bool,
+
+        /// Line width, 0 for no wrapping (text)
+        #[arg(long, default_value_t = 72)]
+        width: usize,
+
+        /// Transliterate to ASCII (text)
+        #[arg(long)]
+        ascii: bool,
+    },
+
+    /// Convert another format to MML, reporting what MML cannot represent
+    Import {
+        /// Input file (use - for stdin)
+        #[arg(short, long)]
+        input: Option<String>,
+
+        /// Output file (use - for stdout)
+        #[arg(short, long)]
+        output: Option<String>,
+
+        /// Input format
+        #[arg(short, long, value_enum, default_value = "markdown")]
+        from: InputFormat,
     },
 
     /// Compress MML to MMLC format
@@ -59,6 +103,16 @@ enum Commands {
         level: CompressionLevel,
     },
 
+    /// Morse code (annexe C); encodes when no action is given
+    #[command(args_conflicts_with_subcommands = true)]
+    Morse {
+        #[command(subcommand)]
+        action: Option<MorseAction>,
+
+        #[command(flatten)]
+        encode: MorseEncodeArgs,
+    },
+
     /// Analyze MML document
     Stats {
         /// Input file (use - for stdin)
@@ -69,9 +123,102 @@ enum Commands {
         #[arg(long)]
         detailed: bool,
     },
+
+    /// Select nodes with a selector such as 'H[Niveau=2] > L'
+    Query {
+        /// Selector expression
+        selector: String,
+
+        /// Input files (stdin when omitted)
+        inputs: Vec<String>,
+    },
+
+    /// Show structural changes between two documents
+    Diff {
+        /// Previous version
+        old: String,
+
+        /// New version
+        new: String,
+
+        /// Print the changes as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Three-way merge, usable as a git merge driver (exit code 1 on conflicts)
+    Merge {
+        /// Common ancestor
+        base: String,
+
+        /// Our version
+        ours: String,
+
+        /// Their version
+        theirs: String,
+
+        /// Output file (use - for stdout)
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum MorseAction {
+    /// Encode MML or MMLC as dots and dashes, or as CW audio
+    Encode(MorseEncodeArgs),
+
+    /// Decode dots and dashes, or a CW recording, back to text
+    Decode(MorseDecodeArgs),
+}
+
+#[derive(clap::Args)]
+struct MorseEncodeArgs {
+    /// Input file (use - for stdin)
+    #[arg(short, long)]
+    input: Option<String>,
+
+    /// Output file (use - for stdout)
+    #[arg(short, long, conflicts_with = "wav")]
+    output: Option<String>,
+
+    /// Letter speed in words per minute
+    #[arg(long, default_value_t = 20.0)]
+    wpm: f64,
+
+    /// Overall speed with Farnsworth spacing, below --wpm
+    #[arg(long)]
+    farnsworth: Option<f64>,
+
+    /// Write CW audio to this WAV file instead of dots and dashes
+    #[arg(long)]
+    wav: Option<PathBuf>,
+
+    /// Tone frequency in Hz (WAV)
+    #[arg(long, default_value_t = 700.0)]
+    tone: f64,
 }
 
-#[derive(clap::ValueEnum, Clone, Debug)]
+#[derive(clap::Args)]
+struct MorseDecodeArgs {
+    /// Input file (use - for stdin)
+    #[arg(short, long, conflicts_with = "wav")]
+    input: Option<String>,
+
+    /// Output file (use - for stdout)
+    #[arg(short, long)]
+    output: Option<String>,
+
+    /// Decode this CW recording (WAV) instead of dots and dashes
+    #[arg(long)]
+    wav: Option<PathBuf>,
+
+    /// Tone frequency in Hz (WAV; found automatically when omitted)
+    #[arg(long, requires = "wav")]
+    tone: Option<f64>,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
 enum CompressionLevel {
     None,
     Basic,
@@ -88,10 +235,35 @@ impl From<CompressionLevel> for mml_parser::CompressionLevel {
     }
 }
 
-#[derive(clap::ValueEnum, Clone, Debug)]
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+enum InputFormat {
+    Html,
+    Markdown,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+enum Theme {
+    Light,
+    Dark,
+    Print,
+}
+
+impl From<Theme> for HtmlTheme {
+    fn from(theme: Theme) -> Self {
+        match theme {
+            Theme::Light => HtmlTheme::Light,
+            Theme::Dark => HtmlTheme::Dark,
+            Theme::Print => HtmlTheme::Print,
+        }
+    }
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
 enum OutputFormat {
     Html,
     Json,
+    Markdown,
+    Mml,
     Text,
 }
 
@@ -100,7 +272,9 @@ impl From<OutputFormat> for mml_parser::OutputFormat {
         match format {
             OutputFormat::Html => mml_parser::OutputFormat::HTML,
             OutputFormat::Json => mml_parser::OutputFormat::JSON,
-            OutputFormat::Text => mml_parser::OutputFormat::MML,
+            OutputFormat::Markdown => mml_parser::OutputFormat::Markdown,
+            OutputFormat::Mml => mml_parser::OutputFormat::MML,
+            OutputFormat::Text => mml_parser::OutputFormat::Text,
         }
     }
 }
@@ -109,14 +283,27 @@ fn main() {
     let cli = Cli::parse();
 
     match &cli.command {
-        Commands::Validate { input, strict } => {
-            if let Err(e) = validate_command(input.as_deref(), *strict) {
+        Commands::Validate { input, strict, profile } => {
+            if let Err(e) = validate_command(input.as_deref(), *strict, profile.as_deref()) {
+                eprintln!("Erreur: {}", e);
+                process::exit(1);
+            }
+        }
+        Commands::Convert { input, output, format, validate_schema, theme, single_file, width, ascii } => {
+            let settings = ConvertSettings {
+                validate_schema: *validate_schema,
+                theme: theme.map(Into::into),
+                single_file: *single_file,
+                width: *width,
+                ascii: *ascii,
+            };
+            if let Err(e) = convert_command(input.as_deref(), output.as_deref(), (*format).into(), settings) {
                 eprintln!("Erreur: {}", e);
                 process::exit(1);
             }
         }
-        Commands::Convert { input, output, format } => {
-            if let Err(e) = convert_command(input.as_deref(), output.as_deref(), (*format).into()) {
+        Commands::Import { input, output, from } => {
+            if let Err(e) = import_command(input.as_deref(), output.as_deref(), *from) {
                 eprintln!("Erreur: {}", e);
                 process::exit(1);
             }
@@ -127,15 +314,55 @@ fn main() {
                 process::exit(1);
             }
         }
+        Commands::Morse { action, encode } => {
+            let result = match action {
+                Some(MorseAction::Encode(args)) => morse_encode_command(args),
+                Some(MorseAction::Decode(args)) => morse_decode_command(args),
+                None => morse_encode_command(encode),
+            };
+            if let Err(e) = result {
+                eprintln!("Erreur: {}", e);
+                process::exit(1);
+            }
+        }
         Commands::Stats { input, detailed } => {
             if let Err(e) = stats_command(input.as_deref(), *detailed) {
                 eprintln!("Erreur: {}", e);
                 process::exit(1);
             }
         }
+        Commands::Query { selector, inputs } => {
+            if let Err(e) = query_command(selector, inputs) {
+                eprintln!("Erreur: {}", e);
+                process::exit(1);
+            }
+        }
+        Commands::Diff { old, new, json } => {
+            if let Err(e) = diff_command(old, new, *json) {
+                eprintln!("Erreur: {}", e);
+                process::exit(1);
+            }
+        }
+        Commands::Merge { base, ours, theirs, output } => match merge_command(base, ours, theirs, output.as_deref()) {
+            Ok(true) => {}
+            Ok(false) => process::exit(1),
+            Err(e) => {
+                eprintln!("Erreur: {}", e);
+                process::exit(2);
+            }
+        },
     }
 }
 
+/// Format-specific options of the convert command
+struct ConvertSettings {
+    validate_schema: bool,
+    theme: Option<HtmlTheme>,
+    single_file: bool,
+    width: usize,
+    ascii: bool,
+}
+
 fn read_input(input: Option<&str>) -> Result<String, Box<dyn std::error::Error>> {
     match input {
         Some("-") | None => {
@@ -162,43 +389,121 @@ fn write_output(output: Option<&str>, content: &str) -> Result<(), Box<dyn std::
     Ok(())
 }
 
-fn validate_command(input: Option<&str>, strict: bool) -> Result<(), Box<dyn std::error::Error>> {
+fn validate_command(input: Option<&str>, strict: bool, profile: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
     let content = read_input(input)?;
     let parser = MMLParser::new();
 
-    match parser.validate(&content) {
-        Ok(_) => {
-            eprintln!("✅ Document MML valide");
-            Ok(())
+    let report = parser.validate(&content);
+
+    for issue in &report.warnings {
+        eprintln!("⚠️  Avertissement{}: {}", format_issue_line(issue.line), issue.error);
+    }
+
+    if report.is_valid() {
+        eprintln!("✅ Document MML valide");
+    } else {
+        for issue in &report.errors {
+            let prefix = if strict { "❌ Erreur" } else { "⚠️  Avertissement" };
+            eprintln!("{}{}: {}", prefix, format_issue_line(issue.line), issue.error);
         }
-        Err(e) => {
-            if strict {
-                Err(e.into())
-            } else {
-                eprintln!("⚠️  Avertissement: {}", e);
-                Ok(())
-            }
+        if strict {
+            return Err(format!("{} erreur(s) de validation", report.errors.len()).into());
         }
     }
+
+    match profile {
+        Some(profile) => check_profile(&parser.parse(&content)?, profile),
+        None => Ok(()),
+    }
+}
+
+fn check_profile(document: &mml_parser::MMLDocument, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let profile = Profile::parse(&fs::read_to_string(path)?)?;
+    let name = profile.name.as_deref().unwrap_or(path);
+    let violations = document.validate_against(&profile);
+
+    if violations.is_empty() {
+        eprintln!("✅ Conforme au profil {}", name);
+        return Ok(());
+    }
+
+    for violation in &violations {
+        eprintln!("❌ Profil: {}", violation);
+    }
+    Err(format!("{} violation(s) du profil {}", violations.len(), name).into())
+}
+
+fn format_issue_line(line: Option<usize>) -> String {
+    line.map(|line| format!(" (ligne {})", line)).unwrap_or_default()
 }
 
 fn convert_command(
     input: Option<&str>,
     output: Option<&str>,
     format: mml_parser::OutputFormat,
+    settings: ConvertSettings,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let content = read_input(input)?;
     let parser = MMLParser::new();
     let document = parser.parse(&content)?;
 
-    let result = match format {
-        mml_parser::OutputFormat::HTML => to_html(&document),
-        mml_parser::OutputFormat::JSON => serde_json::to_string_pretty(&document)?,
-        mml_parser::OutputFormat::MML => to_mml(&document),
-        _ => return Err("Format non supporté".into()),
+    let renderer: Box<dyn Renderer> = match format {
+        mml_parser::OutputFormat::JSON => {
+            // With --validate-schema, omissions fail the conversion instead
+            if !settings.validate_schema {
+                for omission in mml_parser::json::omissions(&document) {
+                    eprintln!("⚠️  Perte (schéma DOM v1.0): {}", omission);
+                }
+            }
+            Box::new(JsonRenderer::with_options(JsonOptions {
+                pretty: true,
+                validate: settings.validate_schema,
+            }))
+        }
+        mml_parser::OutputFormat::HTML => {
+            // Images are resolved next to the input file
+            let base = match input {
+                Some(path) if path != "-" => Path::new(path).parent().unwrap_or(Path::new("")).to_path_buf(),
+                _ => PathBuf::from("."),
+            };
+            Box::new(HtmlRenderer::with_options(HtmlOptions {
+                theme: settings.theme,
+                embed_images: settings.single_file.then_some(base),
+                ..HtmlOptions::default()
+            }))
+        }
+        mml_parser::OutputFormat::Text => Box::new(TextRenderer::with_options(TextOptions {
+            width: (settings.width > 0).then_some(settings.width),
+            ascii: settings.ascii,
+            ..TextOptions::default()
+        })),
+        format => format.renderer(),
+    };
+
+    write_output(output, renderer.render_to_string(&document)?.trim_end())
+}
+
+fn import_command(
+    input: Option<&str>,
+    output: Option<&str>,
+    from: InputFormat,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let content = read_input(input)?;
+    let conversion = match from {
+        InputFormat::Html => import::from_html(&content),
+        InputFormat::Markdown => import::from_markdown(&content),
     };
 
-    write_output(output, &result)
+    write_output(output, conversion.to_mml().trim_end())?;
+
+    for loss in &conversion.losses {
+        eprintln!("⚠️  Perte (ligne {}): {}", loss.line, loss.message);
+    }
+    if !conversion.is_lossless() {
+        eprintln!("{} élément(s) non représentable(s) en MML", conversion.losses.len());
+    }
+
+    Ok(())
 }
 
 fn compress_command(
@@ -209,8 +514,7 @@ fn compress_command(
     let content = read_input(input)?;
     let original_size = content.len();
 
-    // For now, basic compression - expand with full algorithm later
-    let compressed = compress_mml(&content, level);
+    let compressed = render::compress(&content, level);
     let compressed_size = compressed.len();
 
     write_output(output, &compressed)?;
@@ -235,9 +539,10 @@ fn stats_command(input: Option<&str>, detailed: bool) -> Result<(), Box<dyn std:
     println!("📊 Statistiques du document MML:");
     println!("Titre: {}", document.title.as_ref().map(|_| "✅").unwrap_or("❌"));
     println!("Sections: {}", document.sections.len());
-    println!("Paragraphes: {}", count_paragraphs(&document));
-    println!("Liens: {}", count_links(&document));
-    println!("Images: {}", count_images(&document));
+    let counts = count_nodes(&document);
+    println!("Paragraphes: {}", counts.paragraphs);
+    println!("Liens: {}", counts.links);
+    println!("Images: {}", counts.images);
     println!("Métadonnées: {}", document.metadata.len());
     println!("Taille: {} octets", content.len());
     println!("Lignes: {}", document.stats.total_lines);
@@ -265,117 +570,154 @@ fn stats_command(input: Option<&str>, detailed: bool) -> Result<(), Box<dyn std:
     Ok(())
 }
 
-// Helper functions
-
-fn count_paragraphs(doc: &mml_parser::MMLDocument) -> usize {
-    doc.sections.iter()
-        .filter(|s| s.content.is_some())
-        .count()
-}
-
-fn count_links(doc: &mml_parser::MMLDocument) -> usize {
-    doc.links.len() +
-    doc.sections.iter().map(|s| s.links.len()).sum::<usize>()
-}
+fn query_command(selector: &str, inputs: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let selector = Selector::parse(selector)?;
+    let parser = MMLParser::new();
 
-fn count_images(doc: &mml_parser::MMLDocument) -> usize {
-    doc.sections.iter().map(|s| s.images.len()).sum::<usize>()
-}
+    // Without files, read stdin once
+    let sources: Vec<Option<&str>> = if inputs.is_empty() {
+        vec![None]
+    } else {
+        inputs.iter().map(|path| Some(path.as_str())).collect()
+    };
 
-fn to_html(doc: &mml_parser::MMLDocument) -> String {
-    let mut html = String::from("<!DOCTYPE html>\n<html>\n<head>\n");
-    html.push_str("<meta charset=\"utf-8\">\n");
+    for source in sources {
+        let content = read_input(source)?;
+        let document = parser.parse(&content)?;
 
-    if let Some(title) = &doc.title {
-        html.push_str(&format!("<title>{}</title>\n", title));
+        for node in selector.select(&document) {
+            for line in node.to_string().lines() {
+                match source {
+                    Some(path) if inputs.len() > 1 => println!("{}: {}", path, line),
+                    _ => println!("{}", line),
+                }
+            }
+        }
     }
 
-    html.push_str("</head>\n<body>\n");
+    Ok(())
+}
 
-    if let Some(title) = &doc.title {
-        html.push_str(&format!("<h1>{}</h1>\n", title));
+fn diff_command(old: &str, new: &str, json: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let parser = MMLParser::new();
+    let old = parser.parse(&read_input(Some(old))?)?;
+    let new = parser.parse(&read_input(Some(new))?)?;
+    let changes = mml_parser::diff(&old, &new);
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&changes)?);
+    } else if changes.is_empty() {
+        eprintln!("✅ Aucune différence structurelle");
+    } else {
+        print!("{}", changes);
     }
 
-    for section in &doc.sections {
-        html.push_str(&format!("<h2>{}</h2>\n", section.title));
-
-        if let Some(content) = &section.content {
-            html.push_str(&format!("<p>{}</p>\n", content));
-        }
+    Ok(())
+}
 
-        for link in &section.links {
-            html.push_str(&format!("<p><a href=\"{}\">{}</a></p>\n",
-                                  link.url, link.text));
-        }
+fn morse_encode_command(args: &MorseEncodeArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let options = morse::CwOptions {
+        wpm: args.wpm,
+        farnsworth: args.farnsworth,
+        tone: args.tone,
+        ..morse::CwOptions::default()
+    };
+    options.validate()?;
+    let code = morse::encode(&read_input(args.input.as_deref())?)?;
 
-        for image in &section.images {
-            html.push_str(&format!(
-                "<figure>\n<img src=\"{}\" alt=\"{}\">\n<figcaption>{}</figcaption>\n</figure>\n",
-                image.url, image.description, image.description
-            ));
+    match &args.wav {
+        Some(path) => {
+            fs::write(path, morse::to_wav(&code, &options)?)?;
+            eprintln!("Fichier écrit: {}", path.display());
         }
+        None => write_output(args.output.as_deref(), &code)?,
     }
 
-    html.push_str("</body>\n</html>");
-    html
+    let seconds = options.timing().duration(&code)?.as_secs();
+    eprintln!("Durée d'émission à {} WPM: {} min {:02} s ({} unités)",
+              args.farnsworth.unwrap_or(args.wpm).min(args.wpm), seconds / 60, seconds % 60, morse::units(&code));
+    Ok(())
 }
 
-fn to_mml(doc: &mml_parser::MMLDocument) -> String {
-    let mut mml = String::new();
+fn morse_decode_command(args: &MorseDecodeArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(path) = &args.wav else {
+        let decoded = morse::decode(&read_input(args.input.as_deref())?);
+        write_output(args.output.as_deref(), &decoded.text)?;
 
-    if let Some(title) = &doc.title {
-        mml.push_str(&format!("T:{}\n", title));
-    }
+        for uncertain in &decoded.uncertain {
+            eprintln!("⚠️  Incertain (position {}): '{}' lu pour {}",
+                      uncertain.offset, uncertain.reading, uncertain.code);
+        }
+        return Ok(());
+    };
+
+    let options = morse::ReceiveOptions { tone: args.tone, ..morse::ReceiveOptions::default() };
+    let reception = morse::receive_wav(&fs::read(path)?, &options)?;
+    eprintln!("Tonalité {:.0} Hz, {:.0} WPM", reception.tone, reception.wpm);
+    write_output(args.output.as_deref(), &reception.decoded.text)?;
 
-    for (key, value) in &doc.metadata {
-        mml.push_str(&format!("M:{}|{}\n", key, value));
+    // Garbled lines are reported, the rest of the document is kept
+    let (_, report) = reception.to_document();
+    for issue in &report.errors {
+        eprintln!("⚠️  Ligne illisible{}: {}", format_issue_line(issue.line), issue.error);
+    }
+    for issue in &report.warnings {
+        eprintln!("⚠️  Avertissement{}: {}", format_issue_line(issue.line), issue.error);
     }
+    Ok(())
+}
 
-    for section in &doc.sections {
-        mml.push_str(&format!("H:{}\n", section.title));
+/// Returns whether the merge was clean
+fn merge_command(
+    base: &str,
+    ours: &str,
+    theirs: &str,
+    output: Option<&str>,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    let parser = MMLParser::new();
+    let base = parser.parse(&read_input(Some(base))?)?;
+    let ours = parser.parse(&read_input(Some(ours))?)?;
+    let theirs = parser.parse(&read_input(Some(theirs))?)?;
 
-        for (key, value) in &section.metadata {
-            mml.push_str(&format!("M:{}|{}\n", key, value));
-        }
+    let result = mml_parser::merge(&base, &ours, &theirs);
+    write_output(output, result.to_mml().trim_end())?;
 
-        if let Some(content) = &section.content {
-            mml.push_str(&format!("P:{}\n", content));
+    for conflict in &result.conflicts {
+        match &conflict.section {
+            Some(section) => eprintln!("⚠️  Conflit dans '{}': {}", section, conflict.description),
+            None => eprintln!("⚠️  Conflit: {}", conflict.description),
         }
+    }
 
-        for link in &section.links {
-            mml.push_str(&format!("L:{}|{}\n", link.text, link.url));
-        }
+    Ok(result.is_clean())
+}
 
-        for image in &section.images {
-            mml.push_str(&format!("IMG:{}|{}\n", image.description, image.url));
-        }
-    }
+// Helper functions
 
-    mml
+/// Node counts gathered in a single walk of the document
+#[derive(Default)]
+struct NodeCounter {
+    paragraphs: usize,
+    links: usize,
+    images: usize,
 }
 
-fn compress_mml(content: &str, level: mml_parser::CompressionLevel) -> String {
-    // Basic compression - replace common words
-    let mut compressed = content.to_string();
-
-    if level != mml_parser::CompressionLevel::None {
-        let replacements = [
-            ("Rapport", "R"),
-            ("Urgent", "U"),
-            ("Critique", "C"),
-            ("Patient", "P1"),
-            ("Victime", "V"),
-            ("Secteur", "S"),
-            ("Évacuation", "E"),
-            ("Médical", "M"),
-            ("Stable", "S1"),
-            ("Alerte", "A"),
-        ];
-
-        for (word, code) in &replacements {
-            compressed = compressed.replace(word, code);
-        }
+impl Visit for NodeCounter {
+    fn visit_paragraph(&mut self, _paragraph: &str) {
+        self.paragraphs += 1;
     }
 
-    compressed
+    fn visit_link(&mut self, _link: &mml_parser::MMLLink) {
+        self.links += 1;
+    }
+
+    fn visit_image(&mut self, _image: &mml_parser::MMLImage) {
+        self.images += 1;
+    }
+}
+
+fn count_nodes(doc: &mml_parser::MMLDocument) -> NodeCounter {
+    let mut counter = NodeCounter::default();
+    walk(doc, &mut counter);
+    counter
 }
@@ -0,0 +1,456 @@
+//! Three-way merge
+//!
+//! [`merge`] combines two documents edited independently from a common
+//! base. Nodes are matched the same way as in [`crate::diff`]: sections by
+//! title, metadata by key, links by text, images by description, and
+//! paragraphs, code blocks and quotes by content. A node changed on one side
+//! only takes that side's version; a node changed differently on both sides
+//! is a conflict.
+//!
+//! Conflicts keep "our" version in the merged document (or the surviving
+//! version when one side removed the node) and are listed in
+//! [`MergeResult::conflicts`]. [`MergeResult::to_mml`] also writes them as
+//! `#` comments, which the parser ignores, next to the node they concern:
+//!
+//! ```text
+//! M:Statut|Maîtrisé
+//! # <<<<<<< ours: metadata 'Statut'
+//! # M:Statut|Maîtrisé
+//! # ||||||| base
+//! # M:Statut|En cours
+//! # =======
+//! # M:Statut|Évacuation
+//! # >>>>>>> theirs
+//! ```
+
+use crate::diff::match_by_key;
+use crate::query::MMLNode;
+use crate::ser::write_document_with;
+use crate::types::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+
+/// A node changed differently on both sides
+///
+/// Each version is the node written as MML, or `None` when that side does
+/// not have it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MergeConflict {
+    /// Title of the section holding the node, `None` at document level
+    pub section: Option<String>,
+    /// What the conflict is about, such as `metadata 'Statut'`
+    pub description: String,
+    pub base: Option<String>,
+    pub ours: Option<String>,
+    pub theirs: Option<String>,
+}
+
+/// Outcome of [`merge`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergeResult {
+    /// Merged document, using our version of conflicting nodes
+    pub document: MMLDocument,
+    /// Conflicts, in document order
+    pub conflicts: Vec<MergeConflict>,
+}
+
+impl MergeResult {
+    /// True when the merge needed no arbitration
+    pub fn is_clean(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+
+    /// Write the merged document as MML, with conflicts as `#` comments
+    ///
+    /// Packets are transport envelopes and are not written.
+    pub fn to_mml(&self) -> String {
+        let mut written: BTreeSet<Option<String>> = BTreeSet::new();
+        write_document_with(&self.document, |lines, section| {
+            if written.insert(section.map(str::to_string)) {
+                self.write_conflicts(lines, section);
+            }
+        })
+    }
+
+    fn write_conflicts(&self, lines: &mut Vec<String>, section: Option<&str>) {
+        for conflict in self.conflicts.iter().filter(|c| c.section.as_deref() == section) {
+            lines.push(format!("# <<<<<<< ours: {}", conflict.description));
+            push_commented(lines, &conflict.ours);
+            lines.push("# ||||||| base".to_string());
+            push_commented(lines, &conflict.base);
+            lines.push("# =======".to_string());
+            push_commented(lines, &conflict.theirs);
+            lines.push("# >>>>>>> theirs".to_string());
+        }
+    }
+}
+
+fn push_commented(lines: &mut Vec<String>, version: &Option<String>) {
+    match version {
+        Some(mml) => lines.extend(mml.split('\n').map(|line| format!("# {}", line))),
+        None => lines.push("# (absent)".to_string()),
+    }
+}
+
+/// Merge two versions of a document edited from a common base
+///
+/// # Example
+///
+/// ```rust
+/// use mml_parser::{merge, parse_mml};
+///
+/// let base = parse_mml("M:Statut|En cours\nM:Équipe|Alpha").unwrap();
+/// let ours = parse_mml("M:Statut|Maîtrisé\nM:Équipe|Alpha").unwrap();
+/// let theirs = parse_mml("M:Statut|En cours\nM:Équipe|Bravo").unwrap();
+///
+/// let result = merge(&base, &ours, &theirs);
+/// assert!(result.is_clean());
+/// assert_eq!(result.document.metadata["Statut"], "Maîtrisé");
+/// assert_eq!(result.document.metadata["Équipe"], "Bravo");
+/// ```
+pub fn merge(base: &MMLDocument, ours: &MMLDocument, theirs: &MMLDocument) -> MergeResult {
+    let mut merger = Merger::default();
+    let mut document = ours.clone();
+
+    document.title = merger.value("title", base.title.as_ref(), ours.title.as_ref(), theirs.title.as_ref(), |t| {
+        MMLNode::Title(t).to_string()
+    });
+    document.metadata = merger.metadata(&base.metadata, &ours.metadata, &theirs.metadata);
+    document.content = merger.nodes(&base.content, &ours.content, &theirs.content);
+    document.links = merger.nodes(&base.links, &ours.links, &theirs.links);
+    document.images = merger.nodes(&base.images, &ours.images, &theirs.images);
+    document.code = merger.nodes(&base.code, &ours.code, &theirs.code);
+    document.quotes = merger.nodes(&base.quotes, &ours.quotes, &theirs.quotes);
+    document.sections = merger.list(
+        &base.sections,
+        &ours.sections,
+        &theirs.sections,
+        |s| s.title.as_str(),
+        |merger, base, ours, theirs| match (ours, theirs) {
+            (Some(ours), Some(theirs)) => {
+                let empty = MMLSection::new(ours.title.clone());
+                Some(merger.section(base.unwrap_or(&empty), ours, theirs))
+            }
+            _ => {
+                let title = base.or(ours).or(theirs).map(|s| s.title.clone()).unwrap_or_default();
+                merger.value(&format!("section '{}'", title), base, ours, theirs, |s| MMLNode::Section(s).to_string())
+            }
+        },
+    );
+
+    MergeResult {
+        document,
+        conflicts: merger.conflicts,
+    }
+}
+
+/// Node kinds merged as lists, matched by a key
+trait Mergeable: Clone + PartialEq {
+    fn key(&self) -> &str;
+    fn describe(&self) -> String;
+    fn to_mml(&self) -> String;
+}
+
+impl Mergeable for String {
+    fn key(&self) -> &str {
+        self
+    }
+    fn describe(&self) -> String {
+        "paragraph".to_string()
+    }
+    fn to_mml(&self) -> String {
+        MMLNode::Paragraph(self).to_string()
+    }
+}
+
+impl Mergeable for MMLLink {
+    fn key(&self) -> &str {
+        &self.text
+    }
+    fn describe(&self) -> String {
+        format!("link '{}'", self.text)
+    }
+    fn to_mml(&self) -> String {
+        MMLNode::Link(self).to_string()
+    }
+}
+
+impl Mergeable for MMLImage {
+    fn key(&self) -> &str {
+        &self.description
+    }
+    fn describe(&self) -> String {
+        format!("image '{}'", self.description)
+    }
+    fn to_mml(&self) -> String {
+        MMLNode::Image(self).to_string()
+    }
+}
+
+impl Mergeable for MMLCode {
+    fn key(&self) -> &str {
+        &self.content
+    }
+    fn describe(&self) -> String {
+        "code block".to_string()
+    }
+    fn to_mml(&self) -> String {
+        MMLNode::Code(self).to_string()
+    }
+}
+
+impl Mergeable for MMLQuote {
+    fn key(&self) -> &str {
+        &self.content
+    }
+    fn describe(&self) -> String {
+        "quote".to_string()
+    }
+    fn to_mml(&self) -> String {
+        MMLNode::Quote(self).to_string()
+    }
+}
+
+/// Merge state: the section being merged and the conflicts found so far
+#[derive(Debug, Default)]
+struct Merger {
+    section: Option<String>,
+    conflicts: Vec<MergeConflict>,
+}
+
+impl Merger {
+    /// Three-way merge of a single value, `None` meaning absent
+    fn value<T: Clone + PartialEq>(
+        &mut self,
+        description: &str,
+        base: Option<&T>,
+        ours: Option<&T>,
+        theirs: Option<&T>,
+        to_mml: impl Fn(&T) -> String,
+    ) -> Option<T> {
+        if ours == theirs || theirs == base {
+            return ours.cloned();
+        }
+        if ours == base {
+            return theirs.cloned();
+        }
+
+        self.conflicts.push(MergeConflict {
+            section: self.section.clone(),
+            description: description.to_string(),
+            base: base.map(&to_mml),
+            ours: ours.map(&to_mml),
+            theirs: theirs.map(&to_mml),
+        });
+        ours.or(theirs).cloned()
+    }
+
+    fn metadata(
+        &mut self,
+        base: &HashMap<String, String>,
+        ours: &HashMap<String, String>,
+        theirs: &HashMap<String, String>,
+    ) -> HashMap<String, String> {
+        let keys: BTreeSet<&String> = base.keys().chain(ours.keys()).chain(theirs.keys()).collect();
+        let mut merged = HashMap::new();
+
+        for key in keys {
+            let value = self.value(
+                &format!("metadata '{}'", key),
+                base.get(key),
+                ours.get(key),
+                theirs.get(key),
+                |value| MMLNode::Metadata { key, value }.to_string(),
+            );
+            if let Some(value) = value {
+                merged.insert(key.clone(), value);
+            }
+        }
+        merged
+    }
+
+    fn nodes<T: Mergeable>(&mut self, base: &[T], ours: &[T], theirs: &[T]) -> Vec<T> {
+        self.list(base, ours, theirs, T::key, |merger, base, ours, theirs| {
+            let description = base.or(ours).or(theirs).map(T::describe).unwrap_or_default();
+            merger.value(&description, base, ours, theirs, T::to_mml)
+        })
+    }
+
+    fn section(&mut self, base: &MMLSection, ours: &MMLSection, theirs: &MMLSection) -> MMLSection {
+        let parent = self.section.replace(ours.title.clone());
+
+        let mut section = MMLSection::new(ours.title.clone());
+        section.metadata = self.metadata(&base.metadata, &ours.metadata, &theirs.metadata);
+        section.content = self.value(
+            "paragraph",
+            base.content.as_ref(),
+            ours.content.as_ref(),
+            theirs.content.as_ref(),
+            |p| MMLNode::Paragraph(p).to_string(),
+        );
+        section.links = self.nodes(&base.links, &ours.links, &theirs.links);
+        section.images = self.nodes(&base.images, &ours.images, &theirs.images);
+        section.code = self.nodes(&base.code, &ours.code, &theirs.code);
+        section.quotes = self.nodes(&base.quotes, &ours.quotes, &theirs.quotes);
+
+        self.section = parent;
+        section
+    }
+
+    /// Merge two lists matched against the base by key
+    ///
+    /// The result follows our order; nodes only we removed but they changed
+    /// come next, then the nodes only they added. A node added on both sides
+    /// with the same key is merged against an absent base.
+    fn list<T, K, R>(&mut self, base: &[T], ours: &[T], theirs: &[T], key: K, mut resolve: R) -> Vec<T>
+    where
+        K: Copy + Fn(&T) -> &str,
+        R: FnMut(&mut Self, Option<&T>, Option<&T>, Option<&T>) -> Option<T>,
+    {
+        let (ours_matches, _) = match_by_key(base, ours, key);
+        let (theirs_matches, _) = match_by_key(base, theirs, key);
+
+        let mut in_ours = vec![None; base.len()];
+        for (j, matched) in ours_matches.iter().enumerate() {
+            if let Some(i) = matched {
+                in_ours[*i] = Some(j);
+            }
+        }
+        let mut in_theirs = vec![None; base.len()];
+        for (k, matched) in theirs_matches.iter().enumerate() {
+            if let Some(i) = matched {
+                in_theirs[*i] = Some(k);
+            }
+        }
+
+        // Pair the additions of both sides by key
+        let ours_added: Vec<&T> = ours.iter().zip(&ours_matches).filter(|(_, m)| m.is_none()).map(|(t, _)| t).collect();
+        let theirs_added: Vec<&T> =
+            theirs.iter().zip(&theirs_matches).filter(|(_, m)| m.is_none()).map(|(t, _)| t).collect();
+        let (paired, theirs_paired) = match_by_key(&theirs_added, &ours_added, |t: &&T| key(t));
+        let mut paired = paired.into_iter();
+
+        let mut merged = Vec::new();
+        for (ours_item, matched) in ours.iter().zip(&ours_matches) {
+            let result = match matched {
+                Some(i) => resolve(self, Some(&base[*i]), Some(ours_item), in_theirs[*i].map(|k| &theirs[k])),
+                None => {
+                    let theirs_item = paired.next().flatten().map(|k| theirs_added[k]);
+                    resolve(self, None, Some(ours_item), theirs_item)
+                }
+            };
+            merged.extend(result);
+        }
+        for (i, base_item) in base.iter().enumerate() {
+            if in_ours[i].is_none() {
+                merged.extend(resolve(self, Some(base_item), None, in_theirs[i].map(|k| &theirs[k])));
+            }
+        }
+        for (theirs_item, paired) in theirs_added.iter().zip(theirs_paired) {
+            if !paired {
+                merged.extend(resolve(self, None, None, Some(*theirs_item)));
+            }
+        }
+        merged
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_mml;
+
+    const BASE: &str = "T:Sitrep\nM:Statut|En cours\nM:Équipe|Alpha\n\
+                        H:Situation\nM:Surface|450 hectares\nP:Feu actif\nL:Carte|v1.png\n\
+                        H:Ressources\nP:3 camions\nH:Météo\nP:Vent fort";
+
+    fn merged(ours: &str, theirs: &str) -> MergeResult {
+        merge(&parse_mml(BASE).unwrap(), &parse_mml(ours).unwrap(), &parse_mml(theirs).unwrap())
+    }
+
+    #[test]
+    fn test_clean_merge() {
+        let ours = "T:Sitrep\nM:Statut|Maîtrisé\nM:Équipe|Alpha\n\
+                    H:Situation\nM:Surface|450 hectares\nP:Feu fixé\nL:Carte|v1.png\n\
+                    H:Ressources\nP:3 camions\nH:Météo\nP:Vent fort\nH:Renforts\nP:Canadairs";
+        let theirs = "T:Sitrep\nM:Statut|En cours\nM:Équipe|Alpha\nM:Bilan|Aucun blessé\n\
+                      H:Situation\nM:Surface|600 hectares\nP:Feu actif\nL:Carte|v2.png\n\
+                      H:Météo\nP:Vent fort";
+        let result = merged(ours, theirs);
+
+        assert!(result.is_clean());
+        let document = &result.document;
+        assert_eq!(document.metadata["Statut"], "Maîtrisé");
+        assert_eq!(document.metadata["Bilan"], "Aucun blessé");
+
+        let titles: Vec<_> = document.sections.iter().map(|s| s.title.as_str()).collect();
+        assert_eq!(titles, vec!["Situation", "Météo", "Renforts"]);
+        let situation = &document.sections[0];
+        assert_eq!(situation.content.as_deref(), Some("Feu fixé"));
+        assert_eq!(situation.metadata["Surface"], "600 hectares");
+        assert_eq!(situation.links[0].url, "v2.png");
+    }
+
+    #[test]
+    fn test_conflicts() {
+        let ours = BASE.replace("M:Statut|En cours", "M:Statut|Maîtrisé").replace("H:Météo\nP:Vent fort", "");
+        let theirs = BASE
+            .replace("M:Statut|En cours", "M:Statut|Évacuation")
+            .replace("P:Vent fort", "P:Vent tournant");
+        let result = merged(&ours, &theirs);
+
+        assert_eq!(result.conflicts.len(), 2);
+        let status = &result.conflicts[0];
+        assert_eq!(status.section, None);
+        assert_eq!(status.description, "metadata 'Statut'");
+        assert_eq!(status.base.as_deref(), Some("M:Statut|En cours"));
+        assert_eq!(status.theirs.as_deref(), Some("M:Statut|Évacuation"));
+        assert_eq!(result.document.metadata["Statut"], "Maîtrisé");
+
+        // Removed on our side, changed on theirs: the changed section stays
+        assert_eq!(result.conflicts[1].ours, None);
+        assert_eq!(result.document.sections.last().unwrap().content.as_deref(), Some("Vent tournant"));
+    }
+
+    #[test]
+    fn test_conflict_comments_parse_back() {
+        let ours = BASE.replace("P:Feu actif", "P:Feu fixé");
+        let theirs = BASE.replace("P:Feu actif", "P:Feu éteint");
+        let result = merged(&ours, &theirs);
+        let mml = result.to_mml();
+
+        assert!(mml.contains(
+            "L:Carte|v1.png\n# <<<<<<< ours: paragraph\n# P:Feu fixé\n# ||||||| base\n# P:Feu actif\n\
+             # =======\n# P:Feu éteint\n# >>>>>>> theirs\nH:Ressources"
+        ));
+        let reparsed = parse_mml(&mml).unwrap();
+        assert_eq!(reparsed.sections, result.document.sections);
+    }
+
+    #[test]
+    fn test_merged_code_blocks_parse_back() {
+        let base = "H:A\nC:x\nP:t\nC:y";
+        let ours = "H:A\nC:x\nP:t\nC:y\nM:lang|bash";
+        let theirs = "C:z\nC:w\n#\nC:v\nH:A\nC:x\nP:t2\nC:y";
+        let result = merge(&parse_mml(base).unwrap(), &parse_mml(ours).unwrap(), &parse_mml(theirs).unwrap());
+        assert!(result.is_clean());
+        assert_eq!(result.document.sections[0].code.len(), 2);
+
+        let mut reparsed = parse_mml(&result.to_mml()).unwrap();
+        reparsed.stats = result.document.stats.clone();
+        assert_eq!(reparsed, result.document);
+    }
+
+    #[test]
+    fn test_both_sides_add_same_section() {
+        let ours = format!("{}\nH:Bilan\nM:Blessés|0\nP:RAS", BASE);
+        let theirs = format!("{}\nH:Bilan\nM:Blessés|2\nL:Rapport|bilan.pdf", BASE);
+        let result = merged(&ours, &theirs);
+
+        let bilan = result.document.sections.iter().find(|s| s.title == "Bilan").unwrap();
+        assert_eq!(bilan.content.as_deref(), Some("RAS"));
+        assert_eq!(bilan.links.len(), 1);
+        assert_eq!(result.conflicts.len(), 1);
+        assert_eq!(result.conflicts[0].section.as_deref(), Some("Bilan"));
+    }
+}
@@ -0,0 +1,719 @@
+//! Typed metadata values
+//!
+//! Metadata values are stored as text. [`MetadataValue`] reads them as
+//! numbers, quantities, percentages, timestamps, coordinates or booleans,
+//! with rules lenient enough for hand-written field reports in French or
+//! English: decimal commas (`23,4`), thousands separators (`1 500`), units
+//! glued to the number (`5km²`), trailing qualifiers (`25 km/h NNE`),
+//! French times (`14h30`) and hemisphere letters (`45.2N 1.4W`, `O` for
+//! ouest).
+//!
+//! [`MetadataSchema`] optionally declares the expected kind of each key.
+
+use crate::types::{MMLDocument, MMLSection};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+
+/// A metadata value, read on demand as a typed value
+///
+/// # Example
+///
+/// ```rust
+/// use mml_parser::parse_mml;
+///
+/// let document = parse_mml("H:Situation\nM:Vent|25 km/h NNE\nM:Batterie|87%").unwrap();
+/// let section = &document.sections[0];
+///
+/// let wind = section.metadata_value("Vent").unwrap().as_quantity().unwrap();
+/// assert_eq!((wind.value, wind.unit.as_str()), (25.0, "km/h"));
+/// assert_eq!(wind.qualifier.as_deref(), Some("NNE"));
+///
+/// assert_eq!(section.metadata_value("Batterie").unwrap().as_percentage(), Some(87.0));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MetadataValue<'a> {
+    raw: &'a str,
+}
+
+impl<'a> MetadataValue<'a> {
+    /// Wrap a raw metadata value
+    pub fn new(raw: &'a str) -> Self {
+        Self { raw }
+    }
+
+    /// The value as written
+    pub fn as_str(&self) -> &'a str {
+        self.raw
+    }
+
+    /// A plain number: `45`, `-35`, `1013.2`, `23,4`, `1 500`
+    pub fn as_number(&self) -> Option<f64> {
+        match split_number(self.raw.trim()) {
+            Some((value, "")) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// A number followed by a unit and an optional qualifier
+    ///
+    /// `450 hectares`, `23.4°C`, `5km²`, `25 km/h NNE`.
+    pub fn as_quantity(&self) -> Option<Quantity> {
+        let (value, rest) = split_number(self.raw.trim())?;
+        let rest = rest.trim_start();
+        let (unit, qualifier) = match rest.find(char::is_whitespace) {
+            Some(end) => (&rest[..end], rest[end..].trim()),
+            None => (rest, ""),
+        };
+
+        // "1000-5000" is a range, not a unit
+        let first = unit.chars().next()?;
+        if first.is_ascii_digit() || first == '-' {
+            return None;
+        }
+
+        Some(Quantity {
+            value,
+            unit: unit.to_string(),
+            qualifier: (!qualifier.is_empty()).then(|| qualifier.to_string()),
+        })
+    }
+
+    /// A percentage, returned in percent: `87%` gives `87.0`
+    pub fn as_percentage(&self) -> Option<f64> {
+        self.as_quantity().filter(|q| q.unit == "%").map(|q| q.value)
+    }
+
+    /// A date, a time of day, both, or Unix seconds
+    ///
+    /// `2025-11-15T14:30:00Z`, `2025-11-15`, `15/11/2025`, `15NOV2025`,
+    /// `14h30`, `14:30`, `1640995200`.
+    pub fn as_timestamp(&self) -> Option<Timestamp> {
+        Timestamp::parse(self.raw.trim())
+    }
+
+    /// A latitude/longitude pair: `45.234N 2.456E`, `43.5N,1.4W`,
+    /// `48.8566, 2.3522`, `45°14'N 2°27'E`
+    pub fn as_coordinate(&self) -> Option<Coordinate> {
+        Coordinate::parse(self.raw.trim())
+    }
+
+    /// A yes/no value: `oui`, `non`, `yes`, `no`, `true`, `faux`, `actif`...
+    pub fn as_bool(&self) -> Option<bool> {
+        match self.raw.trim().to_lowercase().as_str() {
+            "oui" | "o" | "yes" | "y" | "true" | "vrai" | "1" | "on" | "actif" | "active" | "activé" => Some(true),
+            "non" | "n" | "no" | "false" | "faux" | "0" | "off" | "inactif" | "inactive" | "désactivé" => Some(false),
+            _ => None,
+        }
+    }
+
+    /// Read the value as the given kind
+    pub fn typed(&self, kind: MetadataKind) -> Option<TypedValue> {
+        match kind {
+            MetadataKind::Text => Some(TypedValue::Text(self.raw.to_string())),
+            MetadataKind::Number => self.as_number().map(TypedValue::Number),
+            MetadataKind::Quantity => self.as_quantity().map(TypedValue::Quantity),
+            MetadataKind::Percentage => self.as_percentage().map(TypedValue::Percentage),
+            MetadataKind::Timestamp => self.as_timestamp().map(TypedValue::Timestamp),
+            MetadataKind::Coordinate => self.as_coordinate().map(TypedValue::Coordinate),
+            MetadataKind::Bool => self.as_bool().map(TypedValue::Bool),
+        }
+    }
+
+    /// Guess the kind of the value
+    ///
+    /// Tries, in order: timestamp, coordinate, number, percentage, quantity
+    /// and boolean words; anything else is text. `0` and `1` read as
+    /// numbers here, use a schema hint to read them as booleans.
+    pub fn infer(&self) -> TypedValue {
+        [
+            MetadataKind::Timestamp,
+            MetadataKind::Coordinate,
+            MetadataKind::Number,
+            MetadataKind::Percentage,
+            MetadataKind::Quantity,
+            MetadataKind::Bool,
+        ]
+        .into_iter()
+        .find_map(|kind| self.typed(kind))
+        .unwrap_or_else(|| TypedValue::Text(self.raw.to_string()))
+    }
+}
+
+impl fmt::Display for MetadataValue<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.raw)
+    }
+}
+
+/// A number with a unit, such as `450 hectares` or `25 km/h NNE`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Quantity {
+    pub value: f64,
+    /// Unit as written (`hectares`, `°C`, `km/h`, `%`)
+    pub unit: String,
+    /// Text after the unit, such as a wind direction
+    pub qualifier: Option<String>,
+}
+
+/// A point in time, as precise as the field report gives it
+///
+/// Reports often give only a time (`14h30`) or only a date; the missing
+/// parts stay `None`. `utc_offset_minutes` is `None` for local times.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Timestamp {
+    /// `(year, month, day)`
+    pub date: Option<(i32, u32, u32)>,
+    /// `(hour, minute, second)`
+    pub time: Option<(u32, u32, u32)>,
+    /// Offset from UTC, when known (`Z`, `+02:00`, Unix seconds)
+    pub utc_offset_minutes: Option<i32>,
+}
+
+impl Timestamp {
+    /// Seconds since 1970-01-01T00:00:00Z, when date and offset are known
+    ///
+    /// A date without a time counts as midnight.
+    pub fn unix_seconds(&self) -> Option<i64> {
+        let (year, month, day) = self.date?;
+        let (hour, minute, second) = self.time.unwrap_or((0, 0, 0));
+        let offset = self.utc_offset_minutes?;
+        let days = days_from_civil(year, month, day);
+        Some(days * 86_400 + i64::from(hour * 3600 + minute * 60 + second) - i64::from(offset) * 60)
+    }
+
+    fn parse(text: &str) -> Option<Self> {
+        // Unix seconds: 9 to 11 digits covers 1973 to 5138
+        if (9..=11).contains(&text.len()) && text.bytes().all(|b| b.is_ascii_digit()) {
+            return Some(Self::from_unix(text.parse().ok()?));
+        }
+        if let Some(time) = parse_time(text) {
+            return Some(Self {
+                date: None,
+                time: Some(time),
+                utc_offset_minutes: None,
+            });
+        }
+
+        // `T` or a space between a date and a time, not the T of `15OCT2025`
+        let separator = text.char_indices().find(|&(i, c)| {
+            matches!(c, 'T' | ' ')
+                && text[..i].ends_with(|p: char| p.is_ascii_digit())
+                && text[i + 1..].trim_start().starts_with(|n: char| n.is_ascii_digit())
+        });
+        let (date_part, time_part) = match separator {
+            Some((index, _)) => (&text[..index], Some(text[index + 1..].trim())),
+            None => (text, None),
+        };
+        let date = parse_date(date_part)?;
+        let (time, utc_offset_minutes) = match time_part {
+            Some(time) => {
+                let (time, offset) = split_offset(time)?;
+                (Some(parse_time(time)?), offset)
+            }
+            None => (None, None),
+        };
+
+        Some(Self {
+            date: Some(date),
+            time,
+            utc_offset_minutes,
+        })
+    }
+
+    fn from_unix(seconds: i64) -> Self {
+        let days = seconds.div_euclid(86_400);
+        let rest = seconds.rem_euclid(86_400) as u32;
+        Self {
+            date: Some(civil_from_days(days)),
+            time: Some((rest / 3600, rest % 3600 / 60, rest % 60)),
+            utc_offset_minutes: Some(0),
+        }
+    }
+}
+
+/// A position in decimal degrees, north and east positive
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Coordinate {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+impl Coordinate {
+    fn parse(text: &str) -> Option<Self> {
+        let parts: Vec<&str> = text
+            .split([',', ';', ' '])
+            .map(str::trim)
+            .filter(|p| !p.is_empty())
+            .collect();
+        let [first, second] = parts[..] else {
+            return None;
+        };
+
+        let (first, first_axis) = parse_angle(first)?;
+        let (second, second_axis) = parse_angle(second)?;
+
+        // Without hemispheres, only "lat, lon" reads as a position, and
+        // "23,4" is a decimal comma
+        if first_axis.is_none()
+            && second_axis.is_none()
+            && (!text.contains([',', ';']) || matches!(split_number(text.trim()), Some((_, ""))))
+        {
+            return None;
+        }
+        let (latitude, longitude) = match (first_axis, second_axis) {
+            (Some(Axis::Longitude), None | Some(Axis::Latitude)) | (None, Some(Axis::Latitude)) => (second, first),
+            (Some(a), Some(b)) if a == b => return None,
+            _ => (first, second),
+        };
+
+        ((-90.0..=90.0).contains(&latitude) && (-180.0..=180.0).contains(&longitude))
+            .then_some(Self { latitude, longitude })
+    }
+}
+
+/// Kind of value a metadata key is expected to hold
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MetadataKind {
+    Text,
+    Number,
+    Quantity,
+    Percentage,
+    Timestamp,
+    Coordinate,
+    Bool,
+}
+
+impl MetadataKind {
+    /// Parse a lowercase kind name (`number`, `timestamp`...)
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "text" => Some(MetadataKind::Text),
+            "number" => Some(MetadataKind::Number),
+            "quantity" => Some(MetadataKind::Quantity),
+            "percentage" => Some(MetadataKind::Percentage),
+            "timestamp" => Some(MetadataKind::Timestamp),
+            "coordinate" => Some(MetadataKind::Coordinate),
+            "bool" => Some(MetadataKind::Bool),
+            _ => None,
+        }
+    }
+}
+
+/// A metadata value read as a given kind
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "value", rename_all = "lowercase")]
+pub enum TypedValue {
+    Text(String),
+    Number(f64),
+    Quantity(Quantity),
+    Percentage(f64),
+    Timestamp(Timestamp),
+    Coordinate(Coordinate),
+    Bool(bool),
+}
+
+/// Expected kind per metadata key
+///
+/// Keys are compared case-insensitively, since reports write both
+/// `TEMPERATURE` and `Temperature`.
+///
+/// # Example
+///
+/// ```rust
+/// use mml_parser::{MetadataKind, MetadataSchema, TypedValue};
+///
+/// let schema = MetadataSchema::new()
+///     .hint("Effectifs", MetadataKind::Number)
+///     .hint("Chiffrement", MetadataKind::Bool);
+///
+/// assert_eq!(schema.read("EFFECTIFS", "45"), Some(TypedValue::Number(45.0)));
+/// assert_eq!(schema.read("Effectifs", "quarante"), None);
+/// assert_eq!(schema.read("Chiffrement", "1"), Some(TypedValue::Bool(true)));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MetadataSchema {
+    hints: HashMap<String, MetadataKind>,
+}
+
+impl MetadataSchema {
+    /// Create an empty schema
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declare the kind of a key
+    pub fn hint(mut self, key: &str, kind: MetadataKind) -> Self {
+        self.hints.insert(key.to_lowercase(), kind);
+        self
+    }
+
+    /// Declared kind of a key, if any
+    pub fn kind(&self, key: &str) -> Option<MetadataKind> {
+        self.hints.get(&key.to_lowercase()).copied()
+    }
+
+    /// Read a value with the key's hint, or infer its kind without one
+    ///
+    /// Returns `None` when the value does not match the declared kind.
+    pub fn read(&self, key: &str, value: &str) -> Option<TypedValue> {
+        let value = MetadataValue::new(value);
+        match self.kind(key) {
+            Some(kind) => value.typed(kind),
+            None => Some(value.infer()),
+        }
+    }
+}
+
+impl MMLDocument {
+    /// Global metadata value for `key`, ready to be read as a typed value
+    pub fn metadata_value(&self, key: &str) -> Option<MetadataValue<'_>> {
+        self.metadata.get(key).map(|value| MetadataValue::new(value))
+    }
+}
+
+impl MMLSection {
+    /// Section metadata value for `key`, ready to be read as a typed value
+    pub fn metadata_value(&self, key: &str) -> Option<MetadataValue<'_>> {
+        self.metadata.get(key).map(|value| MetadataValue::new(value))
+    }
+}
+
+/// Split a leading number off `text`
+///
+/// Accepts a sign, a decimal point or comma, and thousands groups separated
+/// by a space (`1 500`, also no-break spaces).
+fn split_number(text: &str) -> Option<(f64, &str)> {
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let mut number = String::new();
+    let mut i = 0;
+
+    if let Some(&(_, sign)) = chars.first() {
+        if matches!(sign, '-' | '+' | '−') {
+            number.push(if sign == '+' { '+' } else { '-' });
+            i += 1;
+        }
+    }
+
+    let digits_from = |i: usize| chars[i..].iter().take_while(|(_, c)| c.is_ascii_digit()).count();
+    let integer = digits_from(i);
+    if integer == 0 {
+        return None;
+    }
+    number.extend(chars[i..i + integer].iter().map(|(_, c)| c));
+    i += integer;
+
+    // Thousands groups: a separator followed by exactly three digits
+    while i < chars.len() && matches!(chars[i].1, ' ' | '\u{a0}' | '\u{202f}') && digits_from(i + 1) == 3 {
+        number.extend(chars[i + 1..i + 4].iter().map(|(_, c)| c));
+        i += 4;
+    }
+
+    if i < chars.len() && matches!(chars[i].1, '.' | ',') {
+        let decimals = digits_from(i + 1);
+        if decimals > 0 {
+            number.push('.');
+            number.extend(chars[i + 1..i + 1 + decimals].iter().map(|(_, c)| c));
+            i += 1 + decimals;
+        }
+    }
+
+    let rest = chars.get(i).map_or("", |&(index, _)| &text[index..]);
+    Some((number.parse().ok()?, rest))
+}
+
+/// `14h30`, `14h`, `14H30`, `14:30`, `14:30:05`, `14:30:05.250`
+fn parse_time(text: &str) -> Option<(u32, u32, u32)> {
+    let (hour, rest) = text.split_once(['h', 'H', ':'])?;
+    let hour: u32 = parse_digits(hour, 1..=2)?;
+    let (minute, second) = match rest.split_once(':') {
+        Some((minute, second)) => {
+            let second = second.split_once('.').map_or(second, |(whole, _)| whole);
+            (parse_digits(minute, 2..=2)?, parse_digits(second, 2..=2)?)
+        }
+        None if rest.is_empty() && text.contains(['h', 'H']) => (0, 0),
+        None => (parse_digits(rest, 2..=2)?, 0),
+    };
+
+    (hour < 24 && minute < 60 && second < 61).then_some((hour, minute, second))
+}
+
+/// `2025-11-15`, `15/11/2025`, `15.11.2025`, `15NOV2025`
+fn parse_date(text: &str) -> Option<(i32, u32, u32)> {
+    let (year, month, day) = if let Some((year, rest)) = text.split_once('-') {
+        let (month, day) = rest.split_once('-')?;
+        (
+            parse_digits(year, 4..=4)?,
+            parse_digits(month, 1..=2)?,
+            parse_digits(day, 1..=2)?,
+        )
+    } else if let Some((day, rest)) = text.split_once(['/', '.']) {
+        let (month, year) = rest.split_once(['/', '.'])?;
+        (
+            parse_digits(year, 4..=4)?,
+            parse_digits(month, 1..=2)?,
+            parse_digits(day, 1..=2)?,
+        )
+    } else {
+        let letters_at = text.find(|c: char| c.is_alphabetic())?;
+        let letters_end = letters_at + text[letters_at..].find(|c: char| !c.is_alphabetic())?;
+        let month = month_number(&text[letters_at..letters_end])?;
+        (
+            parse_digits(&text[letters_end..], 4..=4)?,
+            month,
+            parse_digits(&text[..letters_at], 1..=2)?,
+        )
+    };
+
+    let valid = (1..=12).contains(&month) && day >= 1 && day <= days_in_month(year as i32, month);
+    valid.then_some((year as i32, month, day))
+}
+
+/// Split a trailing `Z` or `±HH:MM` offset off a time
+fn split_offset(text: &str) -> Option<(&str, Option<i32>)> {
+    if let Some(time) = text.strip_suffix(['Z', 'z']) {
+        return Some((time, Some(0)));
+    }
+    match text.rfind(['+', '-']) {
+        Some(index) => {
+            let sign = if text[index..].starts_with('-') { -1 } else { 1 };
+            let offset = text[index + 1..].replace(':', "");
+            let hours: i32 = parse_digits(offset.get(..2)?, 2..=2)? as i32;
+            let minutes: i32 = parse_digits(offset.get(2..).filter(|m| !m.is_empty()).unwrap_or("00"), 2..=2)? as i32;
+            Some((&text[..index], Some(sign * (hours * 60 + minutes))))
+        }
+        None => Some((text, None)),
+    }
+}
+
+fn parse_digits(text: &str, length: std::ops::RangeInclusive<usize>) -> Option<u32> {
+    if !length.contains(&text.len()) || !text.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    text.parse().ok()
+}
+
+/// English and French month abbreviations, as in `15NOV2025`
+fn month_number(name: &str) -> Option<u32> {
+    let month = match name.to_uppercase().as_str() {
+        "JAN" | "JANV" => 1,
+        "FEB" | "FEV" | "FÉV" | "FEVR" | "FÉVR" => 2,
+        "MAR" | "MARS" => 3,
+        "APR" | "AVR" => 4,
+        "MAY" | "MAI" => 5,
+        "JUN" | "JUIN" => 6,
+        "JUL" | "JUIL" => 7,
+        "AUG" | "AOU" | "AOÛ" | "AOUT" | "AOÛT" => 8,
+        "SEP" | "SEPT" => 9,
+        "OCT" => 10,
+        "NOV" => 11,
+        "DEC" | "DÉC" => 12,
+        _ => return None,
+    };
+    Some(month)
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Days since 1970-01-01 (proleptic Gregorian calendar)
+fn days_from_civil(year: i32, month: u32, day: u32) -> i64 {
+    let year = i64::from(year) - i64::from(month <= 2);
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = i64::from(month);
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Inverse of [`days_from_civil`]
+fn civil_from_days(days: i64) -> (i32, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = (if mp < 10 { mp + 3 } else { mp - 9 }) as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year as i32, month, day)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Axis {
+    Latitude,
+    Longitude,
+}
+
+/// `45.234N`, `N45.234`, `-2.45`, `45°14'02"N`, `1.4O` (ouest)
+fn parse_angle(text: &str) -> Option<(f64, Option<Axis>)> {
+    let hemisphere = |c: char| match c.to_ascii_uppercase() {
+        'N' => Some((Axis::Latitude, 1.0)),
+        'S' => Some((Axis::Latitude, -1.0)),
+        'E' => Some((Axis::Longitude, 1.0)),
+        'W' | 'O' => Some((Axis::Longitude, -1.0)),
+        _ => None,
+    };
+
+    let (body, marker) = match (text.chars().next(), text.chars().last()) {
+        (_, Some(last)) if hemisphere(last).is_some() => (&text[..text.len() - 1], hemisphere(last)),
+        (Some(first), _) if hemisphere(first).is_some() => (&text[1..], hemisphere(first)),
+        _ => (text, None),
+    };
+
+    let mut value = 0.0;
+    let mut scale = 1.0;
+    let mut rest = body.trim();
+    let negative = rest.starts_with('-');
+    rest = rest.trim_start_matches(['-', '+']);
+    while !rest.is_empty() {
+        let (number, after) = split_number(rest)?;
+        value += number / scale;
+        scale *= 60.0;
+        rest = after.trim_start_matches(['°', '\'', '"', '′', '″']).trim_start();
+        if scale > 3600.0 && !rest.is_empty() {
+            return None;
+        }
+    }
+    if scale == 1.0 || (negative && marker.is_some()) {
+        return None;
+    }
+
+    let sign = marker.map_or(1.0, |(_, sign)| sign) * if negative { -1.0 } else { 1.0 };
+    Some((sign * value, marker.map(|(axis, _)| axis)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_mml;
+
+    fn value(raw: &str) -> MetadataValue<'_> {
+        MetadataValue::new(raw)
+    }
+
+    #[test]
+    fn test_numbers() {
+        assert_eq!(value("45").as_number(), Some(45.0));
+        assert_eq!(value("-35").as_number(), Some(-35.0));
+        assert_eq!(value("1013.2").as_number(), Some(1013.2));
+        assert_eq!(value("23,4").as_number(), Some(23.4));
+        assert_eq!(value("1 500").as_number(), Some(1500.0));
+        assert_eq!(value("45 hectares").as_number(), None);
+        assert_eq!(value("INC-2025-001").as_number(), None);
+    }
+
+    #[test]
+    fn test_quantities() {
+        let quantity = |raw| {
+            let q = value(raw).as_quantity().unwrap();
+            (q.value, q.unit, q.qualifier)
+        };
+
+        assert_eq!(quantity("450 hectares"), (450.0, "hectares".to_string(), None));
+        assert_eq!(
+            quantity("25 km/h NNE"),
+            (25.0, "km/h".to_string(), Some("NNE".to_string()))
+        );
+        assert_eq!(quantity("23.4°C"), (23.4, "°C".to_string(), None));
+        assert_eq!(quantity("-35°C"), (-35.0, "°C".to_string(), None));
+        assert_eq!(quantity("5km²"), (5.0, "km²".to_string(), None));
+        assert_eq!(quantity("120 pompiers"), (120.0, "pompiers".to_string(), None));
+        assert_eq!(value("1000-5000").as_quantity(), None);
+        assert_eq!(value("CRITIQUE").as_quantity(), None);
+
+        assert_eq!(value("87%").as_percentage(), Some(87.0));
+        assert_eq!(value("15 %").as_percentage(), Some(15.0));
+        assert_eq!(value("80% DETRUITS").as_percentage(), Some(80.0));
+        assert_eq!(value("87").as_percentage(), None);
+    }
+
+    #[test]
+    fn test_timestamps() {
+        let unix = value("1640995200").as_timestamp().unwrap();
+        assert_eq!(unix.date, Some((2022, 1, 1)));
+        assert_eq!(unix.unix_seconds(), Some(1_640_995_200));
+
+        let iso = value("2025-11-15T14:30:00Z").as_timestamp().unwrap();
+        assert_eq!((iso.date, iso.time), (Some((2025, 11, 15)), Some((14, 30, 0))));
+        assert_eq!(iso.unix_seconds(), Some(1_763_217_000));
+        let paris = value("2025-11-15T15:30:00+01:00").as_timestamp().unwrap();
+        assert_eq!(paris.unix_seconds(), iso.unix_seconds());
+
+        let time = value("14h30").as_timestamp().unwrap();
+        assert_eq!((time.date, time.time), (None, Some((14, 30, 0))));
+        assert_eq!(time.unix_seconds(), None);
+        assert_eq!(value("9h").as_timestamp().unwrap().time, Some((9, 0, 0)));
+        assert_eq!(value("14:30").as_timestamp().unwrap().time, Some((14, 30, 0)));
+
+        assert_eq!(value("15NOV2025").as_timestamp().unwrap().date, Some((2025, 11, 15)));
+        assert_eq!(value("15/11/2025").as_timestamp().unwrap().date, Some((2025, 11, 15)));
+        assert_eq!(value("2025-02-30").as_timestamp(), None);
+        assert_eq!(value("25h30").as_timestamp(), None);
+        assert_eq!(value("Hier soir").as_timestamp(), None);
+    }
+
+    #[test]
+    fn test_coordinates() {
+        let coordinate = |raw| value(raw).as_coordinate().map(|c| (c.latitude, c.longitude));
+
+        assert_eq!(coordinate("45.234N 2.456E"), Some((45.234, 2.456)));
+        assert_eq!(coordinate("43.5N 1.4W"), Some((43.5, -1.4)));
+        assert_eq!(coordinate("45.23N,2.45E"), Some((45.23, 2.45)));
+        assert_eq!(coordinate("78.2S 45.1E"), Some((-78.2, 45.1)));
+        assert_eq!(coordinate("48.8566, 2.3522"), Some((48.8566, 2.3522)));
+        assert_eq!(coordinate("1.4O 43.5N"), Some((43.5, -1.4)));
+        assert_eq!(coordinate("45°30'N 2°15'E"), Some((45.5, 2.25)));
+        assert_eq!(coordinate("95N 2E"), None);
+        assert_eq!(coordinate("45N 2N"), None);
+        assert_eq!(coordinate("48.85,2.35"), Some((48.85, 2.35)));
+        assert_eq!(coordinate("23,4"), None);
+        assert_eq!(coordinate("Secteur Alpha"), None);
+    }
+
+    #[test]
+    fn test_booleans_and_inference() {
+        assert_eq!(value("Oui").as_bool(), Some(true));
+        assert_eq!(value("actif").as_bool(), Some(true));
+        assert_eq!(value("non").as_bool(), Some(false));
+        assert_eq!(value("CRITIQUE").as_bool(), None);
+
+        assert_eq!(value("45").infer(), TypedValue::Number(45.0));
+        assert_eq!(value("23,4").infer(), TypedValue::Number(23.4));
+        assert_eq!(value("87%").infer(), TypedValue::Percentage(87.0));
+        assert!(matches!(value("25 km/h NNE").infer(), TypedValue::Quantity(_)));
+        assert!(matches!(value("14h30").infer(), TypedValue::Timestamp(_)));
+        assert!(matches!(value("45.2N 2.1E").infer(), TypedValue::Coordinate(_)));
+        assert_eq!(value("actif").infer(), TypedValue::Bool(true));
+        assert_eq!(
+            value("Forêt domaniale").infer(),
+            TypedValue::Text("Forêt domaniale".to_string())
+        );
+    }
+
+    #[test]
+    fn test_document_accessors() {
+        let document = parse_mml("M:TIMESTAMP|1640995200\nH:Situation\nM:Surface|450 hectares").unwrap();
+
+        assert!(document.metadata_value("TIMESTAMP").unwrap().as_timestamp().is_some());
+        assert_eq!(
+            document.sections[0]
+                .metadata_value("Surface")
+                .unwrap()
+                .as_quantity()
+                .unwrap()
+                .value,
+            450.0
+        );
+        assert!(document.metadata_value("Absent").is_none());
+    }
+}
@@ -0,0 +1,331 @@
+//! CW audio synthesis
+//!
+//! A sine tone is keyed on and off following the ITU timing of annexe C.
+//! Each element rises and falls along a raised cosine instead of starting
+//! abruptly, which would be heard as a click and spread over neighbouring
+//! frequencies on air.
+
+use super::{split, unit_duration};
+use crate::error::{MMLError, MMLResult};
+use std::time::Duration;
+
+/// Most samples [`synthesize`] produces: a WAV file stores its sizes on
+/// 32 bits
+pub const MAX_SAMPLES: usize = ((u32::MAX - 36) / 2) as usize;
+
+/// Element and gap lengths, in seconds
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Timing {
+    /// Dot, gap between the elements of a letter; a dash is three dots
+    pub dot: f64,
+    /// Gap between letters
+    pub letter_gap: f64,
+    /// Gap between words
+    pub word_gap: f64,
+}
+
+impl Timing {
+    /// Standard timing at `wpm` words per minute (PARIS): gaps of 3 and 7
+    /// dots
+    pub fn new(wpm: f64) -> Self {
+        let dot = 1.2 / wpm;
+        Self {
+            dot,
+            letter_gap: 3.0 * dot,
+            word_gap: 7.0 * dot,
+        }
+    }
+
+    /// Farnsworth timing: letters sent at `wpm`, gaps stretched so that
+    /// text goes at `effective_wpm` overall
+    ///
+    /// Learners hear letters at full speed and get time between them. An
+    /// effective speed at or above `wpm` gives the standard timing.
+    pub fn farnsworth(wpm: f64, effective_wpm: f64) -> Self {
+        if effective_wpm >= wpm {
+            return Self::new(wpm);
+        }
+        // PARIS has 31 units of letters and 19 of gaps (4 × 3 + 7), which
+        // take what is left of the minute
+        let gaps = (60.0 * wpm - 37.2 * effective_wpm) / (effective_wpm * wpm);
+        Self {
+            dot: 1.2 / wpm,
+            letter_gap: 3.0 * gaps / 19.0,
+            word_gap: 7.0 * gaps / 19.0,
+        }
+    }
+
+    /// Key-down (`true`) and key-up periods of Morse notation, in seconds
+    pub fn keying(&self, morse: &str) -> Vec<(bool, f64)> {
+        let mut keying = Vec::new();
+        for (w, word) in split(morse).iter().enumerate() {
+            if w > 0 {
+                keying.push((false, self.word_gap));
+            }
+            for (l, letter) in word.iter().enumerate() {
+                if l > 0 {
+                    keying.push((false, self.letter_gap));
+                }
+                let elements: Vec<_> = letter.chars().filter(|c| *c == '.' || *c == '-').collect();
+                for (e, element) in elements.iter().enumerate() {
+                    if e > 0 {
+                        keying.push((false, self.dot));
+                    }
+                    keying.push((true, if *element == '-' { 3.0 * self.dot } else { self.dot }));
+                }
+            }
+        }
+        keying
+    }
+
+    /// Time needed to send Morse notation
+    ///
+    /// Fails when the timing comes from speeds that give no finite
+    /// duration, such as a zero or NaN speed.
+    pub fn duration(&self, morse: &str) -> MMLResult<Duration> {
+        let seconds: f64 = self.keying(morse).iter().map(|(_, seconds)| seconds).sum();
+        Duration::try_from_secs_f64(seconds).map_err(|_| MMLError::ValidationError {
+            field: "timing".to_string(),
+            value: seconds.to_string(),
+            reason: "not a valid duration".to_string(),
+            suggestion: "Check the CW speeds with CwOptions::validate".to_string(),
+        })
+    }
+}
+
+/// Options for [`synthesize`]
+#[derive(Debug, Clone)]
+pub struct CwOptions {
+    /// Letter speed in words per minute
+    pub wpm: f64,
+    /// Overall speed for Farnsworth spacing, below `wpm`
+    pub farnsworth: Option<f64>,
+    /// Tone frequency in Hz
+    pub tone: f64,
+    /// Samples per second; 8000 Hz covers the audio passband of a radio
+    pub sample_rate: u32,
+    /// Rise and fall time of each element
+    pub rise: Duration,
+    /// Peak level, from 0 to 1
+    pub amplitude: f64,
+}
+
+impl Default for CwOptions {
+    fn default() -> Self {
+        Self {
+            wpm: 20.0,
+            farnsworth: None,
+            tone: 700.0,
+            sample_rate: 8000,
+            rise: Duration::from_millis(5),
+            amplitude: 0.8,
+        }
+    }
+}
+
+impl CwOptions {
+    /// Timing for these speeds
+    pub fn timing(&self) -> Timing {
+        match self.farnsworth {
+            Some(effective_wpm) => Timing::farnsworth(self.wpm, effective_wpm),
+            None => Timing::new(self.wpm),
+        }
+    }
+
+    /// Check the speeds, tone and amplitude before synthesis
+    ///
+    /// Speeds have the bounds of [`transmission_time`](super::transmission_time):
+    /// finite, positive and slow enough for a dot to be a [`Duration`].
+    pub fn validate(&self) -> MMLResult<()> {
+        let invalid = |field: &str, value: f64, reason: &str| MMLError::ValidationError {
+            field: field.to_string(),
+            value: format!("{:?}", value),
+            reason: reason.to_string(),
+            suggestion: "Check the CW options".to_string(),
+        };
+        let speed = |field: &str, wpm: f64| match unit_duration(wpm, 1) {
+            Err(MMLError::ValidationError { reason, .. }) => Err(invalid(field, wpm, &reason)),
+            other => other.map(drop),
+        };
+        speed("wpm", self.wpm)?;
+        if let Some(effective_wpm) = self.farnsworth {
+            speed("farnsworth", effective_wpm)?;
+        }
+        if !self.tone.is_finite() || self.tone <= 0.0 || self.tone >= self.sample_rate as f64 / 2.0 {
+            return Err(invalid("tone", self.tone, "tone must be below half the sample rate"));
+        }
+        if !(0.0..=1.0).contains(&self.amplitude) {
+            return Err(invalid(
+                "amplitude",
+                self.amplitude,
+                "amplitude must be between 0 and 1",
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Synthesize Morse notation as 16-bit samples
+///
+/// A word gap of silence comes before and after the message, so that a
+/// transmitter's VOX or a decoder has time to settle. Fails with
+/// [`MMLError::LimitExceeded`] when the audio would not fit in a WAV file.
+pub fn synthesize(morse: &str, options: &CwOptions) -> MMLResult<Vec<i16>> {
+    options.validate()?;
+    let timing = options.timing();
+    let rate = options.sample_rate as f64;
+
+    // Sized before allocating: a slow Farnsworth speed can ask for years
+    let seconds = timing.duration(morse)?.as_secs_f64() + 2.0 * timing.word_gap;
+    let total = (seconds * rate).round() as usize;
+    if total > MAX_SAMPLES {
+        return Err(MMLError::LimitExceeded {
+            limit_type: "CW samples".to_string(),
+            current: total,
+            maximum: MAX_SAMPLES,
+            suggestion: "Raise the Farnsworth speed or send a shorter text".to_string(),
+        });
+    }
+    let peak = options.amplitude * i16::MAX as f64;
+    let rise = (options.rise.as_secs_f64() * rate).round() as usize;
+
+    let mut keying = vec![(false, timing.word_gap)];
+    keying.extend(timing.keying(morse));
+    keying.push((false, timing.word_gap));
+
+    // Sample boundaries come from the running time, so rounding does not
+    // accumulate over a long message
+    let mut samples = Vec::with_capacity(total);
+    let mut time = 0.0;
+    for (on, seconds) in keying {
+        time += seconds;
+        let end = (time * rate).round() as usize;
+        let start = samples.len();
+        let length = end.saturating_sub(start);
+        if !on {
+            samples.resize(end.max(start), 0);
+            continue;
+        }
+        let ramp = rise.min(length / 2);
+        for i in 0..length {
+            let edge = i.min(length - 1 - i);
+            let gain = if edge < ramp {
+                0.5 - 0.5 * (std::f64::consts::PI * edge as f64 / ramp as f64).cos()
+            } else {
+                1.0
+            };
+            let phase = 2.0 * std::f64::consts::PI * options.tone * (start + i) as f64 / rate;
+            samples.push((peak * gain * phase.sin()).round() as i16);
+        }
+    }
+    Ok(samples)
+}
+
+/// Synthesize Morse notation as a WAV file
+pub fn to_wav(morse: &str, options: &CwOptions) -> MMLResult<Vec<u8>> {
+    let samples = synthesize(morse, options)?;
+    let mut bytes = Vec::new();
+    super::wav::write(&mut bytes, &samples, options.sample_rate)?;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::morse::encode;
+
+    #[test]
+    fn test_timing() {
+        let paris = encode("PARIS").unwrap();
+
+        let standard = Timing::new(20.0);
+        assert!((standard.dot - 0.06).abs() < 1e-9);
+        let word = standard.duration(&paris).unwrap().as_secs_f64() + standard.word_gap;
+        assert!((word - 3.0).abs() < 1e-9);
+
+        // 18 WPM letters, 10 WPM overall: PARIS still takes 6 seconds
+        let farnsworth = Timing::farnsworth(18.0, 10.0);
+        assert!((farnsworth.dot - 1.2 / 18.0).abs() < 1e-9);
+        let word = farnsworth.duration(&paris).unwrap().as_secs_f64() + farnsworth.word_gap;
+        assert!((word - 6.0).abs() < 1e-9);
+
+        assert_eq!(Timing::farnsworth(18.0, 25.0), Timing::new(18.0));
+        assert!(Timing::farnsworth(20.0, 0.0).duration(&paris).is_err());
+        assert!(Timing::new(f64::NAN).duration(&paris).is_err());
+    }
+
+    #[test]
+    fn test_keyed_envelope() {
+        let options = CwOptions::default();
+        let samples = synthesize("- .", &options).unwrap();
+
+        // Word gap, dash (3 dots), letter gap (3 dots), dot, word gap
+        let dot = 480;
+        assert_eq!(samples.len(), 7 * dot + 3 * dot + 3 * dot + dot + 7 * dot);
+
+        let dash = &samples[7 * dot..10 * dot];
+        assert!(samples[..7 * dot].iter().all(|s| *s == 0));
+        assert!(dash[0].abs() < 100 && dash[dash.len() - 1].abs() < 100);
+        let peak = dash.iter().map(|s| s.unsigned_abs()).max().unwrap();
+        assert!(peak > 26_000 && peak <= 26_214);
+
+        // No click: the level never jumps by more than the tone itself does
+        let max_step = 2.0 * std::f64::consts::PI * 700.0 / 8000.0 * 26_214.0;
+        assert!(samples
+            .windows(2)
+            .all(|w| ((w[1] as f64) - (w[0] as f64)).abs() <= max_step + 1.0));
+    }
+
+    #[test]
+    fn test_invalid_options() {
+        let options = CwOptions {
+            tone: 5000.0,
+            ..CwOptions::default()
+        };
+        assert!(synthesize(".", &options).is_err());
+
+        let options = CwOptions {
+            wpm: 0.0,
+            ..CwOptions::default()
+        };
+        assert!(to_wav(".", &options).is_err());
+
+        let options = CwOptions {
+            farnsworth: Some(0.0),
+            ..CwOptions::default()
+        };
+        assert!(options.validate().is_err());
+    }
+
+    #[test]
+    fn test_extreme_speeds() {
+        let speeds = [
+            (1e-300, None),
+            (20.0, Some(1e-300)),
+            (f64::INFINITY, None),
+            (20.0, Some(f64::INFINITY)),
+        ];
+        for (wpm, farnsworth) in speeds {
+            let options = CwOptions {
+                wpm,
+                farnsworth,
+                ..CwOptions::default()
+            };
+            assert!(
+                matches!(options.validate(), Err(MMLError::ValidationError { .. })),
+                "{} {:?}",
+                wpm,
+                farnsworth
+            );
+            assert!(to_wav(".", &options).is_err());
+        }
+
+        // Valid speeds, but the gaps would last for months
+        let options = CwOptions {
+            farnsworth: Some(1e-6),
+            ..CwOptions::default()
+        };
+        assert!(options.validate().is_ok());
+        assert!(matches!(synthesize(". .", &options), Err(MMLError::LimitExceeded { .. })));
+    }
+}
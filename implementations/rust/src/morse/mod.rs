@@ -0,0 +1,470 @@
+//! International Morse code (annexe C, ITU-R M.1677-1)
+//!
+//! [`encode`] writes dots and dashes with one space between letters and
+//! ` / ` between words, the notation of chapter 8.4. Line breaks are sent
+//! as the `AA` prosign (`.-.-`) and `|`, which ITU does not define, as
+//! `.-..-`, a code ITU leaves unassigned, so that an MML or MMLC document
+//! survives the round trip. Prosigns are written `<SK>` in text.
+//!
+//! Morse has no case: decoded text is in capitals, which MML tags are
+//! anyway.
+//!
+//! [`audio`] keys a tone with the same timing and writes it as a WAV file,
+//! for training or for a radio fed through a sound card; [`receive`] reads
+//! such a recording back.
+//!
+//! # Example
+//!
+//! ```rust
+//! use mml_parser::morse;
+//!
+//! let code = morse::encode("T:Alerte\nM:Victimes|8").unwrap();
+//! assert!(code.starts_with("- ---... .- .-.. . .-. - . / .-.- / --"));
+//!
+//! let decoded = morse::decode(&code);
+//! assert_eq!(decoded.text, "T:ALERTE\nM:VICTIMES|8");
+//! assert!(decoded.uncertain.is_empty());
+//! ```
+
+use crate::error::{MMLError, MMLResult};
+use crate::unicode::to_ascii;
+use std::time::Duration;
+
+pub mod audio;
+pub mod receive;
+pub mod wav;
+
+pub use audio::{synthesize, to_wav, CwOptions, Timing};
+pub use receive::{receive, receive_wav, ReceiveOptions, Reception};
+
+/// Characters and their codes
+pub const CODE: &[(char, &str)] = &[
+    ('A', ".-"),
+    ('B', "-..."),
+    ('C', "-.-."),
+    ('D', "-.."),
+    ('E', "."),
+    ('F', "..-."),
+    ('G', "--."),
+    ('H', "...."),
+    ('I', ".."),
+    ('J', ".---"),
+    ('K', "-.-"),
+    ('L', ".-.."),
+    ('M', "--"),
+    ('N', "-."),
+    ('O', "---"),
+    ('P', ".--."),
+    ('Q', "--.-"),
+    ('R', ".-."),
+    ('S', "..."),
+    ('T', "-"),
+    ('U', "..-"),
+    ('V', "...-"),
+    ('W', ".--"),
+    ('X', "-..-"),
+    ('Y', "-.--"),
+    ('Z', "--.."),
+    ('É', "..-.."),
+    ('0', "-----"),
+    ('1', ".----"),
+    ('2', "..---"),
+    ('3', "...--"),
+    ('4', "....-"),
+    ('5', "....."),
+    ('6', "-...."),
+    ('7', "--..."),
+    ('8', "---.."),
+    ('9', "----."),
+    ('.', ".-.-.-"),
+    (',', "--..--"),
+    (':', "---..."),
+    ('?', "..--.."),
+    ('\'', ".----."),
+    ('-', "-....-"),
+    ('/', "-..-."),
+    ('(', "-.--."),
+    (')', "-.--.-"),
+    ('"', ".-..-."),
+    ('=', "-...-"),
+    ('+', ".-.-."),
+    ('@', ".--.-."),
+    // Not in ITU-R M.1677-1 but in common use (annexe C)
+    (';', "-.-.-."),
+    ('!', "-.-.--"),
+    ('&', ".-..."),
+    ('_', "..--.-"),
+    ('$', "...-..-"),
+    // MML field separator
+    ('|', ".-..-"),
+];
+
+/// Procedure signals, sent as one character
+///
+/// `AR`, `AS`, `BT` and `KN` share their code with `+`, `&`, `=` and `(`,
+/// which is what [`decode`] reads them as.
+pub const PROSIGNS: &[(&str, &str)] = &[
+    ("AA", NEW_LINE),
+    ("AR", ".-.-."),
+    ("AS", ".-..."),
+    ("BK", "-...-.-"),
+    ("BT", "-...-"),
+    ("CL", "-.-..-.."),
+    ("CT", "-.-.-"),
+    ("HH", "........"),
+    ("KN", "-.--."),
+    ("SK", "...-.-"),
+    ("SN", "...-."),
+    ("SOS", "...---..."),
+];
+
+/// Code of a line break: the `AA` prosign
+pub const NEW_LINE: &str = ".-.-";
+
+/// Units in the standard word `PARIS`, word gap included
+pub const PARIS_UNITS: usize = 50;
+
+/// Code read without certainty
+#[derive(Debug, Clone, PartialEq)]
+pub struct Uncertain {
+    /// Char offset of the reading in [`Decoded::text`]
+    pub offset: usize,
+    /// What was written for it
+    pub reading: String,
+    /// Dots and dashes received
+    pub code: String,
+}
+
+/// Result of [`decode`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Decoded {
+    /// Decoded text, in capitals
+    pub text: String,
+    /// Codes that are not in the table, read as the shortest sequence of
+    /// known characters (letter gaps probably lost)
+    pub uncertain: Vec<Uncertain>,
+}
+
+/// Encode text as Morse
+///
+/// Letters are case-insensitive; accents other than `É` are dropped.
+/// Characters without a code are an error.
+pub fn encode(text: &str) -> MMLResult<String> {
+    let mut words = Vec::new();
+
+    for (index, line) in text.trim_end().lines().enumerate() {
+        if index > 0 {
+            words.push(NEW_LINE.to_string());
+        }
+        for word in line.split_whitespace() {
+            words.push(encode_word(word, index + 1)?);
+        }
+    }
+
+    Ok(words.join(" / "))
+}
+
+fn encode_word(word: &str, line: usize) -> MMLResult<String> {
+    let mut letters = Vec::new();
+    let mut rest = word;
+
+    while let Some(c) = rest.chars().next() {
+        if let Some((name, code)) = PROSIGNS.iter().find(|(name, _)| {
+            rest.strip_prefix('<')
+                .and_then(|r| r.strip_prefix(*name))
+                .is_some_and(|r| r.starts_with('>'))
+        }) {
+            letters.push(*code);
+            rest = &rest[name.len() + 2..];
+            continue;
+        }
+        rest = &rest[c.len_utf8()..];
+
+        for c in c.to_uppercase() {
+            if let Some(code) = code_of(c) {
+                letters.push(code);
+                continue;
+            }
+            let ascii = to_ascii(&c.to_string());
+            let codes: Option<Vec<_>> = ascii.to_uppercase().chars().map(code_of).collect();
+            match codes {
+                Some(codes) if ascii != "?" => letters.extend(codes),
+                _ => {
+                    return Err(MMLError::EncodingError {
+                        operation: "Morse encoding".to_string(),
+                        charset: "ITU-R M.1677-1".to_string(),
+                        error: format!("'{}' at line {} has no Morse code", c, line),
+                        suggestion: "Spell the character out or remove it".to_string(),
+                    })
+                }
+            }
+        }
+    }
+
+    Ok(letters.join(" "))
+}
+
+fn code_of(c: char) -> Option<&'static str> {
+    CODE.iter().find(|(letter, _)| *letter == c).map(|(_, code)| *code)
+}
+
+/// Text for a code: a character, a line break or a prosign
+fn reading_of(code: &str) -> Option<String> {
+    if code == NEW_LINE {
+        return Some("\n".to_string());
+    }
+    if let Some((letter, _)) = CODE.iter().find(|(_, c)| *c == code) {
+        return Some(letter.to_string());
+    }
+    PROSIGNS
+        .iter()
+        .find(|(_, c)| *c == code)
+        .map(|(name, _)| format!("<{}>", name))
+}
+
+/// Split Morse notation into words of letter codes
+///
+/// Dots may be written `.`, `·`, `•` or `*`, dashes `-`, `_`, `–`, `—` or
+/// `−`. Words are separated by `/`, a line break or two spaces or more,
+/// since hand-typed gaps are rarely exact; letters by a single space.
+/// Anything else is kept in the code, which then matches no character.
+pub fn split(morse: &str) -> Vec<Vec<String>> {
+    let mut words = Vec::new();
+    let mut word = Vec::new();
+    let mut letter = String::new();
+    let mut spaces = 0;
+
+    fn end_letter(letter: &mut String, word: &mut Vec<String>) {
+        if !letter.is_empty() {
+            word.push(std::mem::take(letter));
+        }
+    }
+
+    for c in morse.chars() {
+        match c {
+            ' ' | '\t' => {
+                spaces += 1;
+                end_letter(&mut letter, &mut word);
+                continue;
+            }
+            '/' | '\n' | '\r' => {
+                spaces = 2;
+                end_letter(&mut letter, &mut word);
+                continue;
+            }
+            _ => {}
+        }
+        if spaces >= 2 && !word.is_empty() {
+            words.push(std::mem::take(&mut word));
+        }
+        spaces = 0;
+        letter.push(match c {
+            '.' | '·' | '•' | '*' => '.',
+            '-' | '_' | '–' | '—' | '−' => '-',
+            other => other,
+        });
+    }
+    end_letter(&mut letter, &mut word);
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+/// Decode Morse notation (see [`split`] for what is accepted)
+///
+/// Never fails: a code that is not in the table is read as the fewest
+/// known characters it can be cut into, and listed in
+/// [`Decoded::uncertain`].
+pub fn decode(morse: &str) -> Decoded {
+    let words: Vec<Vec<(String, bool)>> = split(morse)
+        .into_iter()
+        .map(|word| word.into_iter().map(|code| (code, false)).collect())
+        .collect();
+    decode_words(&words)
+}
+
+/// Decode words of `(code, uncertain)` letters
+pub(crate) fn decode_words(words: &[Vec<(String, bool)>]) -> Decoded {
+    let mut text = String::new();
+    let mut length = 0;
+    let mut uncertain = Vec::new();
+
+    for word in words {
+        let new_line = word.len() == 1 && word[0].0 == NEW_LINE;
+        if length > 0 && !new_line && !text.ends_with('\n') {
+            text.push(' ');
+            length += 1;
+        }
+        for (code, doubtful) in word {
+            let (reading, known) = match reading_of(code) {
+                Some(reading) => (reading, true),
+                None => (resegment(code), false),
+            };
+            if !known || *doubtful {
+                uncertain.push(Uncertain {
+                    offset: length,
+                    reading: reading.clone(),
+                    code: code.clone(),
+                });
+            }
+            length += reading.chars().count();
+            text.push_str(&reading);
+        }
+    }
+
+    Decoded { text, uncertain }
+}
+
+/// Fewest known characters `code` can be cut into, or `?` for input that
+/// is not dots and dashes
+fn resegment(code: &str) -> String {
+    if code.is_empty() || !code.chars().all(|c| c == '.' || c == '-') {
+        return "?".to_string();
+    }
+    // best[i]: shortest reading of code[..i]
+    let mut best: Vec<Option<(usize, String)>> = vec![None; code.len() + 1];
+    best[0] = Some((0, String::new()));
+    for end in 1..=code.len() {
+        for start in 0..end {
+            let (Some((count, reading)), Some((letter, _))) =
+                (&best[start], CODE.iter().find(|(_, c)| *c == &code[start..end]))
+            else {
+                continue;
+            };
+            if best[end].as_ref().is_none_or(|(best_count, _)| count + 1 < *best_count) {
+                best[end] = Some((count + 1, format!("{}{}", reading, letter)));
+            }
+        }
+    }
+    best[code.len()].take().map(|(_, reading)| reading).unwrap_or_default()
+}
+
+/// Length of Morse notation in dot units: dot 1, dash 3, gap 1 between
+/// elements, 3 between letters and 7 between words
+pub fn units(morse: &str) -> usize {
+    let words = split(morse);
+    words
+        .iter()
+        .map(|word| {
+            let letters: usize = word
+                .iter()
+                .map(|code| {
+                    let elements: usize = code.chars().map(|c| if c == '-' { 3 } else { 1 }).sum();
+                    elements + code.chars().count() - 1
+                })
+                .sum();
+            letters + 3 * (word.len() - 1)
+        })
+        .sum::<usize>()
+        + 7 * words.len().saturating_sub(1)
+}
+
+/// Duration of a dot at `wpm` words per minute
+///
+/// PARIS standard: a word is 50 units, so a dot lasts 1.2 / `wpm` seconds.
+/// Fails unless `wpm` is positive and gives a representable duration.
+pub fn dot_duration(wpm: f64) -> MMLResult<Duration> {
+    unit_duration(wpm, 1)
+}
+
+/// Time needed to send Morse notation at `wpm` words per minute
+pub fn transmission_time(morse: &str, wpm: f64) -> MMLResult<Duration> {
+    unit_duration(wpm, units(morse))
+}
+
+fn unit_duration(wpm: f64, units: usize) -> MMLResult<Duration> {
+    let invalid = |reason: &str| MMLError::ValidationError {
+        field: "wpm".to_string(),
+        value: wpm.to_string(),
+        reason: reason.to_string(),
+        suggestion: "Use a speed such as 20 words per minute".to_string(),
+    };
+    if !wpm.is_finite() || wpm <= 0.0 {
+        return Err(invalid("speed must be positive"));
+    }
+    Duration::try_from_secs_f64(60.0 / (PARIS_UNITS as f64 * wpm) * units as f64)
+        .map_err(|_| invalid("speed too low for a duration"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_table_is_unambiguous() {
+        for (i, (letter, code)) in CODE.iter().enumerate() {
+            assert!(
+                CODE[i + 1..].iter().all(|(_, other)| other != code),
+                "duplicate code for {}",
+                letter
+            );
+            assert_ne!(*code, NEW_LINE);
+        }
+    }
+
+    #[test]
+    fn test_encode() {
+        assert_eq!(encode("sos").unwrap(), "... --- ...");
+        assert_eq!(
+            encode("Évacuez à 5 km").unwrap(),
+            "..-.. ...- .- -.-. ..- . --.. / .- / ..... / -.- --"
+        );
+        assert_eq!(
+            encode("M:Gravité|3\nE E").unwrap(),
+            "-- ---... --. .-. .- ...- .. - ..-.. .-..- ...-- / .-.- / . / ."
+        );
+        assert_eq!(encode("<SK>").unwrap(), "...-.-");
+        assert_eq!(
+            encode("DE <KN> F5XYZ").unwrap(),
+            "-.. . / -.--. / ..-. ..... -..- -.-- --.."
+        );
+
+        let error = encode("P:ok\nP:#1").unwrap_err();
+        assert!(error.to_string().contains("'#' at line 2"));
+    }
+
+    #[test]
+    fn test_tolerant_decode() {
+        assert_eq!(
+            decode("-- ---...  ..-. ·-·· --- --- -.. .-..- ....-").text,
+            "M: FLOOD|4"
+        );
+        assert_eq!(decode("—·· •   —·—").text, "DE K");
+        assert_eq!(decode("...-.- / ........").text, "<SK> <HH>");
+
+        // A letter gap lost inside `..--`, and a character outside Morse
+        let decoded = decode("... - ..-- / .-x");
+        assert_eq!(decoded.text, "STEW ?");
+        assert_eq!(
+            decoded.uncertain,
+            vec![
+                Uncertain {
+                    offset: 2,
+                    reading: "EW".to_string(),
+                    code: "..--".to_string(),
+                },
+                Uncertain {
+                    offset: 5,
+                    reading: "?".to_string(),
+                    code: ".-x".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_paris_timing() {
+        let paris = encode("PARIS").unwrap();
+        assert_eq!(units(&paris) + 7, PARIS_UNITS);
+        assert_eq!(units(&encode("PARIS PARIS").unwrap()), 93);
+
+        assert_eq!(dot_duration(20.0).unwrap(), Duration::from_millis(60));
+        let time = transmission_time(&encode("PARIS PARIS").unwrap(), 10.0).unwrap();
+        assert_eq!(time.as_millis(), 11_160);
+
+        for wpm in [0.0, -5.0, f64::NAN, f64::INFINITY, 1e-300] {
+            assert!(matches!(dot_duration(wpm), Err(MMLError::ValidationError { .. })));
+        }
+    }
+}
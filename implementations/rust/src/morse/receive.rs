@@ -0,0 +1,426 @@
+//! CW reception from recordings
+//!
+//! The tone is found by scanning the passband, then its level is followed
+//! with a Goertzel filter over short overlapping windows, which ignores
+//! noise outside a narrow band around the tone. Key-down periods are told
+//! apart from silence with a threshold placed between the noise floor and
+//! the tone level, with some hysteresis so that noise on a flank does not
+//! split an element.
+//!
+//! Speed is not given: a first estimate of the dot comes from the whole
+//! recording, then follows the sender element by element. Elements and
+//! gaps that fall close to a decision threshold are reported as uncertain.
+
+use super::{decode_words, Decoded};
+use crate::error::{MMLError, MMLResult};
+use crate::parser::MMLParser;
+use crate::types::MMLDocument;
+use crate::validation::ValidationReport;
+use std::time::Duration;
+
+/// Options for [`receive`]
+#[derive(Debug, Clone)]
+pub struct ReceiveOptions {
+    /// Tone frequency in Hz; found in the 200–2000 Hz band when `None`
+    pub tone: Option<f64>,
+    /// Length of the analysis window: shorter follows faster senders,
+    /// longer rejects more noise
+    pub window: Duration,
+}
+
+impl Default for ReceiveOptions {
+    fn default() -> Self {
+        Self {
+            tone: None,
+            window: Duration::from_millis(10),
+        }
+    }
+}
+
+/// Result of [`receive`]
+#[derive(Debug, Clone)]
+pub struct Reception {
+    /// Text received, with its uncertain characters
+    pub decoded: Decoded,
+    /// Tone frequency in Hz
+    pub tone: f64,
+    /// Letter speed of the sender at the end of the recording
+    pub wpm: f64,
+}
+
+impl Reception {
+    /// Parse the received text as MML, in the error-tolerant mode
+    ///
+    /// Garbled lines are reported as errors instead of aborting; lines with
+    /// an uncertain character get a warning.
+    pub fn to_document(&self) -> (MMLDocument, ValidationReport) {
+        let text = &self.decoded.text;
+        let (document, mut report) = MMLParser::new().parse_tolerant(text);
+
+        for uncertain in &self.decoded.uncertain {
+            let line = text.chars().take(uncertain.offset).filter(|c| *c == '\n').count() + 1;
+            report.warning(
+                Some(line),
+                MMLError::EncodingError {
+                    operation: "CW reception".to_string(),
+                    charset: "ITU-R M.1677-1".to_string(),
+                    error: format!("'{}' uncertain, received as {}", uncertain.reading, uncertain.code),
+                    suggestion: "Ask for a repeat of this line".to_string(),
+                },
+            );
+        }
+        (document, report)
+    }
+}
+
+/// Decode CW from mono samples
+pub fn receive(samples: &[i16], sample_rate: u32, options: &ReceiveOptions) -> MMLResult<Reception> {
+    let rate = sample_rate as f64;
+    let window = ((options.window.as_secs_f64() * rate).round() as usize).max(16);
+    let hop = window / 2;
+    let no_signal = || MMLError::EncodingError {
+        operation: "CW reception".to_string(),
+        charset: "ITU-R M.1677-1".to_string(),
+        error: "no CW tone found".to_string(),
+        suggestion: "Check the recording level or give the tone frequency".to_string(),
+    };
+
+    let tone = match options.tone {
+        Some(tone) => tone,
+        None => find_tone(samples, rate, 4 * window).ok_or_else(no_signal)?,
+    };
+    let levels: Vec<f64> = (0..samples.len().saturating_sub(window) / hop)
+        .map(|i| goertzel(&samples[i * hop..i * hop + window], tone, rate))
+        .collect();
+
+    let (noise, signal) = two_means(&levels).ok_or_else(no_signal)?;
+    if signal < 3.0 * noise.max(1.0) {
+        return Err(no_signal());
+    }
+    let key_down = noise + 0.6 * (signal - noise);
+    let key_up = noise + 0.4 * (signal - noise);
+
+    // Runs of windows with the key down or up
+    let mut runs: Vec<(bool, usize)> = Vec::new();
+    let mut on = false;
+    for level in levels {
+        on = if on { level > key_up } else { level > key_down };
+        match runs.last_mut() {
+            Some((state, length)) if *state == on => *length += 1,
+            _ => runs.push((on, 1)),
+        }
+    }
+    let seconds = hop as f64 / rate;
+    let periods = runs.into_iter().map(|(on, length)| (on, length as f64 * seconds));
+    let periods: Vec<(bool, f64)> = debounce(periods, 1.5 * seconds)
+        .into_iter()
+        .skip_while(|(on, _)| !on)
+        .collect();
+    let end = periods.iter().rposition(|(on, _)| *on).map_or(0, |last| last + 1);
+    // Fades longer than a window still cut slow elements in pieces
+    let shortest = 0.3 * initial_dot(&periods[..end]);
+    let periods = debounce(periods[..end].iter().copied(), shortest);
+
+    let (words, dot) = read_elements(&periods);
+    Ok(Reception {
+        decoded: decode_words(&words),
+        tone,
+        wpm: 1.2 / dot,
+    })
+}
+
+/// Decode CW from a WAV file
+pub fn receive_wav(bytes: &[u8], options: &ReceiveOptions) -> MMLResult<Reception> {
+    let (samples, sample_rate) = super::wav::read(bytes)?;
+    receive(&samples, sample_rate, options)
+}
+
+/// Level of `frequency` in `block`
+fn goertzel(block: &[i16], frequency: f64, rate: f64) -> f64 {
+    let coefficient = 2.0 * (2.0 * std::f64::consts::PI * frequency / rate).cos();
+    let (mut s1, mut s2) = (0.0, 0.0);
+    for &sample in block {
+        let s = sample as f64 + coefficient * s1 - s2;
+        s2 = s1;
+        s1 = s;
+    }
+    (s1 * s1 + s2 * s2 - coefficient * s1 * s2).max(0.0).sqrt()
+}
+
+/// Frequency, in 10 Hz steps, with the most energy over the first 30
+/// seconds
+fn find_tone(samples: &[i16], rate: f64, block: usize) -> Option<f64> {
+    let samples = &samples[..samples.len().min(30 * rate as usize)];
+    let top = 2000.0_f64.min(rate / 2.0 - 100.0);
+    (20..)
+        .map(|step| step as f64 * 10.0)
+        .take_while(|frequency| *frequency <= top)
+        .map(|frequency| {
+            let energy: f64 = samples.chunks_exact(block).map(|b| goertzel(b, frequency, rate)).sum();
+            (frequency, energy)
+        })
+        .filter(|(_, energy)| *energy > 0.0)
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(frequency, _)| frequency)
+}
+
+/// Means of the lower and upper of two clusters of `values`
+fn two_means(values: &[f64]) -> Option<(f64, f64)> {
+    let mut low = values.iter().copied().reduce(f64::min)?;
+    let mut high = values.iter().copied().reduce(f64::max)?;
+    for _ in 0..32 {
+        let middle = (low + high) / 2.0;
+        let mean = |upper: bool| {
+            let part: Vec<_> = values.iter().filter(|v| (**v >= middle) == upper).collect();
+            (!part.is_empty()).then(|| part.iter().copied().sum::<f64>() / part.len() as f64)
+        };
+        let (Some(new_low), Some(new_high)) = (mean(false), mean(true)) else {
+            break;
+        };
+        if new_low == low && new_high == high {
+            break;
+        }
+        (low, high) = (new_low, new_high);
+    }
+    Some((low, high))
+}
+
+/// Merge periods shorter than `shortest`, noise on a flank or a fade,
+/// into their neighbours
+fn debounce(periods: impl Iterator<Item = (bool, f64)>, shortest: f64) -> Vec<(bool, f64)> {
+    let mut merged: Vec<(bool, f64)> = Vec::new();
+    for (on, seconds) in periods {
+        match merged.last_mut() {
+            Some((state, previous)) if *state == on => *previous += seconds,
+            Some((_, previous)) if seconds < shortest => *previous += seconds,
+            _ => merged.push((on, seconds)),
+        }
+    }
+    merged
+}
+
+/// First estimate of the dot, in seconds
+///
+/// Dots and dashes form two clusters three times apart. When all elements
+/// are alike, the gaps inside letters, which last one dot, tell which.
+fn initial_dot(periods: &[(bool, f64)]) -> f64 {
+    let marks: Vec<f64> = periods.iter().filter(|(on, _)| *on).map(|(_, s)| s.ln()).collect();
+    let Some((short, long)) = two_means(&marks) else {
+        return 0.06;
+    };
+    let (short, long) = (short.exp(), long.exp());
+    if long > 2.0 * short {
+        return (short + long / 3.0) / 2.0;
+    }
+    let gaps: Vec<f64> = periods.iter().filter(|(on, _)| !on).map(|(_, s)| s.ln()).collect();
+    match two_means(&gaps) {
+        Some((gap, _)) if short > 2.0 * gap.exp() => short / 3.0,
+        _ => short,
+    }
+}
+
+/// Marks on each side of an element that decide whether it is a dash
+const NEIGHBOURS: usize = 8;
+
+/// Dot or dash for each mark, whether it was borderline, and the dot in
+/// force at each period
+///
+/// The dots and dashes of nearby elements form two clusters, three times
+/// apart at a steady speed; splitting them locally follows a sender who
+/// speeds up or slows down. Where nearby elements are all alike (`HI 5`),
+/// the dot carries over from the previous ones.
+fn classify_marks(periods: &[(bool, f64)]) -> (Vec<(char, bool)>, Vec<f64>) {
+    let marks: Vec<f64> = periods.iter().filter(|(on, _)| *on).map(|(_, s)| s.ln()).collect();
+    let mut dot = initial_dot(periods);
+    let mut elements = Vec::with_capacity(marks.len());
+    let mut dots = Vec::with_capacity(periods.len());
+
+    for &(on, seconds) in periods {
+        if on {
+            let index = elements.len();
+            let nearby = &marks[index.saturating_sub(NEIGHBOURS)..(index + NEIGHBOURS + 1).min(marks.len())];
+            let threshold = match two_means(nearby) {
+                Some((short, long)) if long - short > 1.5_f64.ln() => {
+                    dot = (short.exp() + long.exp() / 3.0) / 2.0;
+                    ((short + long) / 2.0).exp()
+                }
+                _ => {
+                    let threshold = 2.0 * dot;
+                    let element = if seconds < threshold {
+                        seconds
+                    } else {
+                        seconds.min(5.0 * dot) / 3.0
+                    };
+                    dot = 0.85 * dot + 0.15 * element;
+                    threshold
+                }
+            };
+            // Borderline between dot and dash, or a key held far too long
+            let doubtful = (seconds / threshold - 1.0).abs() < 0.2 || seconds > 5.0 * dot;
+            elements.push((if seconds < threshold { '.' } else { '-' }, doubtful));
+        }
+        dots.push(dot);
+    }
+    (elements, dots)
+}
+
+/// Gap length, in dots, above which a gap separates words
+///
+/// Gaps longer than an element gap form two clusters, letters and words;
+/// with Farnsworth spacing both are longer than 3 and 7 dots.
+fn word_threshold(periods: &[(bool, f64)], dots: &[f64]) -> f64 {
+    let gaps: Vec<f64> = periods
+        .iter()
+        .zip(dots)
+        .filter(|((on, seconds), dot)| !on && *seconds > 2.0 * *dot)
+        .map(|((_, seconds), dot)| (seconds / dot).ln())
+        .collect();
+    match two_means(&gaps) {
+        Some((letter, word)) if word - letter > 1.6_f64.ln() => ((letter + word) / 2.0).exp(),
+        _ => 5.0,
+    }
+}
+
+/// Letter codes, grouped in words, with their uncertainty; and the dot at
+/// the end
+fn read_elements(periods: &[(bool, f64)]) -> (Vec<Vec<(String, bool)>>, f64) {
+    let (elements, dots) = classify_marks(periods);
+    let word_gap = word_threshold(periods, &dots);
+    let mut elements = elements.into_iter();
+
+    let mut words = Vec::new();
+    let mut word: Vec<(String, bool)> = Vec::new();
+    let mut letter = String::new();
+    let mut doubtful = false;
+
+    for (&(on, seconds), dot) in periods.iter().zip(&dots) {
+        if on {
+            let (element, borderline) = elements.next().unwrap_or(('.', true));
+            letter.push(element);
+            doubtful |= borderline;
+            continue;
+        }
+        let units = seconds / dot;
+        if units < 2.0 {
+            continue;
+        }
+        // A gap close to a threshold may have joined or split letters
+        let borderline = units < 2.5 || (units / word_gap - 1.0).abs() < 0.2;
+        word.push((std::mem::take(&mut letter), std::mem::take(&mut doubtful)));
+        doubtful = borderline;
+        if units >= word_gap {
+            words.push(std::mem::take(&mut word));
+        }
+    }
+    if !letter.is_empty() {
+        word.push((letter, doubtful));
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    (words, dots.last().copied().unwrap_or(0.06))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::morse::{encode, to_wav, CwOptions};
+
+    /// Add white noise of standard deviation `level` (0 to 1)
+    fn add_noise(samples: &mut [i16], level: f64) {
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        let mut uniform = || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state >> 11) as f64 / (1u64 << 53) as f64
+        };
+        for sample in samples.iter_mut() {
+            let gaussian = (-2.0 * (1.0 - uniform()).ln()).sqrt() * (2.0 * std::f64::consts::PI * uniform()).cos();
+            *sample =
+                (*sample as f64 + gaussian * level * i16::MAX as f64).clamp(i16::MIN as f64, i16::MAX as f64) as i16;
+        }
+    }
+
+    fn noisy_wav(text: &str, options: &CwOptions, noise: f64) -> Vec<u8> {
+        let bytes = to_wav(&encode(text).unwrap(), options).unwrap();
+        let (mut samples, rate) = crate::morse::wav::read(&bytes).unwrap();
+        add_noise(&mut samples, noise);
+        let mut bytes = Vec::new();
+        crate::morse::wav::write(&mut bytes, &samples, rate).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn test_noisy_recording() {
+        let text = "T:ALERTE INONDATION\nM:VICTIMES|8\nP:EVACUATION URGENTE";
+        let options = CwOptions {
+            wpm: 22.0,
+            tone: 650.0,
+            ..CwOptions::default()
+        };
+        let reception = receive_wav(&noisy_wav(text, &options, 0.5), &ReceiveOptions::default()).unwrap();
+
+        assert_eq!(reception.decoded.text, text);
+        assert!(reception.decoded.uncertain.is_empty());
+        assert!((reception.tone - 650.0).abs() <= 20.0);
+        assert!((reception.wpm - 22.0).abs() < 2.0);
+
+        let (document, report) = reception.to_document();
+        assert!(report.is_clean());
+        assert_eq!(document.title.as_deref(), Some("ALERTE INONDATION"));
+    }
+
+    #[test]
+    fn test_speed_changes() {
+        let slow = CwOptions {
+            wpm: 12.0,
+            ..CwOptions::default()
+        };
+        let fast = CwOptions {
+            wpm: 30.0,
+            ..CwOptions::default()
+        };
+        let mut samples = crate::morse::synthesize(&encode("CQ CQ DE F5XYZ").unwrap(), &slow).unwrap();
+        samples.extend(crate::morse::synthesize(&encode("QRU 73").unwrap(), &fast).unwrap());
+        add_noise(&mut samples, 0.2);
+
+        let reception = receive(&samples, 8000, &ReceiveOptions::default()).unwrap();
+        assert_eq!(reception.decoded.text, "CQ CQ DE F5XYZ QRU 73");
+        assert!((reception.wpm - 30.0).abs() < 4.0);
+
+        let farnsworth = CwOptions {
+            wpm: 18.0,
+            farnsworth: Some(8.0),
+            ..CwOptions::default()
+        };
+        let reception = receive_wav(&noisy_wav("H:PONT NORD", &farnsworth, 0.3), &ReceiveOptions::default()).unwrap();
+        assert_eq!(reception.decoded.text, "H:PONT NORD");
+    }
+
+    #[test]
+    fn test_garbled_lines_are_flagged() {
+        let reception = Reception {
+            decoded: crate::morse::decode("- ---... --- -.- / .-.- / ..--..--- / .-.- / .--. ---... --- -.-"),
+            tone: 700.0,
+            wpm: 20.0,
+        };
+        let (document, report) = reception.to_document();
+
+        assert_eq!(document.title.as_deref(), Some("OK"));
+        assert_eq!(document.content, vec!["OK".to_string()]);
+        assert_eq!(report.errors.len(), 1);
+        assert_eq!(report.errors[0].line, Some(2));
+        assert_eq!(
+            report.warnings.iter().map(|w| w.line).collect::<Vec<_>>(),
+            vec![Some(2)]
+        );
+    }
+
+    #[test]
+    fn test_silence() {
+        let mut samples = vec![0; 16_000];
+        add_noise(&mut samples, 0.1);
+        assert!(receive(&samples, 8000, &ReceiveOptions::default()).is_err());
+    }
+}
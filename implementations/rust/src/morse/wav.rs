@@ -0,0 +1,141 @@
+//! WAV files: 16-bit mono PCM out, 8 or 16-bit PCM in
+
+use crate::error::{MMLError, MMLResult};
+use std::io;
+
+/// Write `samples` as a 16-bit mono PCM WAV file
+pub fn write(out: &mut dyn io::Write, samples: &[i16], sample_rate: u32) -> MMLResult<()> {
+    let data_len = samples.len() as u32 * 2;
+    let mut bytes = Vec::with_capacity(44 + data_len as usize);
+
+    bytes.extend_from_slice(b"RIFF");
+    bytes.extend_from_slice(&(36 + data_len).to_le_bytes());
+    bytes.extend_from_slice(b"WAVE");
+
+    bytes.extend_from_slice(b"fmt ");
+    bytes.extend_from_slice(&16u32.to_le_bytes());
+    bytes.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    bytes.extend_from_slice(&1u16.to_le_bytes()); // mono
+    bytes.extend_from_slice(&sample_rate.to_le_bytes());
+    bytes.extend_from_slice(&(sample_rate * 2).to_le_bytes()); // bytes per second
+    bytes.extend_from_slice(&2u16.to_le_bytes()); // bytes per frame
+    bytes.extend_from_slice(&16u16.to_le_bytes()); // bits per sample
+
+    bytes.extend_from_slice(b"data");
+    bytes.extend_from_slice(&data_len.to_le_bytes());
+    for sample in samples {
+        bytes.extend_from_slice(&sample.to_le_bytes());
+    }
+
+    out.write_all(&bytes).map_err(|e| MMLError::IoError {
+        operation: "writing WAV".to_string(),
+        path: "output".to_string(),
+        error: e.to_string(),
+    })
+}
+
+/// Read a PCM WAV file (8 or 16 bits): mono samples and sample rate
+///
+/// Channels are mixed down; chunks other than `fmt ` and `data` are
+/// skipped.
+pub fn read(bytes: &[u8]) -> MMLResult<(Vec<i16>, u32)> {
+    let invalid = |error: &str| MMLError::EncodingError {
+        operation: "reading WAV".to_string(),
+        charset: "PCM".to_string(),
+        error: error.to_string(),
+        suggestion: "Record as 16-bit PCM WAV".to_string(),
+    };
+    if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+        return Err(invalid("not a RIFF/WAVE file"));
+    }
+
+    let mut format = None;
+    let mut data = None;
+    let mut rest = &bytes[12..];
+    while rest.len() >= 8 {
+        let size = u32::from_le_bytes([rest[4], rest[5], rest[6], rest[7]]) as usize;
+        let body = &rest[8..(8 + size).min(rest.len())];
+        match &rest[0..4] {
+            b"fmt " if body.len() >= 16 => format = Some(body),
+            b"data" => data = Some(body),
+            _ => {}
+        }
+        // Chunks are padded to an even length
+        rest = &rest[(8 + size + size % 2).min(rest.len())..];
+    }
+
+    let format = format.ok_or_else(|| invalid("no fmt chunk"))?;
+    let data = data.ok_or_else(|| invalid("no data chunk"))?;
+    let field = |at: usize| u16::from_le_bytes([format[at], format[at + 1]]);
+    let (tag, channels, bits) = (field(0), field(2) as usize, field(14));
+    let sample_rate = u32::from_le_bytes([format[4], format[5], format[6], format[7]]);
+
+    // 0xFFFE: WAVE_FORMAT_EXTENSIBLE, PCM for the bit depths read here
+    if tag != 1 && tag != 0xFFFE {
+        return Err(invalid("compressed WAV"));
+    }
+    if channels == 0 || sample_rate == 0 {
+        return Err(invalid("empty format"));
+    }
+    let width = match bits {
+        8 => 1,
+        16 => 2,
+        _ => return Err(invalid(&format!("{}-bit samples", bits))),
+    };
+
+    let samples = data
+        .chunks_exact(width * channels)
+        .map(|frame| {
+            let sum: i32 = frame
+                .chunks_exact(width)
+                .map(|sample| match sample {
+                    [byte] => (*byte as i32 - 128) << 8,
+                    [low, high] => i16::from_le_bytes([*low, *high]) as i32,
+                    _ => 0,
+                })
+                .sum();
+            (sum / channels as i32) as i16
+        })
+        .collect();
+    Ok((samples, sample_rate))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_header() {
+        let mut bytes = Vec::new();
+        write(&mut bytes, &[0, 1, -1], 8000).unwrap();
+
+        assert_eq!(bytes.len(), 44 + 6);
+        assert_eq!(&bytes[0..4], b"RIFF");
+        assert_eq!(u32::from_le_bytes(bytes[4..8].try_into().unwrap()), 42);
+        assert_eq!(&bytes[8..16], b"WAVEfmt ");
+        assert_eq!(u32::from_le_bytes(bytes[24..28].try_into().unwrap()), 8000);
+        assert_eq!(&bytes[36..40], b"data");
+        assert_eq!(u32::from_le_bytes(bytes[40..44].try_into().unwrap()), 6);
+        assert_eq!(&bytes[44..], &[0, 0, 1, 0, 0xff, 0xff]);
+    }
+
+    #[test]
+    fn test_read() {
+        let mut bytes = Vec::new();
+        write(&mut bytes, &[0, 1000, -1000, i16::MAX], 8000).unwrap();
+        assert_eq!(read(&bytes).unwrap(), (vec![0, 1000, -1000, i16::MAX], 8000));
+
+        // 8-bit stereo with a LIST chunk before the data
+        let mut bytes = b"RIFF\0\0\0\0WAVEfmt ".to_vec();
+        bytes.extend_from_slice(&16u32.to_le_bytes());
+        bytes.extend_from_slice(&[1, 0, 2, 0]);
+        bytes.extend_from_slice(&11025u32.to_le_bytes());
+        bytes.extend_from_slice(&22050u32.to_le_bytes());
+        bytes.extend_from_slice(&[2, 0, 8, 0]);
+        bytes.extend_from_slice(b"LIST\x03\0\0\0abc\0data\x04\0\0\0");
+        bytes.extend_from_slice(&[128, 128, 255, 129]);
+        assert_eq!(read(&bytes).unwrap(), (vec![0, 16384], 11025));
+
+        assert!(read(b"RIFF\0\0\0\0WAVE").is_err());
+    }
+}
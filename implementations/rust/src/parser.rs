@@ -3,7 +3,10 @@
 //! High-performance parser with multiple backend options.
 
 use crate::error::{MMLError, MMLResult};
+use crate::escape::split_fields;
 use crate::types::*;
+use crate::validation::ValidationReport;
+use crate::{MAX_METADATA, MAX_SECTIONS};
 use lazy_static::lazy_static;
 use regex::Regex;
 use std::collections::HashMap;
@@ -20,6 +23,23 @@ pub struct ParserOptions {
     pub max_metadata: usize,
     pub validate_strict: bool,
     pub measure_time: bool,
+    /// Where content found before the first `H:` line ends up
+    pub orphan_policy: OrphanPolicy,
+}
+
+/// Placement policy for `P:`, `C:`, `Q:` and `IMG:` lines that appear
+/// before any `H:` section has been opened
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum OrphanPolicy {
+    /// Keep the content at document level (`document.content`, `document.images`)
+    #[default]
+    AttachToDocument,
+    /// Open a section with the given title and place the content there
+    CreateImplicitSection(String),
+    /// Silently discard the content
+    Drop,
+    /// Reject the document with a structure error
+    Error,
 }
 
 impl Default for ParserOptions {
@@ -29,6 +49,7 @@ impl Default for ParserOptions {
             max_metadata: MAX_METADATA,
             validate_strict: true,
             measure_time: false,
+            orphan_policy: OrphanPolicy::default(),
         }
     }
 }
@@ -46,6 +67,37 @@ impl MMLParser {
 
     /// Parse MML text into a document
     pub fn parse(&self, input: &str) -> MMLResult<MMLDocument> {
+        self.run(input, None)
+    }
+
+    /// Validate MML text without keeping the document
+    ///
+    /// Runs the same pipeline as [`parse`](Self::parse), but records every
+    /// problem instead of stopping at the first one, then checks the
+    /// structure of the result.
+    pub fn validate(&self, input: &str) -> ValidationReport {
+        let mut report = ValidationReport::new();
+        // Never fails when a report is collecting the errors
+        let _ = self.run(input, Some(&mut report));
+        report
+    }
+
+    /// Parse what can be parsed and report the rest
+    ///
+    /// The error-tolerant counterpart of [`parse`](Self::parse), for text
+    /// received over a noisy link: lines that fail are recorded in the
+    /// report, as [`validate`](Self::validate) does, and skipped.
+    pub fn parse_tolerant(&self, input: &str) -> (MMLDocument, ValidationReport) {
+        let mut report = ValidationReport::new();
+        let document = self.run(input, Some(&mut report)).unwrap_or_default();
+        (document, report)
+    }
+
+    /// Shared parse/validate pipeline
+    ///
+    /// With a report, errors are recorded and processing continues; without
+    /// one, the first error aborts (subject to `validate_strict`).
+    fn run(&self, input: &str, mut report: Option<&mut ValidationReport>) -> MMLResult<MMLDocument> {
         let start_time = if self.options.measure_time {
             Some(Instant::now())
         } else {
@@ -53,7 +105,7 @@ impl MMLParser {
         };
 
         let mut document = MMLDocument::default();
-        let mut current_section: Option<usize> = None;
+        let mut context = ParseContext::default();
 
         let mut total_lines = 0;
         let mut parsed_lines = 0;
@@ -63,19 +115,34 @@ impl MMLParser {
             total_lines += 1;
             let line_num = line_num + 1; // 1-based line numbers
 
+            // Blank lines and `#` comments (annexe B) carry no content; a
+            // comment still ends a code block and drops a language hint
             let trimmed = line.trim();
             if trimmed.is_empty() {
                 continue;
             }
+            if trimmed.starts_with('#') {
+                context.end_block();
+                continue;
+            }
 
             match self.parse_line(trimmed, line_num) {
                 Ok(parsed_line) => {
                     parsed_lines += 1;
-                    self.apply_parsed_line(&mut document, &mut current_section, parsed_line, line_num)?;
+                    let tag = parsed_line.tag;
+                    if let Err(error) = self.apply_parsed_line(&mut document, &mut context, parsed_line, line_num) {
+                        match report.as_deref_mut() {
+                            Some(report) => report.error(Some(line_num), error),
+                            None => return Err(error),
+                        }
+                    }
+                    context.advance(tag);
                 }
-                Err(_) => {
+                Err(error) => {
                     error_lines += 1;
-                    if self.options.validate_strict {
+                    if let Some(report) = report.as_deref_mut() {
+                        report.error(Some(line_num), error);
+                    } else if self.options.validate_strict {
                         return Err(MMLError::ParseError {
                             message: format!("Failed to parse line {}", line_num),
                             context: trimmed.to_string(),
@@ -86,6 +153,10 @@ impl MMLParser {
             }
         }
 
+        if let Some(report) = report {
+            check_structure(&document, &context, report);
+        }
+
         // Update statistics
         document.stats = MMLStats {
             total_lines,
@@ -97,27 +168,20 @@ impl MMLParser {
         Ok(document)
     }
 
-    /// Validate MML syntax without creating a document
-    pub fn validate(&self, input: &str) -> MMLResult<()> {
-        for (line_num, line) in input.lines().enumerate() {
-            let line_num = line_num + 1;
-            let trimmed = line.trim();
-
-            if trimmed.is_empty() {
-                continue;
-            }
-
-            self.parse_line(trimmed, line_num)?;
-        }
-        Ok(())
-    }
-
     /// Parse a single line of MML
     fn parse_line(&self, line: &str, line_num: usize) -> MMLResult<ParsedLine> {
         lazy_static! {
             static ref LINE_REGEX: Regex = Regex::new(r"^([^:]+):(.*)$").unwrap();
         }
 
+        // Packet terminator is the only line without a ':'
+        if line == MMLTag::End.to_str() {
+            return Ok(ParsedLine {
+                tag: MMLTag::End,
+                content: String::new(),
+            });
+        }
+
         let captures = LINE_REGEX.captures(line).ok_or_else(|| {
             MMLError::InvalidSyntax {
                 line: line_num,
@@ -129,7 +193,7 @@ impl MMLParser {
         })?;
 
         let tag_str = captures.get(1).unwrap().as_str().trim();
-        let content = captures.get(2).unwrap().as_str().trim();
+        let raw_content = captures.get(2).unwrap().as_str();
 
         let tag = MMLTag::from_str(tag_str);
         if tag == MMLTag::Unknown {
@@ -140,6 +204,13 @@ impl MMLParser {
             });
         }
 
+        // Code keeps its indentation, everything else is trimmed
+        let content = if tag == MMLTag::Code {
+            raw_content.trim_end()
+        } else {
+            raw_content.trim()
+        };
+
         Ok(ParsedLine {
             tag,
             content: content.to_string(),
@@ -150,66 +221,149 @@ impl MMLParser {
     fn apply_parsed_line(
         &self,
         document: &mut MMLDocument,
-        current_section: &mut Option<usize>,
+        context: &mut ParseContext,
         parsed_line: ParsedLine,
         line_num: usize,
     ) -> MMLResult<()> {
+        if matches!(
+            parsed_line.tag,
+            MMLTag::Paragraph | MMLTag::Code | MMLTag::Quote | MMLTag::Image
+        ) && context.current_section.is_none()
+        {
+            return self.apply_orphan_line(document, context, parsed_line, line_num);
+        }
+        let current_section = context.current_section;
+
         match parsed_line.tag {
             MMLTag::Title => {
                 document.title = Some(parsed_line.content);
             }
             MMLTag::Section => {
-                if document.sections.len() >= self.options.max_sections {
-                    return Err(MMLError::LimitExceeded {
-                        limit_type: "sections".to_string(),
-                        current: document.sections.len(),
-                        maximum: self.options.max_sections,
-                        suggestion: "Split document into multiple files or increase section limit.".to_string(),
-                    });
-                }
-                document.sections.push(MMLSection::new(parsed_line.content));
-                *current_section = Some(document.sections.len() - 1);
+                self.open_section(document, context, parsed_line.content, line_num)?;
             }
             MMLTag::Paragraph => {
-                if let Some(section_idx) = *current_section {
+                if let Some(section_idx) = current_section {
                     let section = &mut document.sections[section_idx];
                     section.content = Some(parsed_line.content);
                 }
             }
             MMLTag::Metadata => {
-                self.parse_metadata(document, current_section, &parsed_line.content, line_num)?;
+                self.parse_metadata(document, &current_section, &parsed_line.content, line_num)?;
+                context.language_hint = language_hint(&parsed_line.content);
             }
             MMLTag::Link => {
-                self.parse_link(document, current_section, &parsed_line.content, line_num)?;
+                self.parse_link(document, &current_section, &parsed_line.content, line_num)?;
             }
             MMLTag::Image => {
-                self.parse_image(document, current_section, &parsed_line.content, line_num)?;
+                if let Some(section_idx) = current_section {
+                    let image = self.parse_image(&parsed_line.content, line_num)?;
+                    document.sections[section_idx].images.push(image);
+                }
             }
             MMLTag::Code => {
-                if let Some(section_idx) = *current_section {
+                if let Some(section_idx) = current_section {
                     let section = &mut document.sections[section_idx];
-                    section.content = Some(format!("```\n{}\n```", parsed_line.content));
+                    push_code_line(&mut section.code, context, parsed_line.content);
                 }
             }
             MMLTag::Quote => {
-                if let Some(section_idx) = *current_section {
-                    let section = &mut document.sections[section_idx];
-                    section.content = Some(format!("> {}", parsed_line.content));
+                if let Some(section_idx) = current_section {
+                    let quote = self.parse_quote(&parsed_line.content);
+                    document.sections[section_idx].quotes.push(quote);
                 }
             }
-            MMLTag::Config | MMLTag::Packet => {
+            MMLTag::Config => {
                 // For now, treat as metadata
                 document.metadata.insert(
                     format!("{:?}", parsed_line.tag).to_lowercase(),
                     parsed_line.content,
                 );
             }
+            MMLTag::Packet => {
+                // An unterminated packet is implicitly closed by the next one
+                document.packets.push(parse_packet_header(&parsed_line.content));
+                context.open_packet = Some(document.packets.len() - 1);
+            }
+            MMLTag::End => {
+                let packet_idx = context.open_packet.take().ok_or_else(|| MMLError::StructureError {
+                    message: format!("'END' at line {} does not close any packet", line_num),
+                    suggestion: "Remove the stray 'END' or add the matching 'PKT:' line.".to_string(),
+                })?;
+                document.packets[packet_idx].terminated = true;
+            }
+            MMLTag::Rule => {
+                // Rules describe other documents; see profile.rs
+            }
+            MMLTag::Hash => {
+                // Checked against the canonical form; see canonical.rs
+            }
             MMLTag::Unknown => unreachable!("Should have been caught in parse_line"),
         }
 
         Ok(())
     }
 
+    /// Push a new section, enforcing the section limit
+    fn open_section(
+        &self,
+        document: &mut MMLDocument,
+        context: &mut ParseContext,
+        title: String,
+        line_num: usize,
+    ) -> MMLResult<()> {
+        if document.sections.len() >= self.options.max_sections {
+            return Err(MMLError::LimitExceeded {
+                limit_type: "sections".to_string(),
+                current: document.sections.len(),
+                maximum: self.options.max_sections,
+                suggestion: "Split document into multiple files or increase section limit.".to_string(),
+            });
+        }
+        document.sections.push(MMLSection::new(title));
+        context.current_section = Some(document.sections.len() - 1);
+        context.section_lines.push(line_num);
+        Ok(())
+    }
+
+    /// Place a content line that appears before any section according to
+    /// the configured orphan policy
+    fn apply_orphan_line(
+        &self,
+        document: &mut MMLDocument,
+        context: &mut ParseContext,
+        parsed_line: ParsedLine,
+        line_num: usize,
+    ) -> MMLResult<()> {
+        match &self.options.orphan_policy {
+            OrphanPolicy::AttachToDocument => match parsed_line.tag {
+                MMLTag::Paragraph => document.content.push(parsed_line.content),
+                MMLTag::Code => push_code_line(&mut document.code, context, parsed_line.content),
+                MMLTag::Quote => document.quotes.push(self.parse_quote(&parsed_line.content)),
+                MMLTag::Image => {
+                    document.images.push(self.parse_image(&parsed_line.content, line_num)?);
+                }
+                _ => unreachable!("Only content lines can be orphans"),
+            },
+            OrphanPolicy::CreateImplicitSection(title) => {
+                self.open_section(document, context, title.clone(), line_num)?;
+                self.apply_parsed_line(document, context, parsed_line, line_num)?;
+            }
+            OrphanPolicy::Drop => {}
+            OrphanPolicy::Error => {
+                return Err(MMLError::StructureError {
+                    message: format!(
+                        "'{}:' line {} appears before any section",
+                        parsed_line.tag.to_str(),
+                        line_num
+                    ),
+                    suggestion: "Add an 'H:' line before content or relax the orphan policy.".to_string(),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
     /// Parse metadata (key|value format)
     fn parse_metadata(
         &self,
@@ -218,12 +372,12 @@ impl MMLParser {
         content: &str,
         line_num: usize,
     ) -> MMLResult<()> {
-        let parts: Vec<&str> = content.split('|').collect();
+        let parts = split_fields(content);
         if parts.len() != 2 {
             return Err(MMLError::MetadataError {
                 key: content.to_string(),
                 value: "".to_string(),
-                error: "Invalid format".to_string(),
+                error: format!("Invalid format at line {}", line_num),
                 suggestion: "Use format 'key|value' for metadata.".to_string(),
             });
         }
@@ -266,12 +420,12 @@ impl MMLParser {
         content: &str,
         line_num: usize,
     ) -> MMLResult<()> {
-        let parts: Vec<&str> = content.split('|').collect();
+        let parts = split_fields(content);
         if parts.len() != 2 {
             return Err(MMLError::LinkError {
                 text: content.to_string(),
                 url: "".to_string(),
-                error: "Invalid format".to_string(),
+                error: format!("Invalid format at line {}", line_num),
                 suggestion: "Use format 'text|url' for links.".to_string(),
             });
         }
@@ -291,39 +445,39 @@ impl MMLParser {
     }
 
     /// Parse image (description|url format)
-    fn parse_image(
-        &self,
-        document: &mut MMLDocument,
-        current_section: &Option<usize>,
-        content: &str,
-        line_num: usize,
-    ) -> MMLResult<()> {
-        let parts: Vec<&str> = content.split('|').collect();
+    fn parse_image(&self, content: &str, line_num: usize) -> MMLResult<MMLImage> {
+        let parts = split_fields(content);
         if parts.len() != 2 {
             return Err(MMLError::ImageError {
                 description: content.to_string(),
                 url: "".to_string(),
-                error: "Invalid format".to_string(),
+                error: format!("Invalid format at line {}", line_num),
                 suggestion: "Use format 'description|url' for images.".to_string(),
             });
         }
 
-        let image = MMLImage::new(
+        Ok(MMLImage::new(
             parts[0].trim().to_string(),
             parts[1].trim().to_string(),
-        );
+        ))
+    }
 
-        if let Some(section_idx) = *current_section {
-            document.sections[section_idx].images.push(image);
-        } else {
-            // For now, add to first section or create a default one
-            if document.sections.is_empty() {
-                document.sections.push(MMLSection::new("Media".to_string()));
-            }
-            document.sections[0].images.push(image);
+    /// Parse quote (text or text|source format)
+    ///
+    /// The attribution follows the last unescaped '|', so a quote ending with
+    /// an empty source (`Q:A|B|`) keeps its inner '|'.
+    fn parse_quote(&self, content: &str) -> MMLQuote {
+        let mut parts = split_fields(content);
+        if parts.len() == 1 {
+            return MMLQuote::new(parts.remove(0), None);
         }
 
-        Ok(())
+        let source = parts.pop().unwrap_or_default();
+        let source = source.trim();
+        MMLQuote::new(
+            parts.join("|").trim().to_string(),
+            (!source.is_empty()).then(|| source.to_string()),
+        )
     }
 }
 
@@ -334,6 +488,143 @@ struct ParsedLine {
     content: String,
 }
 
+/// State carried from one line to the next while building a document
+#[derive(Debug, Default)]
+struct ParseContext {
+    /// Index of the section receiving content, if any
+    current_section: Option<usize>,
+    /// Tag of the previous non-empty line
+    previous_tag: Option<MMLTag>,
+    /// Language announced by an `M:lang|...` line right before a code block
+    language_hint: Option<String>,
+    /// Index of the packet waiting for its `END`
+    open_packet: Option<usize>,
+    /// Line number of each section's `H:` line
+    section_lines: Vec<usize>,
+}
+
+impl ParseContext {
+    /// Record that a line with `tag` has been applied
+    fn advance(&mut self, tag: MMLTag) {
+        if tag != MMLTag::Metadata {
+            self.language_hint = None;
+        }
+        self.previous_tag = Some(tag);
+    }
+
+    /// Start the next `C:` line as a new block, without language hint
+    fn end_block(&mut self) {
+        self.language_hint = None;
+        self.previous_tag = None;
+    }
+}
+
+/// Append a `C:` line to a list of code blocks
+///
+/// A `C:` line directly following another one extends the last block.
+/// Otherwise a new block starts, taking its language from a `lang|` prefix
+/// or from a preceding `M:lang|...` hint.
+fn push_code_line(blocks: &mut Vec<MMLCode>, context: &mut ParseContext, content: String) {
+    if context.previous_tag == Some(MMLTag::Code) {
+        if let Some(block) = blocks.last_mut() {
+            block.content.push('\n');
+            block.content.push_str(&content);
+            return;
+        }
+    }
+
+    let hint = context.language_hint.take();
+    let (language, code) = split_code_language(&content, hint.as_deref());
+    let language = language.or(hint);
+    blocks.push(MMLCode::new(code, language));
+}
+
+/// Split an optional `lang|` prefix off the first line of a code block
+///
+/// The prefix must name a language of [`CODE_LANGUAGES`] or the one of the
+/// `hint` (`M:lang|...`); otherwise it is code, as in `C:ls|wc -l`. An empty
+/// prefix (`C:|code`) explicitly marks a block without language.
+pub(crate) fn split_code_language(content: &str, hint: Option<&str>) -> (Option<String>, String) {
+    match content.split_once('|') {
+        Some(("", code)) => (None, code.to_string()),
+        Some((language, code))
+            if MMLCode::is_known_language(language) || hint.is_some_and(|hint| hint.eq_ignore_ascii_case(language)) =>
+        {
+            (Some(language.to_lowercase()), code.to_string())
+        }
+        _ => (None, content.to_string()),
+    }
+}
+
+/// Parse a `PKT:` header (`ID[:KEY=VALUE...]`)
+fn parse_packet_header(content: &str) -> MMLPacket {
+    let mut fields = content.split(':');
+    let mut packet = MMLPacket::new(fields.next().unwrap_or_default().trim().to_string());
+
+    for field in fields {
+        if let Some((key, value)) = field.split_once('=') {
+            packet.headers.insert(key.trim().to_string(), value.trim().to_string());
+        }
+    }
+
+    packet
+}
+
+/// Structural checks run after a validation pass
+fn check_structure(document: &MMLDocument, context: &ParseContext, report: &mut ValidationReport) {
+    if document.title.is_none() {
+        report.warning(None, MMLError::StructureError {
+            message: "Document has no title".to_string(),
+            suggestion: "Add a 'T:' line at the top of the document.".to_string(),
+        });
+    }
+
+    let mut seen_titles: HashMap<&str, usize> = HashMap::new();
+    for (section, &line) in document.sections.iter().zip(&context.section_lines) {
+        if section.content.is_none()
+            && section.metadata.is_empty()
+            && section.links.is_empty()
+            && section.images.is_empty()
+            && section.code.is_empty()
+            && section.quotes.is_empty()
+        {
+            report.warning(Some(line), MMLError::StructureError {
+                message: format!("Section '{}' is empty", section.title),
+                suggestion: "Add content under the section or remove it.".to_string(),
+            });
+        }
+
+        if let Some(first_line) = seen_titles.insert(&section.title, line) {
+            report.warning(Some(line), MMLError::StructureError {
+                message: format!(
+                    "Section title '{}' already used at line {}",
+                    section.title, first_line
+                ),
+                suggestion: "Give each section a distinct title.".to_string(),
+            });
+        }
+    }
+
+    for packet in document.packets.iter().filter(|packet| !packet.terminated) {
+        report.error(None, MMLError::StructureError {
+            message: format!("Packet '{}' has no 'END' line", packet.id),
+            suggestion: "Close each 'PKT:' block with a line containing only 'END'.".to_string(),
+        });
+    }
+}
+
+/// Extract the language announced by an `M:lang|...` metadata line
+fn language_hint(content: &str) -> Option<String> {
+    let (key, value) = content.split_once('|')?;
+    let key = key.trim().to_lowercase();
+    let value = value.trim();
+    if matches!(key.as_str(), "lang" | "language" | "langage") && !value.is_empty() {
+        Some(value.to_lowercase())
+    } else {
+        None
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -394,12 +685,17 @@ mod tests {
         let mml = "INVALID LINE WITHOUT COLON";
         let result = parser.parse(mml);
 
-        assert!(result.is_err());
-        if let Err(MMLError::InvalidSyntax { .. }) = result {
-            // Expected error type
-        } else {
-            panic!("Expected InvalidSyntax error");
+        // Strict mode wraps the line error with its position
+        match result {
+            Err(MMLError::ParseError { message, context }) => {
+                assert_eq!(message, "Failed to parse line 1");
+                assert_eq!(context, mml);
+            }
+            _ => panic!("Expected ParseError"),
         }
+
+        let report = parser.validate(mml);
+        assert!(matches!(report.errors[0].error, MMLError::InvalidSyntax { .. }));
     }
 
     #[test]
@@ -408,19 +704,87 @@ mod tests {
         let mml = "X:Unknown tag";
         let result = parser.parse(mml);
 
-        assert!(result.is_err());
-        if let Err(MMLError::UnknownTag { .. }) = result {
-            // Expected error type
-        } else {
-            panic!("Expected UnknownTag error");
-        }
+        assert!(matches!(result, Err(MMLError::ParseError { .. })));
+
+        let report = parser.validate(mml);
+        assert!(matches!(report.errors[0].error, MMLError::UnknownTag { .. }));
     }
 
     #[test]
     fn test_validate_valid_document() {
         let parser = MMLParser::new();
         let mml = "T:Valid\nH:Section\nP:Content";
-        assert!(parser.validate(mml).is_ok());
+        assert!(parser.validate(mml).is_clean());
+    }
+
+    #[test]
+    fn test_validate_runs_full_pipeline() {
+        let parser = MMLParser::new();
+        let mml = "T:Rapport\nM:sans séparateur\nH:Secteur\nX:inconnu\nL:lien sans url";
+        let report = parser.validate(mml);
+
+        let lines: Vec<_> = report.errors.iter().map(|issue| issue.line).collect();
+        assert_eq!(lines, vec![Some(2), Some(4), Some(5)]);
+        assert!(matches!(report.errors[0].error, MMLError::MetadataError { .. }));
+        assert!(matches!(report.errors[1].error, MMLError::UnknownTag { .. }));
+    }
+
+    #[test]
+    fn test_parse_tolerant() {
+        let parser = MMLParser::new();
+        let (document, report) = parser.parse_tolerant("T:Rapport\nH:Secteur\nEVACUATI0N URGENTE\nP:Tenir");
+
+        assert_eq!(document.title.as_deref(), Some("Rapport"));
+        assert_eq!(document.sections[0].content.as_deref(), Some("Tenir"));
+        assert_eq!(report.errors.len(), 1);
+        assert_eq!(report.errors[0].line, Some(3));
+    }
+
+    #[test]
+    fn test_validate_structure_warnings() {
+        let parser = MMLParser::new();
+        let mml = "H:Secteur A\nP:Stable\nH:Secteur B\nH:Secteur A\nP:Renforts";
+        let report = parser.validate(mml);
+
+        assert!(report.is_valid());
+        let messages: Vec<String> = report.warnings.iter().map(|issue| issue.error.to_string()).collect();
+        assert_eq!(messages.len(), 3);
+        assert!(messages[0].contains("no title"));
+        assert!(messages[1].contains("'Secteur B' is empty"));
+        assert!(messages[2].contains("already used at line 1"));
+        assert_eq!(report.warnings[2].line, Some(4));
+    }
+
+    #[test]
+    fn test_validate_limits() {
+        let options = ParserOptions {
+            max_sections: 1,
+            ..ParserOptions::default()
+        };
+        let report = MMLParser::with_options(options).validate("T:X\nH:A\nP:a\nH:B\nP:b");
+
+        assert_eq!(report.errors.len(), 1);
+        assert_eq!(report.errors[0].line, Some(4));
+        assert!(matches!(report.errors[0].error, MMLError::LimitExceeded { .. }));
+    }
+
+    #[test]
+    fn test_packets() {
+        let parser = MMLParser::new();
+        let mml = "PKT:SEC-ALERT-001:TTL=3600:PRIORITY=HIGH\nT:Alerte\nEND\nPKT:SENSOR-001\nT:Capteur";
+        let doc = parser.parse(mml).unwrap();
+
+        assert_eq!(doc.packets.len(), 2);
+        assert_eq!(doc.packets[0].id, "SEC-ALERT-001");
+        assert_eq!(doc.packets[0].headers.get("TTL"), Some(&"3600".to_string()));
+        assert!(doc.packets[0].terminated);
+        assert!(!doc.packets[1].terminated);
+
+        let report = parser.validate(mml);
+        assert_eq!(report.errors.len(), 1);
+        assert!(report.errors[0].error.to_string().contains("'SENSOR-001' has no 'END'"));
+
+        assert!(matches!(parser.parse("T:X\nEND"), Err(MMLError::StructureError { .. })));
     }
 
     #[test]
@@ -442,7 +806,11 @@ mod tests {
 
     #[test]
     fn test_parsing_statistics() {
-        let parser = MMLParser::new();
+        // Only a lenient parser gets past the invalid line
+        let parser = MMLParser::with_options(ParserOptions {
+            validate_strict: false,
+            ..ParserOptions::default()
+        });
         let mml = "T:Test\n\nH:Section\nP:Content\nINVALID LINE\n";
         let result = parser.parse(mml);
 
@@ -452,4 +820,130 @@ mod tests {
         assert_eq!(doc.stats.parsed_lines, 3); // T, H, P
         assert_eq!(doc.stats.error_lines, 1); // INVALID LINE
     }
+
+    #[test]
+    fn test_orphan_paragraph_attached_to_document() {
+        let parser = MMLParser::new();
+        let doc = parser.parse("T:Alerte\nP:Évacuer\nIMG:Carte|carte.png").unwrap();
+
+        assert!(doc.sections.is_empty());
+        assert_eq!(doc.content, vec!["Évacuer".to_string()]);
+        assert_eq!(doc.images.len(), 1);
+        assert_eq!(doc.images[0].url, "carte.png");
+    }
+
+    #[test]
+    fn test_orphan_implicit_section() {
+        let options = ParserOptions {
+            orphan_policy: OrphanPolicy::CreateImplicitSection("Media".to_string()),
+            ..ParserOptions::default()
+        };
+        let parser = MMLParser::with_options(options);
+
+        let doc = parser.parse("IMG:Photo|photo.jpg\nP:Légende\nH:Suite").unwrap();
+        assert_eq!(doc.sections.len(), 2);
+        assert_eq!(doc.sections[0].title, "Media");
+        assert_eq!(doc.sections[0].images.len(), 1);
+        assert_eq!(doc.sections[0].content, Some("Légende".to_string()));
+        assert!(doc.content.is_empty());
+    }
+
+    #[test]
+    fn test_orphan_drop_and_error() {
+        let mut options = ParserOptions {
+            orphan_policy: OrphanPolicy::Drop,
+            ..ParserOptions::default()
+        };
+        let doc = MMLParser::with_options(options.clone()).parse("P:Perdu\nH:Section").unwrap();
+        assert!(doc.content.is_empty());
+        assert_eq!(doc.sections.len(), 1);
+
+        options.orphan_policy = OrphanPolicy::Error;
+        let result = MMLParser::with_options(options).parse("P:Perdu\nH:Section");
+        assert!(matches!(result, Err(MMLError::StructureError { .. })));
+    }
+
+    #[test]
+    fn test_consecutive_code_lines_merge() {
+        let parser = MMLParser::new();
+        let doc = parser.parse("H:Addition\nC:2 + 3 = 5\nC:4 + 1 = 5\nP:Bravo\nC:2 × 3 = 6").unwrap();
+
+        let code = &doc.sections[0].code;
+        assert_eq!(code.len(), 2);
+        assert_eq!(code[0].content, "2 + 3 = 5\n4 + 1 = 5");
+        assert_eq!(code[0].language, None);
+        assert_eq!(code[1].content, "2 × 3 = 6");
+        assert_eq!(doc.sections[0].content, Some("Bravo".to_string()));
+    }
+
+    #[test]
+    fn test_code_language() {
+        let parser = MMLParser::new();
+        let mml = "H:Script\nC:python|if power_failure:\nC:    switch_to_backup()\n\
+                   M:lang|bash\nC:echo ok | tee log\nH:Brut\nC:|a|b";
+        let doc = parser.parse(mml).unwrap();
+
+        let code = &doc.sections[0].code;
+        assert_eq!(code[0].language.as_deref(), Some("python"));
+        assert_eq!(code[0].content, "if power_failure:\n    switch_to_backup()");
+        assert_eq!(code[1].language.as_deref(), Some("bash"));
+        assert_eq!(code[1].content, "echo ok | tee log");
+        assert_eq!(doc.sections[1].code[0].language, None);
+        assert_eq!(doc.sections[1].code[0].content, "a|b");
+    }
+
+    #[test]
+    fn test_code_pipeline_is_not_a_language() {
+        let parser = MMLParser::new();
+        let mml = "H:Shell\nC:ls|wc -l\n#\nM:lang|zig\nC:zig|const x = 1;\n#\nC:zig|const y = 2;";
+        let doc = parser.parse(mml).unwrap();
+
+        let code = &doc.sections[0].code;
+        assert_eq!(code[0], MMLCode::new("ls|wc -l".to_string(), None));
+        // A name outside the list is read only after a matching hint
+        assert_eq!(code[1], MMLCode::new("const x = 1;".to_string(), Some("zig".to_string())));
+        assert_eq!(code[2], MMLCode::new("zig|const y = 2;".to_string(), None));
+    }
+
+    #[test]
+    fn test_escaped_separators() {
+        let parser = MMLParser::new();
+        let mml = "M:Description|Texte avec \\| caractère spécial\nH:Liens\nL:A\\|B|https://example.com\nQ:x \\| y|Source";
+        let doc = parser.parse(mml).unwrap();
+
+        assert_eq!(doc.metadata.get("Description"), Some(&"Texte avec | caractère spécial".to_string()));
+        assert_eq!(doc.sections[0].links[0].text, "A|B");
+        assert_eq!(doc.sections[0].quotes[0].content, "x | y");
+        assert_eq!(doc.sections[0].quotes[0].attribution.as_deref(), Some("Source"));
+    }
+
+    #[test]
+    fn test_comments_are_ignored() {
+        let parser = MMLParser::new();
+        let mml = "# Rapport du PC\nT:Rapport\n  # note\nC:x = 1\n\nC:y = 2\n# bloc suivant\nC:z = 3";
+        let doc = parser.parse(mml).unwrap();
+
+        assert_eq!(doc.title.as_deref(), Some("Rapport"));
+        assert_eq!(doc.code[0].content, "x = 1\ny = 2");
+        assert_eq!(doc.code[1].content, "z = 3");
+
+        let doc = parser.parse("H:Script\nM:lang|bash\n#\nC:ls").unwrap();
+        assert_eq!(doc.sections[0].code[0].language, None);
+    }
+
+    #[test]
+    fn test_parse_quotes() {
+        let parser = MMLParser::new();
+        let mml = "Q:La rapidité sauve des vies\nH:Protocole\nP:Isoler\n\
+                   Q:La vaccination sauve des vies|Dr. Smith\nQ:A | B|";
+        let doc = parser.parse(mml).unwrap();
+
+        assert_eq!(doc.quotes, vec![MMLQuote::new("La rapidité sauve des vies".to_string(), None)]);
+        let quotes = &doc.sections[0].quotes;
+        assert_eq!(quotes.len(), 2);
+        assert_eq!(quotes[0].attribution.as_deref(), Some("Dr. Smith"));
+        assert_eq!(quotes[1].content, "A | B");
+        assert_eq!(quotes[1].attribution, None);
+        assert_eq!(doc.sections[0].content, Some("Isoler".to_string()));
+    }
 }
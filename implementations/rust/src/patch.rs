@@ -0,0 +1,694 @@
+//! Incremental patches
+//!
+//! A patch is written in MML itself, one tag line per operation, prefixed
+//! with `+` (add), `-` (remove) or `~` (select a section):
+//!
+//! ```text
+//! -M:Statut|En cours
+//! +M:Statut|Maîtrisé
+//! ~H:Situation actuelle
+//! -P:Feu actif
+//! +P:Feu fixé
+//! +H:Renforts
+//! +P:Deux Canadairs engagés
+//! -H:Ressources
+//! ```
+//!
+//! Lines before the first `H` operation apply to the document. `~H:` and
+//! `+H:` make the following lines apply to that section; `-H:` removes a
+//! section and must be followed by another `H` operation before any node
+//! line. A modification is written as the removal of the old value followed
+//! by the addition of the new one, so every removal also checks that the
+//! base document is the one the patch was computed from.
+//!
+//! `~H:` and `-H:` refer to the first section with that title not yet
+//! touched by the patch. `+H:` inserts the new section right after the
+//! section named by the previous `H` operation, or before all sections when
+//! it comes first; [`MMLPatch::compute`] selects an unchanged section when
+//! it is needed as such an anchor. Other added nodes are appended.
+//!
+//! Consecutive `C:` lines with the same prefix form one code block; a blank
+//! line separates two blocks.
+
+use crate::diff::{diff_matched, diff_metadata, diff_option, match_by_key, Change};
+use crate::error::{MMLError, MMLResult};
+use crate::escape::split_fields;
+use crate::parser::split_code_language;
+use crate::query::MMLNode;
+use crate::types::*;
+use std::collections::HashMap;
+use std::fmt;
+
+/// What a patch operation does
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PatchAction {
+    /// `+`: add the node
+    Add,
+    /// `-`: remove the node, which must exist with this exact value
+    Remove,
+    /// `~`: select an existing section (only valid with `H`)
+    Select,
+}
+
+impl PatchAction {
+    /// Prefix written before the tag
+    pub fn marker(self) -> char {
+        match self {
+            PatchAction::Add => '+',
+            PatchAction::Remove => '-',
+            PatchAction::Select => '~',
+        }
+    }
+}
+
+/// Node carried by a patch operation
+#[derive(Debug, Clone, PartialEq)]
+pub enum PatchNode {
+    Title(String),
+    Section(String),
+    Metadata { key: String, value: String },
+    Paragraph(String),
+    Link(MMLLink),
+    Image(MMLImage),
+    Code(MMLCode),
+    Quote(MMLQuote),
+}
+
+impl PatchNode {
+    fn tag(&self) -> MMLTag {
+        match self {
+            PatchNode::Title(_) => MMLTag::Title,
+            PatchNode::Section(_) => MMLTag::Section,
+            PatchNode::Metadata { .. } => MMLTag::Metadata,
+            PatchNode::Paragraph(_) => MMLTag::Paragraph,
+            PatchNode::Link(_) => MMLTag::Link,
+            PatchNode::Image(_) => MMLTag::Image,
+            PatchNode::Code(_) => MMLTag::Code,
+            PatchNode::Quote(_) => MMLTag::Quote,
+        }
+    }
+}
+
+/// A single patch line (several `C:` lines for a code block)
+#[derive(Debug, Clone, PartialEq)]
+pub struct PatchOperation {
+    pub action: PatchAction,
+    pub node: PatchNode,
+}
+
+impl PatchOperation {
+    /// Create a new operation
+    pub fn new(action: PatchAction, node: PatchNode) -> Self {
+        Self { action, node }
+    }
+}
+
+impl fmt::Display for PatchOperation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let marker = self.action.marker();
+        let node = match &self.node {
+            PatchNode::Section(title) => return write!(f, "{}H:{}", marker, title),
+            PatchNode::Title(title) => MMLNode::Title(title),
+            PatchNode::Metadata { key, value } => MMLNode::Metadata { key, value },
+            PatchNode::Paragraph(text) => MMLNode::Paragraph(text),
+            PatchNode::Link(link) => MMLNode::Link(link),
+            PatchNode::Image(image) => MMLNode::Image(image),
+            PatchNode::Code(code) => MMLNode::Code(code),
+            PatchNode::Quote(quote) => MMLNode::Quote(quote),
+        };
+
+        for (i, line) in node.to_string().split('\n').enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}{}", marker, line)?;
+        }
+        Ok(())
+    }
+}
+
+/// Patch turning one version of a document into another
+///
+/// # Example
+///
+/// ```rust
+/// use mml_parser::{parse_mml, MMLPatch};
+///
+/// let old = parse_mml("T:Sitrep\nM:Statut|En cours\nH:Situation\nP:Feu actif").unwrap();
+/// let new = parse_mml("T:Sitrep\nM:Statut|Maîtrisé\nH:Situation\nP:Feu actif").unwrap();
+///
+/// let patch = MMLPatch::compute(&old, &new);
+/// assert_eq!(patch.to_string(), "-M:Statut|En cours\n+M:Statut|Maîtrisé\n");
+///
+/// let mut updated = old.clone();
+/// updated.apply_patch(&patch).unwrap();
+/// assert_eq!(updated.metadata, new.metadata);
+///
+/// // Applying it twice is a conflict: the base no longer matches
+/// assert!(updated.apply_patch(&patch).is_err());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MMLPatch {
+    pub operations: Vec<PatchOperation>,
+}
+
+impl MMLPatch {
+    /// Create an empty patch
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// True when the patch changes nothing
+    pub fn is_empty(&self) -> bool {
+        self.operations.is_empty()
+    }
+
+    /// Compute the patch turning `old` into `new`
+    ///
+    /// Paragraphs, links, images, code blocks and quotes are compared the
+    /// same way as in [`crate::diff`]; code blocks and quotes are matched by
+    /// content.
+    pub fn compute(old: &MMLDocument, new: &MMLDocument) -> Self {
+        let mut patch = Self::new();
+
+        patch.push_change(diff_option(&old.title, &new.title), PatchNode::Title);
+        patch.push_metadata(&old.metadata, &new.metadata);
+        patch.push_changes(diff_matched(&old.content, &new.content, |p| p.as_str()), PatchNode::Paragraph);
+        patch.push_lists(
+            (&old.links, &new.links),
+            (&old.images, &new.images),
+            (&old.code, &new.code),
+            (&old.quotes, &new.quotes),
+        );
+
+        let (matches, used) = match_by_key(&old.sections, &new.sections, |s| s.title.as_str());
+        let mut titles: HashMap<&str, usize> = HashMap::new();
+        for section in &old.sections {
+            *titles.entry(section.title.as_str()).or_default() += 1;
+        }
+
+        let mut anchored = true;
+        for (index, (section, matched)) in new.sections.iter().zip(matches).enumerate() {
+            match matched {
+                Some(i) => {
+                    let mut changes = Self::new();
+                    let base = &old.sections[i];
+                    changes.push_metadata(&base.metadata, &section.metadata);
+                    changes.push_change(diff_option(&base.content, &section.content), PatchNode::Paragraph);
+                    changes.push_lists(
+                        (&base.links, &section.links),
+                        (&base.images, &section.images),
+                        (&base.code, &section.code),
+                        (&base.quotes, &section.quotes),
+                    );
+
+                    // A repeated title must be selected even when unchanged,
+                    // so that later operations reach the right occurrence
+                    anchored = !changes.is_empty() || titles[section.title.as_str()] > 1;
+                    if anchored {
+                        patch.push(PatchAction::Select, PatchNode::Section(section.title.clone()));
+                        patch.operations.extend(changes.operations);
+                    }
+                }
+                None => {
+                    // Select the previous section so the new one goes after it
+                    if !anchored {
+                        let previous = new.sections[index - 1].title.clone();
+                        patch.push(PatchAction::Select, PatchNode::Section(previous));
+                    }
+                    patch.push_section(section);
+                    anchored = true;
+                }
+            }
+        }
+        for (section, used) in old.sections.iter().zip(used) {
+            if !used {
+                patch.push(PatchAction::Remove, PatchNode::Section(section.title.clone()));
+            }
+        }
+
+        patch
+    }
+
+    /// Parse a patch from its text form
+    pub fn parse(input: &str) -> MMLResult<Self> {
+        let mut patch = Self::new();
+        let mut previous_line = 0;
+
+        for (index, raw) in input.lines().enumerate() {
+            let line_num = index + 1;
+            if raw.trim().is_empty() {
+                continue;
+            }
+
+            let action = match raw.chars().next() {
+                Some('+') => PatchAction::Add,
+                Some('-') => PatchAction::Remove,
+                Some('~') => PatchAction::Select,
+                _ => return Err(syntax_error(line_num, 1, raw, "'+', '-' or '~'", "Prefix each line with an operation.")),
+            };
+            let Some((tag, content)) = raw[1..].split_once(':') else {
+                return Err(syntax_error(line_num, 2, raw, "TAG:content", "Write the operation as '+M:key|value'."));
+            };
+
+            let tag = MMLTag::from_str(tag.trim());
+            if action == PatchAction::Select && tag != MMLTag::Section {
+                return Err(syntax_error(line_num, 1, raw, "'+' or '-'", "'~' only selects sections ('~H:title')."));
+            }
+
+            // Consecutive code lines with the same action form one block
+            if tag == MMLTag::Code && previous_line == line_num - 1 {
+                if let Some(PatchOperation { action: last_action, node: PatchNode::Code(code) }) =
+                    patch.operations.last_mut()
+                {
+                    if *last_action == action {
+                        code.content.push('\n');
+                        code.content.push_str(content.trim_end());
+                        previous_line = line_num;
+                        continue;
+                    }
+                }
+            }
+
+            let node = match tag {
+                MMLTag::Title => PatchNode::Title(content.trim().to_string()),
+                MMLTag::Section => PatchNode::Section(content.trim().to_string()),
+                MMLTag::Paragraph => PatchNode::Paragraph(content.trim().to_string()),
+                MMLTag::Metadata => {
+                    let (key, value) = two_fields(content, line_num, raw)?;
+                    PatchNode::Metadata { key, value }
+                }
+                MMLTag::Link => {
+                    let (text, url) = two_fields(content, line_num, raw)?;
+                    PatchNode::Link(MMLLink::new(text, url))
+                }
+                MMLTag::Image => {
+                    let (description, url) = two_fields(content, line_num, raw)?;
+                    PatchNode::Image(MMLImage::new(description, url))
+                }
+                MMLTag::Code => {
+                    let (language, code) = split_code_language(content.trim_end(), None);
+                    PatchNode::Code(MMLCode::new(code, language))
+                }
+                MMLTag::Quote => {
+                    let mut fields = split_fields(content);
+                    let attribution = if fields.len() > 1 { fields.pop() } else { None };
+                    let attribution = attribution.map(|a| a.trim().to_string()).filter(|a| !a.is_empty());
+                    PatchNode::Quote(MMLQuote::new(fields.join("|").trim().to_string(), attribution))
+                }
+                _ => {
+                    return Err(MMLError::UnknownTag {
+                        tag: raw[1..].split(':').next().unwrap_or_default().to_string(),
+                        line: line_num,
+                        suggestion: "Patches can change T, H, P, M, L, IMG, C and Q lines.".to_string(),
+                    })
+                }
+            };
+
+            patch.push(action, node);
+            previous_line = line_num;
+        }
+
+        Ok(patch)
+    }
+
+    /// Apply the patch to a document
+    ///
+    /// Stops at the first conflict and leaves the document as it was.
+    pub fn apply(&self, document: &mut MMLDocument) -> MMLResult<()> {
+        let mut patched = document.clone();
+        let mut sections: Vec<Option<MMLSection>> = patched.sections.drain(..).map(Some).collect();
+        let mut touched = vec![false; sections.len()];
+        let mut added: Vec<MMLSection> = Vec::new();
+        let mut order: Vec<Scope> = (0..sections.len()).map(Scope::Section).collect();
+        // Position in `order` of the section named by the last `H` operation
+        let mut anchor: Option<usize> = None;
+        let mut scope = Scope::Document;
+
+        for operation in &self.operations {
+            let conflict = |reason: String| MMLError::PatchConflict {
+                operation: operation.to_string().lines().next().unwrap_or_default().to_string(),
+                reason,
+            };
+
+            if let PatchNode::Section(title) = &operation.node {
+                scope = match operation.action {
+                    PatchAction::Add => {
+                        added.push(MMLSection::new(title.clone()));
+                        let position = anchor.map_or(0, |position| position + 1);
+                        order.insert(position, Scope::Added(added.len() - 1));
+                        anchor = Some(position);
+                        Scope::Added(added.len() - 1)
+                    }
+                    PatchAction::Select | PatchAction::Remove => {
+                        let index = (0..sections.len())
+                            .find(|&i| !touched[i] && sections[i].as_ref().is_some_and(|s| &s.title == title))
+                            .ok_or_else(|| conflict(format!("no section titled '{}' left to update", title)))?;
+                        touched[index] = true;
+                        anchor = order.iter().position(|slot| matches!(slot, Scope::Section(i) if *i == index));
+                        if operation.action == PatchAction::Remove {
+                            sections[index] = None;
+                            Scope::None
+                        } else {
+                            Scope::Section(index)
+                        }
+                    }
+                };
+                continue;
+            }
+
+            let result = match scope {
+                Scope::Document => apply_to_document(&mut patched, operation),
+                Scope::Section(index) => match sections[index].as_mut() {
+                    Some(section) => apply_to_section(section, operation),
+                    None => Err("section was removed".to_string()),
+                },
+                Scope::Added(index) => apply_to_section(&mut added[index], operation),
+                Scope::None => Err("no section selected after '-H:'".to_string()),
+            };
+            result.map_err(conflict)?;
+        }
+
+        let mut added: Vec<Option<MMLSection>> = added.into_iter().map(Some).collect();
+        patched.sections = order
+            .into_iter()
+            .filter_map(|slot| match slot {
+                Scope::Section(index) => sections[index].take(),
+                Scope::Added(index) => added[index].take(),
+                _ => None,
+            })
+            .collect();
+        *document = patched;
+        Ok(())
+    }
+
+    fn push(&mut self, action: PatchAction, node: PatchNode) {
+        self.operations.push(PatchOperation::new(action, node));
+    }
+
+    /// Removal first, then addition, so the old value is checked
+    fn push_change<T>(&mut self, change: Option<Change<T>>, node: impl Fn(T) -> PatchNode) {
+        match change {
+            Some(Change::Added(value)) => self.push(PatchAction::Add, node(value)),
+            Some(Change::Removed(value)) => self.push(PatchAction::Remove, node(value)),
+            Some(Change::Modified { old, new }) => {
+                self.push(PatchAction::Remove, node(old));
+                self.push(PatchAction::Add, node(new));
+            }
+            None => {}
+        }
+    }
+
+    /// Removals of a list go first, so an added duplicate is never removed
+    fn push_changes<T>(&mut self, changes: Vec<Change<T>>, node: impl Fn(T) -> PatchNode) {
+        let (removed, others): (Vec<_>, Vec<_>) =
+            changes.into_iter().partition(|change| matches!(change, Change::Removed(_)));
+        for change in removed.into_iter().chain(others) {
+            self.push_change(Some(change), &node);
+        }
+    }
+
+    fn push_metadata(&mut self, old: &HashMap<String, String>, new: &HashMap<String, String>) {
+        for (key, change) in diff_metadata(old, new) {
+            self.push_change(Some(change), |value| PatchNode::Metadata { key: key.clone(), value });
+        }
+    }
+
+    fn push_lists(
+        &mut self,
+        links: (&[MMLLink], &[MMLLink]),
+        images: (&[MMLImage], &[MMLImage]),
+        code: (&[MMLCode], &[MMLCode]),
+        quotes: (&[MMLQuote], &[MMLQuote]),
+    ) {
+        self.push_changes(diff_matched(links.0, links.1, |l| l.text.as_str()), PatchNode::Link);
+        self.push_changes(diff_matched(images.0, images.1, |i| i.description.as_str()), PatchNode::Image);
+        self.push_changes(diff_matched(code.0, code.1, |c| c.content.as_str()), PatchNode::Code);
+        self.push_changes(diff_matched(quotes.0, quotes.1, |q| q.content.as_str()), PatchNode::Quote);
+    }
+
+    fn push_section(&mut self, section: &MMLSection) {
+        self.push(PatchAction::Add, PatchNode::Section(section.title.clone()));
+
+        let mut metadata: Vec<_> = section.metadata.iter().collect();
+        metadata.sort();
+        for (key, value) in metadata {
+            self.push(PatchAction::Add, PatchNode::Metadata { key: key.clone(), value: value.clone() });
+        }
+        if let Some(text) = &section.content {
+            self.push(PatchAction::Add, PatchNode::Paragraph(text.clone()));
+        }
+        for link in &section.links {
+            self.push(PatchAction::Add, PatchNode::Link(link.clone()));
+        }
+        for image in &section.images {
+            self.push(PatchAction::Add, PatchNode::Image(image.clone()));
+        }
+        for code in &section.code {
+            self.push(PatchAction::Add, PatchNode::Code(code.clone()));
+        }
+        for quote in &section.quotes {
+            self.push(PatchAction::Add, PatchNode::Quote(quote.clone()));
+        }
+    }
+}
+
+impl fmt::Display for MMLPatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut previous: Option<&PatchOperation> = None;
+        for operation in &self.operations {
+            // Adjacent code lines would be read back as a single block
+            if let Some(last) = previous {
+                if matches!(last.node, PatchNode::Code(_))
+                    && matches!(operation.node, PatchNode::Code(_))
+                    && last.action == operation.action
+                {
+                    writeln!(f)?;
+                }
+            }
+            writeln!(f, "{}", operation)?;
+            previous = Some(operation);
+        }
+        Ok(())
+    }
+}
+
+impl MMLDocument {
+    /// Apply a patch computed with [`MMLPatch::compute`] or parsed from text
+    ///
+    /// Fails with [`MMLError::PatchConflict`] when the document is not the
+    /// base the patch expects; the document is then left unchanged.
+    pub fn apply_patch(&mut self, patch: &MMLPatch) -> MMLResult<()> {
+        patch.apply(self)
+    }
+}
+
+/// Where node operations currently apply
+#[derive(Debug, Clone, Copy)]
+enum Scope {
+    Document,
+    Section(usize),
+    Added(usize),
+    None,
+}
+
+fn apply_to_document(document: &mut MMLDocument, operation: &PatchOperation) -> Result<(), String> {
+    let add = operation.action == PatchAction::Add;
+    match &operation.node {
+        PatchNode::Title(title) => apply_single(&mut document.title, title, add, "title"),
+        PatchNode::Metadata { key, value } => apply_metadata(&mut document.metadata, key, value, add),
+        PatchNode::Paragraph(text) => apply_item(&mut document.content, text, add),
+        PatchNode::Link(link) => apply_item(&mut document.links, link, add),
+        PatchNode::Image(image) => apply_item(&mut document.images, image, add),
+        PatchNode::Code(code) => apply_item(&mut document.code, code, add),
+        PatchNode::Quote(quote) => apply_item(&mut document.quotes, quote, add),
+        PatchNode::Section(_) => unreachable!("section operations change the scope"),
+    }
+}
+
+fn apply_to_section(section: &mut MMLSection, operation: &PatchOperation) -> Result<(), String> {
+    let add = operation.action == PatchAction::Add;
+    match &operation.node {
+        PatchNode::Title(_) => Err(format!("'{}' lines only apply before the first section", operation.node.tag().to_str())),
+        PatchNode::Metadata { key, value } => apply_metadata(&mut section.metadata, key, value, add),
+        PatchNode::Paragraph(text) => apply_single(&mut section.content, text, add, "paragraph"),
+        PatchNode::Link(link) => apply_item(&mut section.links, link, add),
+        PatchNode::Image(image) => apply_item(&mut section.images, image, add),
+        PatchNode::Code(code) => apply_item(&mut section.code, code, add),
+        PatchNode::Quote(quote) => apply_item(&mut section.quotes, quote, add),
+        PatchNode::Section(_) => unreachable!("section operations change the scope"),
+    }
+}
+
+fn apply_single(slot: &mut Option<String>, value: &str, add: bool, name: &str) -> Result<(), String> {
+    match (add, slot.as_deref()) {
+        (true, None) => *slot = Some(value.to_string()),
+        (true, Some(current)) => return Err(format!("{} is already '{}'", name, current)),
+        (false, Some(current)) if current == value => *slot = None,
+        (false, Some(current)) => return Err(format!("{} is '{}'", name, current)),
+        (false, None) => return Err(format!("no {} to remove", name)),
+    }
+    Ok(())
+}
+
+fn apply_metadata(metadata: &mut HashMap<String, String>, key: &str, value: &str, add: bool) -> Result<(), String> {
+    match (add, metadata.get(key)) {
+        (true, None) => {
+            metadata.insert(key.to_string(), value.to_string());
+        }
+        (true, Some(current)) => return Err(format!("'{}' is already '{}'", key, current)),
+        (false, Some(current)) if current == value => {
+            metadata.remove(key);
+        }
+        (false, Some(current)) => return Err(format!("'{}' is '{}'", key, current)),
+        (false, None) => return Err(format!("no metadata '{}'", key)),
+    }
+    Ok(())
+}
+
+fn apply_item<T: Clone + PartialEq>(items: &mut Vec<T>, item: &T, add: bool) -> Result<(), String> {
+    if add {
+        items.push(item.clone());
+        return Ok(());
+    }
+    match items.iter().position(|existing| existing == item) {
+        Some(index) => {
+            items.remove(index);
+            Ok(())
+        }
+        None => Err("not found in the document".to_string()),
+    }
+}
+
+fn two_fields(content: &str, line_num: usize, raw: &str) -> MMLResult<(String, String)> {
+    let mut fields = split_fields(content);
+    if fields.len() != 2 {
+        return Err(syntax_error(line_num, 2, raw, "two fields separated by '|'", "Escape literal pipes as '\\|'."));
+    }
+    let second = fields.pop().unwrap_or_default().trim().to_string();
+    let first = fields.pop().unwrap_or_default().trim().to_string();
+    Ok((first, second))
+}
+
+fn syntax_error(line: usize, column: usize, found: &str, expected: &str, suggestion: &str) -> MMLError {
+    MMLError::InvalidSyntax {
+        line,
+        column,
+        found: found.to_string(),
+        expected: expected.to_string(),
+        suggestion: suggestion.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_mml;
+
+    const OLD: &str = "T:Sitrep\nM:Statut|En cours\nM:Équipe|Alpha\n\
+                       H:Situation actuelle\nM:Surface|450 hectares\nP:Feu actif\nL:Carte|v1.png\n\
+                       H:Ressources\nP:3 camions";
+    const NEW: &str = "T:Sitrep\nM:Statut|Maîtrisé\nM:Équipe|Alpha\n\
+                       H:Situation actuelle\nM:Surface|600 hectares\nP:Feu fixé\nL:Carte|v2.png\n\
+                       C:python|if vent > 30:\nC:    alerter()\n\
+                       H:Renforts\nP:Canadairs\nQ:Tenez bon|PC";
+
+    #[test]
+    fn test_compute_and_apply() {
+        let old = parse_mml(OLD).unwrap();
+        let new = parse_mml(NEW).unwrap();
+        let patch = MMLPatch::compute(&old, &new);
+
+        let mut updated = old.clone();
+        updated.apply_patch(&patch).unwrap();
+        assert_eq!(updated.metadata, new.metadata);
+        assert_eq!(updated.sections, new.sections);
+    }
+
+    #[test]
+    fn test_text_round_trip() {
+        let patch = MMLPatch::compute(&parse_mml(OLD).unwrap(), &parse_mml(NEW).unwrap());
+        let text = patch.to_string();
+
+        assert_eq!(
+            text,
+            "-M:Statut|En cours\n+M:Statut|Maîtrisé\n\
+             ~H:Situation actuelle\n\
+             -M:Surface|450 hectares\n+M:Surface|600 hectares\n\
+             -P:Feu actif\n+P:Feu fixé\n\
+             -L:Carte|v1.png\n+L:Carte|v2.png\n\
+             +C:python|if vent > 30:\n+C:    alerter()\n\
+             +H:Renforts\n+P:Canadairs\n+Q:Tenez bon|PC\n\
+             -H:Ressources\n"
+        );
+        assert_eq!(MMLPatch::parse(&text).unwrap(), patch);
+    }
+
+    #[test]
+    fn test_conflicts_leave_document_unchanged() {
+        let mut document = parse_mml("M:Statut|Annulé\nH:Situation\nP:Feu actif").unwrap();
+        let original = document.clone();
+
+        let patch = MMLPatch::parse("+M:Équipe|Bravo\n-M:Statut|En cours\n+M:Statut|Maîtrisé").unwrap();
+        let error = document.apply_patch(&patch).unwrap_err();
+        assert!(matches!(error, MMLError::PatchConflict { ref operation, .. } if operation == "-M:Statut|En cours"));
+        assert_eq!(document, original);
+
+        let missing = MMLPatch::parse("~H:Bilan\n+P:Aucun blessé").unwrap();
+        assert!(matches!(document.apply_patch(&missing), Err(MMLError::PatchConflict { .. })));
+
+        let orphan = MMLPatch::parse("-H:Situation\n+P:Texte").unwrap();
+        assert!(matches!(document.apply_patch(&orphan), Err(MMLError::PatchConflict { .. })));
+    }
+
+    #[test]
+    fn test_repeated_titles() {
+        let old = parse_mml("H:Note\nP:A\nH:Note\nP:B\nH:Note\nP:C").unwrap();
+        let new = parse_mml("H:Note\nP:A\nH:Note\nP:B2").unwrap();
+        let patch = MMLPatch::compute(&old, &new);
+
+        let mut updated = old.clone();
+        updated.apply_patch(&patch).unwrap();
+        assert_eq!(updated.sections, new.sections);
+    }
+
+    #[test]
+    fn test_section_inserted_in_the_middle() {
+        let old = parse_mml("H:A\nP:a\nH:C\nP:c").unwrap();
+        let new = parse_mml("H:A\nP:a\nH:B\nP:b\nH:C\nP:c").unwrap();
+        let patch = MMLPatch::compute(&old, &new);
+        assert_eq!(patch.to_string(), "~H:A\n+H:B\n+P:b\n");
+
+        let mut updated = old.clone();
+        updated.apply_patch(&MMLPatch::parse(&patch.to_string()).unwrap()).unwrap();
+        assert_eq!(updated.sections, new.sections);
+
+        let new = parse_mml("H:Z\nH:A\nP:a\nH:C\nP:c").unwrap();
+        let mut updated = old.clone();
+        updated.apply_patch(&MMLPatch::compute(&old, &new)).unwrap();
+        assert_eq!(updated.sections, new.sections);
+    }
+
+    #[test]
+    fn test_consecutive_code_blocks() {
+        let old = parse_mml("H:Code\nP:x").unwrap();
+        let mut new = old.clone();
+        new.sections[0].code = vec![
+            MMLCode::new("ls".to_string(), Some("bash".to_string())),
+            MMLCode::new("pwd\ncd /".to_string(), None),
+        ];
+        let patch = MMLPatch::compute(&old, &new);
+        let text = patch.to_string();
+
+        assert_eq!(text, "~H:Code\n+C:bash|ls\n\n+C:pwd\n+C:cd /\n");
+        assert_eq!(MMLPatch::parse(&text).unwrap(), patch);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(matches!(MMLPatch::parse("M:Statut|OK"), Err(MMLError::InvalidSyntax { line: 1, .. })));
+        assert!(matches!(MMLPatch::parse("+P:ok\n~P:texte"), Err(MMLError::InvalidSyntax { line: 2, .. })));
+        assert!(matches!(MMLPatch::parse("+M:sans valeur"), Err(MMLError::InvalidSyntax { .. })));
+        assert!(matches!(MMLPatch::parse("+PKT:MSG-1"), Err(MMLError::UnknownTag { .. })));
+    }
+}
@@ -0,0 +1,611 @@
+//! Document profiles
+//!
+//! A profile is itself an MML file: `T:` names it, `M:` lines describe it
+//! and each `RULE:target|constraint|arguments...` line constrains the
+//! documents checked against it.
+//!
+//! ```text
+//! T:Rapport d'incident
+//! RULE:M:ID|required
+//! RULE:M:ID|pattern|^INC-[0-9]{4}-[0-9]{3}$
+//! RULE:M:Priorité|required
+//! RULE:M:Priorité|enum|CRITIQUE|HAUTE|NORMALE
+//! RULE:M:Contact|optional
+//! RULE:M|closed
+//! RULE:H:Situation actuelle|required
+//! RULE:H:Situation actuelle > M:Surface|kind|quantity
+//! RULE:H:Actions requises|count|1..3
+//! RULE:H|order|Situation actuelle|Actions requises
+//! ```
+//!
+//! Targets are `M:key` and `M` (document metadata), `H:title > M:key`,
+//! `H:title > M` and `H:* > M:key` (section metadata), `H:title` and `H:*`
+//! (sections) and `H` (the section list). Constraints:
+//!
+//! | Constraint | Targets | Meaning |
+//! |---|---|---|
+//! | `required` / `optional` | `M:key`, `H:title` | must / may be present |
+//! | `closed` | `M`, `H` | only declared keys or titles are allowed |
+//! | `pattern\|regex` | `M:key` | the value matches the regex |
+//! | `enum\|a\|b...` | `M:key` | the value is one of the list |
+//! | `kind\|number` | `M:key` | the value reads as a [`MetadataKind`] |
+//! | `count\|n`, `count\|n..m`, `count\|n..` | `H:title`, `H:*` | number of sections |
+//! | `order\|a\|b...` | `H` | the listed sections appear in this order |
+
+use crate::error::{MMLError, MMLResult};
+use crate::escape::split_fields;
+use crate::metadata::{MetadataKind, MetadataValue};
+use crate::types::*;
+use regex::Regex;
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+
+/// What a rule applies to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RuleTarget {
+    /// `M:key`, or `M` when `key` is `None`
+    ///
+    /// `section` is the section title, `Some("*")` for every section and
+    /// `None` for the document.
+    Metadata {
+        section: Option<String>,
+        key: Option<String>,
+    },
+    /// `H:title`, or `H:*` when `None`
+    Section(Option<String>),
+    /// `H`
+    Sections,
+}
+
+/// Condition checked by a rule
+#[derive(Debug, Clone)]
+pub enum Constraint {
+    Required,
+    Optional,
+    Closed,
+    Pattern(Regex),
+    OneOf(Vec<String>),
+    Kind(MetadataKind),
+    Count { min: usize, max: Option<usize> },
+    Order(Vec<String>),
+}
+
+/// One `RULE:` line
+#[derive(Debug, Clone)]
+pub struct Rule {
+    /// 1-based line in the profile
+    pub line: usize,
+    pub target: RuleTarget,
+    pub constraint: Constraint,
+}
+
+/// Set of rules a document must follow
+#[derive(Debug, Clone, Default)]
+pub struct Profile {
+    /// `T:` line of the profile
+    pub name: Option<String>,
+    /// `M:` lines of the profile (version, author...)
+    pub metadata: HashMap<String, String>,
+    pub rules: Vec<Rule>,
+}
+
+/// Kind of profile violation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ViolationKind {
+    MissingMetadata,
+    UnknownMetadata,
+    PatternMismatch,
+    ValueNotAllowed,
+    WrongKind,
+    MissingSection,
+    UnknownSection,
+    Cardinality,
+    Order,
+}
+
+/// A rule broken by a document
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProfileViolation {
+    pub kind: ViolationKind,
+    /// Line of the broken rule in the profile
+    pub rule_line: usize,
+    /// Section concerned, if any
+    pub section: Option<String>,
+    /// Metadata key concerned, if any
+    pub key: Option<String>,
+    pub message: String,
+}
+
+impl fmt::Display for ProfileViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(section) = &self.section {
+            write!(f, "H:{}: ", section)?;
+        }
+        write!(f, "{} (profile line {})", self.message, self.rule_line)
+    }
+}
+
+impl Profile {
+    /// Parse a profile
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use mml_parser::{parse_mml, Profile};
+    ///
+    /// let profile = Profile::parse("T:Incident\nRULE:M:ID|required\nRULE:H:Situation actuelle|required").unwrap();
+    /// let document = parse_mml("T:Feu\nM:ID|INC-2025-001").unwrap();
+    ///
+    /// let violations = document.validate_against(&profile);
+    /// assert_eq!(violations.len(), 1);
+    /// assert_eq!(violations[0].to_string(), "H:Situation actuelle is missing (profile line 3)");
+    /// ```
+    pub fn parse(input: &str) -> MMLResult<Self> {
+        let mut profile = Self::default();
+
+        for (index, raw) in input.lines().enumerate() {
+            let line_num = index + 1;
+            let line = raw.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((tag, content)) = line.split_once(':') else {
+                return Err(syntax_error(
+                    line_num,
+                    raw,
+                    "TAG:content",
+                    "Write rules as 'RULE:M:key|required'.",
+                ));
+            };
+
+            match MMLTag::from_str(tag.trim()) {
+                MMLTag::Title => profile.name = Some(content.trim().to_string()),
+                MMLTag::Metadata => {
+                    let mut fields = split_fields(content).into_iter();
+                    let key = fields.next().unwrap_or_default().trim().to_string();
+                    let value = fields.collect::<Vec<_>>().join("|").trim().to_string();
+                    profile.metadata.insert(key, value);
+                }
+                MMLTag::Rule => profile.rules.push(parse_rule(content, line_num, raw)?),
+                _ => {
+                    return Err(syntax_error(
+                        line_num,
+                        raw,
+                        "T:, M: or RULE:",
+                        "Profiles only contain rules.",
+                    ));
+                }
+            }
+        }
+
+        Ok(profile)
+    }
+}
+
+impl MMLDocument {
+    /// Check the document against a profile
+    ///
+    /// Violations are listed in rule order.
+    pub fn validate_against(&self, profile: &Profile) -> Vec<ProfileViolation> {
+        let mut violations = Vec::new();
+        for rule in &profile.rules {
+            check_rule(self, profile, rule, &mut violations);
+        }
+        violations
+    }
+}
+
+fn parse_rule(content: &str, line_num: usize, raw: &str) -> MMLResult<Rule> {
+    let fields: Vec<String> = split_fields(content)
+        .into_iter()
+        .map(|f| f.trim().to_string())
+        .collect();
+    let target = parse_target(&fields[0]).ok_or_else(|| {
+        syntax_error(
+            line_num,
+            raw,
+            "M:key, M, H:title, H:* or H",
+            "Start the rule with its target.",
+        )
+    })?;
+    let name = fields.get(1).map(String::as_str).unwrap_or_default();
+    let args = fields.get(2..).unwrap_or_default();
+
+    let constraint = match (name, args.len()) {
+        ("required", 0) => Constraint::Required,
+        ("optional", 0) => Constraint::Optional,
+        ("closed", 0) => Constraint::Closed,
+        ("pattern", n) if n > 0 => {
+            let pattern = args.join("|");
+            let regex = Regex::new(&pattern)
+                .map_err(|_| syntax_error(line_num, raw, "a regular expression", "Fix the pattern."))?;
+            Constraint::Pattern(regex)
+        }
+        ("enum", n) if n > 0 => Constraint::OneOf(args.to_vec()),
+        ("kind", 1) => Constraint::Kind(MetadataKind::from_name(&args[0]).ok_or_else(|| {
+            syntax_error(
+                line_num,
+                raw,
+                "text, number, quantity, percentage, timestamp, coordinate or bool",
+                "Use a metadata kind.",
+            )
+        })?),
+        ("count", 1) => {
+            let (min, max) = parse_range(&args[0])
+                .ok_or_else(|| syntax_error(line_num, raw, "n, n..m or n..", "Write the count as '1..3'."))?;
+            Constraint::Count { min, max }
+        }
+        ("order", n) if n > 1 => Constraint::Order(args.to_vec()),
+        _ => {
+            return Err(syntax_error(
+                line_num,
+                raw,
+                "required, optional, closed, pattern, enum, kind, count or order",
+                "Check the constraint and its arguments.",
+            ))
+        }
+    };
+
+    let applies = matches!(
+        (&target, &constraint),
+        (
+            RuleTarget::Metadata { key: Some(_), .. },
+            Constraint::Required
+                | Constraint::Optional
+                | Constraint::Pattern(_)
+                | Constraint::OneOf(_)
+                | Constraint::Kind(_)
+        ) | (
+            RuleTarget::Metadata { key: None, .. } | RuleTarget::Sections,
+            Constraint::Closed
+        ) | (
+            RuleTarget::Section(Some(_)),
+            Constraint::Required | Constraint::Optional
+        ) | (RuleTarget::Section(_), Constraint::Count { .. })
+            | (RuleTarget::Sections, Constraint::Order(_))
+    );
+    if !applies {
+        return Err(syntax_error(
+            line_num,
+            raw,
+            "a constraint valid for this target",
+            "See the table in profile.rs.",
+        ));
+    }
+
+    Ok(Rule {
+        line: line_num,
+        target,
+        constraint,
+    })
+}
+
+fn parse_target(text: &str) -> Option<RuleTarget> {
+    if let Some((section, metadata)) = text.rsplit_once('>') {
+        let section = match parse_target(section.trim())? {
+            RuleTarget::Section(Some(title)) => title,
+            RuleTarget::Section(None) => "*".to_string(),
+            _ => return None,
+        };
+        return match parse_target(metadata.trim())? {
+            RuleTarget::Metadata { key, .. } => Some(RuleTarget::Metadata {
+                section: Some(section),
+                key,
+            }),
+            _ => None,
+        };
+    }
+
+    match text.split_once(':') {
+        None if text == "M" => Some(RuleTarget::Metadata {
+            section: None,
+            key: None,
+        }),
+        None if text == "H" => Some(RuleTarget::Sections),
+        Some(("M", key)) if !key.trim().is_empty() => Some(RuleTarget::Metadata {
+            section: None,
+            key: Some(key.trim().to_string()),
+        }),
+        Some(("H", "*")) => Some(RuleTarget::Section(None)),
+        Some(("H", title)) if !title.trim().is_empty() => Some(RuleTarget::Section(Some(title.trim().to_string()))),
+        _ => None,
+    }
+}
+
+/// `2`, `1..3` or `1..`
+fn parse_range(text: &str) -> Option<(usize, Option<usize>)> {
+    match text.split_once("..") {
+        None => text.parse().ok().map(|n| (n, Some(n))),
+        Some((min, "")) => Some((min.parse().ok()?, None)),
+        Some((min, max)) => {
+            let (min, max) = (min.parse().ok()?, max.parse().ok()?);
+            (min <= max).then_some((min, Some(max)))
+        }
+    }
+}
+
+fn check_rule(document: &MMLDocument, profile: &Profile, rule: &Rule, violations: &mut Vec<ProfileViolation>) {
+    let mut report = |kind, section: Option<&str>, key: Option<&str>, message: String| {
+        violations.push(ProfileViolation {
+            kind,
+            rule_line: rule.line,
+            section: section.map(str::to_string),
+            key: key.map(str::to_string),
+            message,
+        })
+    };
+
+    match &rule.target {
+        RuleTarget::Metadata {
+            section,
+            key: Some(key),
+        } => {
+            for (title, metadata) in scopes(document, section.as_deref()) {
+                let value = metadata.get(key);
+                match (&rule.constraint, value) {
+                    (Constraint::Required, None) => {
+                        report(
+                            ViolationKind::MissingMetadata,
+                            title,
+                            Some(key),
+                            format!("M:{} is missing", key),
+                        );
+                    }
+                    (Constraint::Pattern(regex), Some(value)) if !regex.is_match(value) => report(
+                        ViolationKind::PatternMismatch,
+                        title,
+                        Some(key),
+                        format!("M:{} '{}' does not match {}", key, value, regex),
+                    ),
+                    (Constraint::OneOf(allowed), Some(value)) if !allowed.contains(value) => report(
+                        ViolationKind::ValueNotAllowed,
+                        title,
+                        Some(key),
+                        format!("M:{} '{}' is not one of {}", key, value, allowed.join(", ")),
+                    ),
+                    (Constraint::Kind(kind), Some(value)) if MetadataValue::new(value).typed(*kind).is_none() => {
+                        report(
+                            ViolationKind::WrongKind,
+                            title,
+                            Some(key),
+                            format!("M:{} '{}' is not a {:?} value", key, value, kind).to_lowercase(),
+                        )
+                    }
+                    _ => {}
+                }
+            }
+        }
+        RuleTarget::Metadata { section, key: None } => {
+            for (title, metadata) in scopes(document, section.as_deref()) {
+                let declared = declared_keys(profile, title);
+                let mut unknown: Vec<_> = metadata.keys().filter(|k| !declared.contains(k.as_str())).collect();
+                unknown.sort();
+                for key in unknown {
+                    report(
+                        ViolationKind::UnknownMetadata,
+                        title,
+                        Some(key),
+                        format!("M:{} is not declared", key),
+                    );
+                }
+            }
+        }
+        RuleTarget::Section(title) => {
+            let count = document
+                .sections
+                .iter()
+                .filter(|s| title.as_ref().is_none_or(|t| &s.title == t))
+                .count();
+            let label = title.as_deref().unwrap_or("*");
+            match rule.constraint {
+                Constraint::Required if count == 0 => {
+                    report(
+                        ViolationKind::MissingSection,
+                        None,
+                        None,
+                        format!("H:{} is missing", label),
+                    );
+                }
+                Constraint::Count { min, max } if count < min || max.is_some_and(|max| count > max) => {
+                    let expected = match max {
+                        Some(max) if max == min => min.to_string(),
+                        Some(max) => format!("{}..{}", min, max),
+                        None => format!("{}..", min),
+                    };
+                    report(
+                        ViolationKind::Cardinality,
+                        None,
+                        None,
+                        format!("H:{} appears {} time(s), expected {}", label, count, expected),
+                    );
+                }
+                _ => {}
+            }
+        }
+        RuleTarget::Sections => match &rule.constraint {
+            Constraint::Closed => {
+                let declared: BTreeSet<&str> = profile
+                    .rules
+                    .iter()
+                    .filter_map(|r| match &r.target {
+                        RuleTarget::Section(Some(title)) => Some(title.as_str()),
+                        _ => None,
+                    })
+                    .collect();
+                for section in document
+                    .sections
+                    .iter()
+                    .filter(|s| !declared.contains(s.title.as_str()))
+                {
+                    report(
+                        ViolationKind::UnknownSection,
+                        Some(&section.title),
+                        None,
+                        "section is not declared".to_string(),
+                    );
+                }
+            }
+            Constraint::Order(titles) => {
+                let positions: Vec<(&String, usize)> = titles
+                    .iter()
+                    .filter_map(|t| document.sections.iter().position(|s| &s.title == t).map(|p| (t, p)))
+                    .collect();
+                for pair in positions.windows(2) {
+                    let ((earlier, first), (later, second)) = (pair[0], pair[1]);
+                    if second < first {
+                        report(
+                            ViolationKind::Order,
+                            Some(later),
+                            None,
+                            format!("section must come after H:{}", earlier),
+                        );
+                    }
+                }
+            }
+            _ => {}
+        },
+    }
+}
+
+/// Metadata maps a rule applies to, with their section title
+fn scopes<'d>(document: &'d MMLDocument, section: Option<&str>) -> Vec<(Option<&'d str>, &'d HashMap<String, String>)> {
+    match section {
+        None => vec![(None, &document.metadata)],
+        Some(wanted) => document
+            .sections
+            .iter()
+            .filter(|s| wanted == "*" || s.title == wanted)
+            .map(|s| (Some(s.title.as_str()), &s.metadata))
+            .collect(),
+    }
+}
+
+/// Keys declared for the document (`None`) or a section
+fn declared_keys<'p>(profile: &'p Profile, title: Option<&str>) -> BTreeSet<&'p str> {
+    profile
+        .rules
+        .iter()
+        .filter_map(|rule| match &rule.target {
+            RuleTarget::Metadata {
+                section,
+                key: Some(key),
+            } => {
+                let applies = match (section.as_deref(), title) {
+                    (None, None) => true,
+                    (Some(wanted), Some(title)) => wanted == "*" || wanted == title,
+                    _ => false,
+                };
+                applies.then_some(key.as_str())
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+fn syntax_error(line: usize, found: &str, expected: &str, suggestion: &str) -> MMLError {
+    MMLError::InvalidSyntax {
+        line,
+        column: 1,
+        found: found.to_string(),
+        expected: expected.to_string(),
+        suggestion: suggestion.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_mml;
+
+    const PROFILE: &str = "T:Rapport d'incident\nM:Version|1.0\n# Identification\n\
+                           RULE:M:ID|required\nRULE:M:ID|pattern|^INC-[0-9]{4}-[0-9]{3}$\n\
+                           RULE:M:Priorité|required\nRULE:M:Priorité|enum|CRITIQUE|HAUTE|NORMALE\n\
+                           RULE:M:Contact|optional\nRULE:M|closed\n\
+                           RULE:H:Situation actuelle|required\nRULE:H:Situation actuelle > M:Surface|kind|quantity\n\
+                           RULE:H:Actions requises|required\nRULE:H:Actions requises|count|1..2\n\
+                           RULE:H|order|Situation actuelle|Actions requises\nRULE:H|closed";
+
+    fn kinds(document: &str) -> Vec<ViolationKind> {
+        let profile = Profile::parse(PROFILE).unwrap();
+        let document = parse_mml(document).unwrap();
+        document.validate_against(&profile).iter().map(|v| v.kind).collect()
+    }
+
+    #[test]
+    fn test_parse_profile() {
+        let profile = Profile::parse(PROFILE).unwrap();
+        assert_eq!(profile.name.as_deref(), Some("Rapport d'incident"));
+        assert_eq!(profile.metadata["Version"], "1.0");
+        assert_eq!(profile.rules.len(), 12);
+        assert_eq!(profile.rules[0].line, 4);
+        assert_eq!(
+            profile.rules[7].target,
+            RuleTarget::Metadata {
+                section: Some("Situation actuelle".to_string()),
+                key: Some("Surface".to_string())
+            }
+        );
+        assert!(matches!(
+            profile.rules[9].constraint,
+            Constraint::Count { min: 1, max: Some(2) }
+        ));
+
+        assert!(matches!(
+            Profile::parse("RULE:M:ID|mandatory"),
+            Err(MMLError::InvalidSyntax { line: 1, .. })
+        ));
+        assert!(Profile::parse("RULE:H|required").is_err());
+        assert!(Profile::parse("RULE:M:ID|pattern|(").is_err());
+        assert!(Profile::parse("P:texte").is_err());
+    }
+
+    #[test]
+    fn test_conforming_document() {
+        let document = "T:Feu\nM:ID|INC-2025-001\nM:Priorité|CRITIQUE\n\
+                        H:Situation actuelle\nM:Surface|450 hectares\nH:Actions requises\nP:Évacuer";
+        assert!(kinds(document).is_empty());
+    }
+
+    #[test]
+    fn test_violations() {
+        let document = "T:Feu\nM:ID|2025-1\nM:Priorité|URGENT\nM:Météo|vent\n\
+                        H:Actions requises\nH:Situation actuelle\nM:Surface|beaucoup\n\
+                        H:Actions requises\nH:Actions requises\nH:Divers";
+        assert_eq!(
+            kinds(document),
+            vec![
+                ViolationKind::PatternMismatch,
+                ViolationKind::ValueNotAllowed,
+                ViolationKind::UnknownMetadata,
+                ViolationKind::WrongKind,
+                ViolationKind::Cardinality,
+                ViolationKind::Order,
+                ViolationKind::UnknownSection,
+            ]
+        );
+
+        let profile = Profile::parse(PROFILE).unwrap();
+        let violations = parse_mml("T:Vide").unwrap().validate_against(&profile);
+        assert_eq!(
+            violations.iter().map(|v| v.kind).collect::<Vec<_>>(),
+            vec![
+                ViolationKind::MissingMetadata,
+                ViolationKind::MissingMetadata,
+                ViolationKind::MissingSection,
+                ViolationKind::MissingSection,
+                ViolationKind::Cardinality,
+            ]
+        );
+        assert_eq!(violations[0].key.as_deref(), Some("ID"));
+        assert_eq!(violations[0].to_string(), "M:ID is missing (profile line 4)");
+    }
+
+    #[test]
+    fn test_rule_lines_are_ignored_in_documents() {
+        let document = parse_mml("T:Profil\nRULE:M:ID|required\nH:Section").unwrap();
+        assert!(document.metadata.is_empty());
+        assert_eq!(document.sections.len(), 1);
+    }
+}
@@ -0,0 +1,567 @@
+//! Selector queries over MML documents
+//!
+//! A small CSS-like language to pick nodes out of a document:
+//!
+//! | Selector | Matches |
+//! |----------|---------|
+//! | `M[Priorité]` | Metadata entries with key `Priorité`, anywhere |
+//! | `> M` | Document-level metadata only |
+//! | `H[Niveau=2] > L` | Links of sections whose `M:Niveau` is `2` |
+//! | `L[url^=https]` | Links whose URL starts with `https` |
+//! | `C[language=python], Q` | Python code blocks and all quotes |
+//!
+//! Steps are tag names (`T`, `H`, `P`, `M`, `L`, `IMG`, `C`, `Q`, `PKT`) or
+//! `*`. Steps are joined by `>` (child) or whitespace (descendant); a leading
+//! `>` anchors the first step at the document root.
+//!
+//! Attribute filters are `[name]` (present) or `[name OP value]` with `=`,
+//! `!=`, `^=` (prefix), `$=` (suffix) and `*=` (contains). Values may be
+//! quoted with `"`. The attributes of each node are:
+//!
+//! - `H`: `title` and the section's metadata keys
+//! - `M`: the entry's own key (`M[Surface]`), plus `key` and `value`
+//! - `L`: `text`, `url`; `IMG`: `description`, `url`
+//! - `C`: `language`, `content`; `Q`: `content`, `attribution`
+//! - `PKT`: `id` and the packet headers; `T`, `P`: `content`
+
+use crate::error::{MMLError, MMLResult};
+use crate::escape::escape_field;
+use crate::types::*;
+use std::collections::BTreeSet;
+use std::fmt;
+
+/// Reference to a node of an [`MMLDocument`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MMLNode<'a> {
+    Document(&'a MMLDocument),
+    Title(&'a str),
+    Section(&'a MMLSection),
+    Paragraph(&'a str),
+    Metadata { key: &'a str, value: &'a str },
+    Link(&'a MMLLink),
+    Image(&'a MMLImage),
+    Code(&'a MMLCode),
+    Quote(&'a MMLQuote),
+    Packet(&'a MMLPacket),
+}
+
+impl<'a> MMLNode<'a> {
+    /// Tag this node is written with
+    pub fn tag(&self) -> Option<MMLTag> {
+        match self {
+            MMLNode::Document(_) => None,
+            MMLNode::Title(_) => Some(MMLTag::Title),
+            MMLNode::Section(_) => Some(MMLTag::Section),
+            MMLNode::Paragraph(_) => Some(MMLTag::Paragraph),
+            MMLNode::Metadata { .. } => Some(MMLTag::Metadata),
+            MMLNode::Link(_) => Some(MMLTag::Link),
+            MMLNode::Image(_) => Some(MMLTag::Image),
+            MMLNode::Code(_) => Some(MMLTag::Code),
+            MMLNode::Quote(_) => Some(MMLTag::Quote),
+            MMLNode::Packet(_) => Some(MMLTag::Packet),
+        }
+    }
+
+    /// Child nodes, in the same order as [`crate::visit`]
+    pub fn children(&self) -> Vec<MMLNode<'a>> {
+        let mut children = Vec::new();
+
+        match *self {
+            MMLNode::Document(document) => {
+                if let Some(title) = &document.title {
+                    children.push(MMLNode::Title(title));
+                }
+                children.extend(document.packets.iter().map(MMLNode::Packet));
+                push_metadata(&mut children, &document.metadata);
+                children.extend(document.content.iter().map(|p| MMLNode::Paragraph(p)));
+                children.extend(document.links.iter().map(MMLNode::Link));
+                children.extend(document.images.iter().map(MMLNode::Image));
+                children.extend(document.code.iter().map(MMLNode::Code));
+                children.extend(document.quotes.iter().map(MMLNode::Quote));
+                children.extend(document.sections.iter().map(MMLNode::Section));
+            }
+            MMLNode::Section(section) => {
+                push_metadata(&mut children, &section.metadata);
+                if let Some(content) = &section.content {
+                    children.push(MMLNode::Paragraph(content));
+                }
+                children.extend(section.links.iter().map(MMLNode::Link));
+                children.extend(section.images.iter().map(MMLNode::Image));
+                children.extend(section.code.iter().map(MMLNode::Code));
+                children.extend(section.quotes.iter().map(MMLNode::Quote));
+            }
+            _ => {}
+        }
+
+        children
+    }
+
+    /// Value of a selector attribute on this node
+    pub fn attribute(&self, name: &str) -> Option<&'a str> {
+        match *self {
+            MMLNode::Document(_) => None,
+            MMLNode::Title(content) | MMLNode::Paragraph(content) => {
+                (name == "content").then_some(content)
+            }
+            MMLNode::Section(section) => match name {
+                "title" => Some(section.title.as_str()),
+                _ => section.metadata.get(name).map(String::as_str),
+            },
+            MMLNode::Metadata { key, value } => match name {
+                "key" => Some(key),
+                "value" => Some(value),
+                _ => (name == key).then_some(value),
+            },
+            MMLNode::Link(link) => match name {
+                "text" => Some(link.text.as_str()),
+                "url" => Some(link.url.as_str()),
+                _ => None,
+            },
+            MMLNode::Image(image) => match name {
+                "description" => Some(image.description.as_str()),
+                "url" => Some(image.url.as_str()),
+                _ => None,
+            },
+            MMLNode::Code(code) => match name {
+                "language" => code.language.as_deref(),
+                "content" => Some(code.content.as_str()),
+                _ => None,
+            },
+            MMLNode::Quote(quote) => match name {
+                "content" => Some(quote.content.as_str()),
+                "attribution" => quote.attribution.as_deref(),
+                _ => None,
+            },
+            MMLNode::Packet(packet) => match name {
+                "id" => Some(packet.id.as_str()),
+                _ => packet.headers.get(name).map(String::as_str),
+            },
+        }
+    }
+}
+
+/// Metadata entries sorted by key, for a deterministic order
+fn push_metadata<'a>(children: &mut Vec<MMLNode<'a>>, metadata: &'a std::collections::HashMap<String, String>) {
+    let mut entries: Vec<_> = metadata.iter().collect();
+    entries.sort_by(|a, b| a.0.cmp(b.0));
+    children.extend(entries.into_iter().map(|(key, value)| MMLNode::Metadata { key, value }));
+}
+
+/// Nodes are displayed as the MML line(s) they come from
+impl fmt::Display for MMLNode<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MMLNode::Document(document) => write!(f, "T:{}", document.title.as_deref().unwrap_or_default()),
+            MMLNode::Title(title) => write!(f, "T:{}", title),
+            MMLNode::Section(section) => write!(f, "H:{}", section.title),
+            MMLNode::Paragraph(content) => write!(f, "P:{}", content),
+            MMLNode::Metadata { key, value } => write!(f, "M:{}|{}", escape_field(key), escape_field(value)),
+            MMLNode::Link(link) => write!(f, "L:{}|{}", escape_field(&link.text), escape_field(&link.url)),
+            MMLNode::Image(image) => write!(f, "IMG:{}|{}", escape_field(&image.description), escape_field(&image.url)),
+            MMLNode::Code(code) => {
+                for (i, line) in code.lines().enumerate() {
+                    if i > 0 {
+                        writeln!(f)?;
+                    }
+                    match (&code.language, i) {
+                        (Some(language), 0) => write!(f, "C:{}|{}", language, line)?,
+                        (None, 0) if line.contains('|') => write!(f, "C:|{}", line)?,
+                        _ => write!(f, "C:{}", line)?,
+                    }
+                }
+                Ok(())
+            }
+            MMLNode::Quote(quote) => match &quote.attribution {
+                Some(source) => write!(f, "Q:{}|{}", escape_field(&quote.content), escape_field(source)),
+                None => write!(f, "Q:{}", escape_field(&quote.content)),
+            },
+            MMLNode::Packet(packet) => write!(f, "PKT:{}", packet.id),
+        }
+    }
+}
+
+/// Attribute comparison operator
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    Equals,
+    NotEquals,
+    StartsWith,
+    EndsWith,
+    Contains,
+}
+
+/// `[name]` or `[name OP value]`
+#[derive(Debug, Clone, PartialEq)]
+struct AttributeFilter {
+    name: String,
+    test: Option<(Operator, String)>,
+}
+
+impl AttributeFilter {
+    fn matches(&self, node: &MMLNode<'_>) -> bool {
+        let Some(actual) = node.attribute(&self.name) else {
+            return false;
+        };
+
+        match &self.test {
+            None => true,
+            Some((Operator::Equals, expected)) => actual == expected,
+            Some((Operator::NotEquals, expected)) => actual != expected,
+            Some((Operator::StartsWith, expected)) => actual.starts_with(expected.as_str()),
+            Some((Operator::EndsWith, expected)) => actual.ends_with(expected.as_str()),
+            Some((Operator::Contains, expected)) => actual.contains(expected.as_str()),
+        }
+    }
+}
+
+/// How a step relates to the previous one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Combinator {
+    Child,
+    Descendant,
+}
+
+/// One `TAG[filters]` step
+#[derive(Debug, Clone, PartialEq)]
+struct Step {
+    combinator: Combinator,
+    /// `None` for `*`
+    tag: Option<MMLTag>,
+    filters: Vec<AttributeFilter>,
+}
+
+impl Step {
+    fn matches(&self, node: &MMLNode<'_>) -> bool {
+        (self.tag.is_none() || self.tag == node.tag())
+            && self.filters.iter().all(|filter| filter.matches(node))
+    }
+}
+
+/// A compiled selector, reusable across documents
+///
+/// # Example
+///
+/// ```rust
+/// use mml_parser::{parse_mml, query::Selector};
+///
+/// let selector = Selector::parse("M[Priorité]").unwrap();
+/// let document = parse_mml("T:Rapport\nM:Priorité|CRITIQUE").unwrap();
+/// let nodes = selector.select(&document);
+/// assert_eq!(nodes[0].to_string(), "M:Priorité|CRITIQUE");
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Selector {
+    /// Comma-separated alternatives
+    groups: Vec<Vec<Step>>,
+}
+
+impl Selector {
+    /// Compile a selector
+    pub fn parse(input: &str) -> MMLResult<Self> {
+        let mut parser = SelectorParser { input, chars: input.char_indices().collect(), pos: 0 };
+        parser.parse()
+    }
+
+    /// Select matching nodes, in document order
+    ///
+    /// Nodes are told apart by their position in the document, so equal
+    /// nodes in different sections are all selected.
+    pub fn select<'a>(&self, document: &'a MMLDocument) -> Vec<MMLNode<'a>> {
+        let tree = Tree::new(document);
+        let mut selected = BTreeSet::new();
+
+        for steps in &self.groups {
+            let mut current = BTreeSet::from([0]);
+            for step in steps {
+                current = current
+                    .iter()
+                    .flat_map(|&node| {
+                        let tree = &tree;
+                        (node + 1..tree.ends[node]).filter(move |&other| match step.combinator {
+                            Combinator::Child => tree.parents[other] == Some(node),
+                            Combinator::Descendant => true,
+                        })
+                    })
+                    .filter(|&other| step.matches(&tree.nodes[other]))
+                    .collect();
+            }
+            selected.extend(current);
+        }
+
+        // Pre-order indices are document order
+        selected.into_iter().map(|index| tree.nodes[index]).collect()
+    }
+}
+
+/// Nodes of a document in pre-order, the document itself first
+///
+/// The descendants of the node at `i` are the nodes from `i + 1` up to
+/// `ends[i]`.
+struct Tree<'a> {
+    nodes: Vec<MMLNode<'a>>,
+    parents: Vec<Option<usize>>,
+    ends: Vec<usize>,
+}
+
+impl<'a> Tree<'a> {
+    fn new(document: &'a MMLDocument) -> Self {
+        let mut tree = Tree { nodes: Vec::new(), parents: Vec::new(), ends: Vec::new() };
+        tree.push(MMLNode::Document(document), None);
+        tree
+    }
+
+    fn push(&mut self, node: MMLNode<'a>, parent: Option<usize>) {
+        let index = self.nodes.len();
+        self.nodes.push(node);
+        self.parents.push(parent);
+        self.ends.push(index + 1);
+        for child in node.children() {
+            self.push(child, Some(index));
+        }
+        self.ends[index] = self.nodes.len();
+    }
+}
+
+impl MMLDocument {
+    /// Select nodes with a selector such as `H[Priorité=CRITIQUE] > L`
+    ///
+    /// See [`crate::query`] for the syntax.
+    pub fn select(&self, selector: &str) -> MMLResult<Vec<MMLNode<'_>>> {
+        Ok(Selector::parse(selector)?.select(self))
+    }
+}
+
+/// Recursive-descent parser for selectors
+struct SelectorParser<'s> {
+    input: &'s str,
+    chars: Vec<(usize, char)>,
+    pos: usize,
+}
+
+impl SelectorParser<'_> {
+    fn parse(&mut self) -> MMLResult<Selector> {
+        let mut groups = vec![self.parse_group()?];
+        while self.eat(',') {
+            groups.push(self.parse_group()?);
+        }
+        self.skip_whitespace();
+        if let Some(c) = self.peek() {
+            return Err(self.error(&c.to_string(), "',' or end of selector"));
+        }
+        Ok(Selector { groups })
+    }
+
+    fn parse_group(&mut self) -> MMLResult<Vec<Step>> {
+        let mut steps = Vec::new();
+        let mut combinator = if self.eat('>') { Combinator::Child } else { Combinator::Descendant };
+
+        loop {
+            self.skip_whitespace();
+            steps.push(self.parse_step(combinator)?);
+
+            let had_space = self.skip_whitespace();
+            combinator = match self.peek() {
+                Some('>') => {
+                    self.pos += 1;
+                    Combinator::Child
+                }
+                Some(',') | None => return Ok(steps),
+                Some(_) if had_space => Combinator::Descendant,
+                Some(c) => return Err(self.error(&c.to_string(), "'>', ',' or whitespace")),
+            };
+        }
+    }
+
+    fn parse_step(&mut self, combinator: Combinator) -> MMLResult<Step> {
+        let tag = if self.eat_char('*') {
+            None
+        } else {
+            let start = self.pos;
+            while self.peek().is_some_and(|c| c.is_ascii_alphabetic()) {
+                self.pos += 1;
+            }
+            let name = self.slice(start, self.pos);
+            match MMLTag::from_str(&name.to_uppercase()) {
+                MMLTag::Unknown | MMLTag::Config | MMLTag::End | MMLTag::Rule | MMLTag::Hash => {
+                    return Err(self.error(&name, "a tag (T, H, P, M, L, IMG, C, Q, PKT) or '*'"));
+                }
+                tag => Some(tag),
+            }
+        };
+
+        let mut filters = Vec::new();
+        while self.eat_char('[') {
+            filters.push(self.parse_filter()?);
+        }
+
+        Ok(Step { combinator, tag, filters })
+    }
+
+    fn parse_filter(&mut self) -> MMLResult<AttributeFilter> {
+        let name = self.parse_value(&['=', '!', '^', '$', '*', ']'])?;
+        if name.is_empty() {
+            return Err(self.error(&self.rest(), "an attribute name"));
+        }
+
+        let operator = match self.peek() {
+            Some(']') => None,
+            Some('=') => Some(Operator::Equals),
+            Some('!') => Some(Operator::NotEquals),
+            Some('^') => Some(Operator::StartsWith),
+            Some('$') => Some(Operator::EndsWith),
+            Some('*') => Some(Operator::Contains),
+            _ => return Err(self.error("end of selector", "']'")),
+        };
+
+        let test = match operator {
+            None => None,
+            Some(operator) => {
+                if operator != Operator::Equals {
+                    self.pos += 1;
+                }
+                if !self.eat_char('=') {
+                    return Err(self.error(&self.rest(), "'='"));
+                }
+                Some((operator, self.parse_value(&[']'])?))
+            }
+        };
+
+        if !self.eat_char(']') {
+            return Err(self.error(&self.rest(), "']'"));
+        }
+        Ok(AttributeFilter { name, test })
+    }
+
+    /// Bare text up to one of `stops`, or a `"quoted"` string
+    fn parse_value(&mut self, stops: &[char]) -> MMLResult<String> {
+        self.skip_whitespace();
+        if self.eat_char('"') {
+            let start = self.pos;
+            while self.peek().is_some_and(|c| c != '"') {
+                self.pos += 1;
+            }
+            let value = self.slice(start, self.pos);
+            if !self.eat_char('"') {
+                return Err(self.error(&value, "closing '\"'"));
+            }
+            self.skip_whitespace();
+            return Ok(value);
+        }
+
+        let start = self.pos;
+        while self.peek().is_some_and(|c| !stops.contains(&c)) {
+            self.pos += 1;
+        }
+        Ok(self.slice(start, self.pos).trim().to_string())
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).map(|&(_, c)| c)
+    }
+
+    fn eat_char(&mut self, expected: char) -> bool {
+        if self.peek() == Some(expected) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        self.skip_whitespace();
+        self.eat_char(expected)
+    }
+
+    fn skip_whitespace(&mut self) -> bool {
+        let start = self.pos;
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+        self.pos > start
+    }
+
+    fn offset(&self, pos: usize) -> usize {
+        self.chars.get(pos).map_or(self.input.len(), |&(offset, _)| offset)
+    }
+
+    fn slice(&self, start: usize, end: usize) -> String {
+        self.input[self.offset(start)..self.offset(end)].to_string()
+    }
+
+    fn rest(&self) -> String {
+        let rest = self.slice(self.pos, self.chars.len());
+        if rest.is_empty() { "end of selector".to_string() } else { rest }
+    }
+
+    fn error(&self, found: &str, expected: &str) -> MMLError {
+        MMLError::InvalidSyntax {
+            line: 1,
+            column: self.pos + 1,
+            found: found.to_string(),
+            expected: expected.to_string(),
+            suggestion: "Use selectors like 'H[Niveau=2] > L' or 'M[Priorité]'.".to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_mml;
+
+    const REPORT: &str = "T:Rapport d'urgence\nM:Priorité|CRITIQUE\nM:ID|INC-2025-001\n\
+                          H:Situation actuelle\nM:Niveau|2\nM:Surface|450 hectares\nP:Incendie\n\
+                          L:Carte|https://cartes.example/7\nH:Moyens engagés\nM:Niveau|1\n\
+                          L:Planning|planning.pdf\nC:python|x = 1\nQ:Vite|Chef";
+
+    fn select(selector: &str) -> Vec<String> {
+        let document = parse_mml(REPORT).unwrap();
+        document.select(selector).unwrap().iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn test_links_in_sections_by_metadata() {
+        assert_eq!(select("H[Niveau=2] > L"), vec!["L:Carte|https://cartes.example/7"]);
+        assert_eq!(select("H[Niveau!=2] L"), vec!["L:Planning|planning.pdf"]);
+        assert_eq!(select("L[url^=https]").len(), 1);
+    }
+
+    #[test]
+    fn test_metadata_selection() {
+        assert_eq!(select("M[Priorité]"), vec!["M:Priorité|CRITIQUE"]);
+        assert_eq!(select("M[Surface]"), vec!["M:Surface|450 hectares"]);
+        assert_eq!(select("> M"), vec!["M:ID|INC-2025-001", "M:Priorité|CRITIQUE"]);
+        assert_eq!(select("H > M[key=Niveau]"), vec!["M:Niveau|2", "M:Niveau|1"]);
+    }
+
+    #[test]
+    fn test_groups_keep_document_order() {
+        assert_eq!(
+            select("Q, C[language=python], H[title=\"Situation actuelle\"]"),
+            vec!["H:Situation actuelle", "C:python|x = 1", "Q:Vite|Chef"]
+        );
+        assert_eq!(select("*").len(), 13);
+    }
+
+    #[test]
+    fn test_equal_nodes_in_different_sections() {
+        let document = parse_mml("H:A\nP:ok\nH:B\nP:ok").unwrap();
+        let nodes = document.select("P").unwrap();
+        assert_eq!(nodes.len(), 2);
+        assert_eq!(document.select("H[title=B] > P").unwrap().len(), 1);
+        assert_eq!(document.select("H > P, P").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_invalid_selectors() {
+        let document = parse_mml(REPORT).unwrap();
+        for selector in ["X", "H[Niveau", "H[=2]", "H[Niveau=\"2]", "H]"] {
+            assert!(
+                matches!(document.select(selector), Err(MMLError::InvalidSyntax { .. })),
+                "{} should be rejected",
+                selector
+            );
+        }
+    }
+}
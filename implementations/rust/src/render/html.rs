@@ -0,0 +1,472 @@
+//! HTML renderer
+//!
+//! All text and attribute values are escaped, and link or image URLs whose
+//! scheme is not in [`HtmlOptions::allowed_schemes`] are dropped: an
+//! `L:x|javascript:alert(1)` line is written as plain text. Relative URLs
+//! have no scheme and are always kept.
+//!
+//! The output is a single file with no external resources besides remote
+//! images: themes are embedded in a `<style>` element, and
+//! [`HtmlOptions::embed_images`] inlines local images as `data:` URIs.
+
+use super::Renderer;
+use crate::error::{MMLError, MMLResult};
+use crate::query::MMLNode;
+use crate::types::*;
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+
+/// Layout shared by the screen themes
+const BASE_CSS: &str = "\
+body{font:16px/1.5 system-ui,sans-serif;max-width:48em;margin:0 auto;padding:1em}
+h1,h2{line-height:1.2}
+dl.metadata{display:grid;grid-template-columns:max-content auto;gap:.2em 1em}
+dl.metadata dt{font-weight:bold}
+dl.metadata dd{margin:0}
+pre{padding:.75em;overflow-x:auto;border-radius:4px}
+blockquote{margin:1em 0;padding-left:1em;border-left:4px solid}
+figure{margin:1em 0}
+img{max-width:100%}
+";
+
+const LIGHT_CSS: &str = "\
+body{color:#1a1a1a;background:#fff}
+a{color:#0645ad}
+pre{background:#f4f4f4}
+blockquote{border-color:#ccc;color:#444}
+";
+
+const DARK_CSS: &str = "\
+body{color:#e6e6e6;background:#181818}
+a{color:#8ab4f8}
+pre{background:#262626}
+blockquote{border-color:#555;color:#bbb}
+";
+
+/// Paper output: black on white, URLs printed after links
+const PRINT_CSS: &str = "\
+@page{margin:2cm}
+body{font:11pt/1.4 Georgia,serif;color:#000;background:#fff;margin:0}
+h1,h2{break-after:avoid}
+a{color:#000;text-decoration:none}
+a[href]::after{content:\" (\" attr(href) \")\";font-size:90%}
+dl.metadata dt{font-weight:bold}
+pre{white-space:pre-wrap;border:1px solid #999;padding:.5em}
+blockquote{margin:1em 0;padding-left:1em;border-left:2px solid #000}
+figure,pre,blockquote{break-inside:avoid}
+img{max-width:100%}
+";
+
+/// Embedded stylesheet of a standalone page
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HtmlTheme {
+    /// Dark text on a light background
+    Light,
+    /// Light text on a dark background
+    Dark,
+    /// Paper output, for reports printed in the field
+    Print,
+}
+
+impl HtmlTheme {
+    /// CSS of the theme
+    pub fn css(self) -> String {
+        match self {
+            HtmlTheme::Light => format!("{}{}", BASE_CSS, LIGHT_CSS),
+            HtmlTheme::Dark => format!("{}{}", BASE_CSS, DARK_CSS),
+            HtmlTheme::Print => PRINT_CSS.to_string(),
+        }
+    }
+}
+
+/// Options for [`HtmlRenderer`]
+#[derive(Debug, Clone)]
+pub struct HtmlOptions {
+    /// Wrap the body in a complete `<!DOCTYPE html>` page
+    pub standalone: bool,
+    /// Stylesheet embedded in a standalone page
+    pub theme: Option<HtmlTheme>,
+    /// URL schemes kept in `href` and `src`, compared case-insensitively
+    pub allowed_schemes: Vec<String>,
+    /// Inline local images as `data:` URIs, resolving relative paths from
+    /// this directory; images outside it, absolute paths included, are
+    /// refused
+    pub embed_images: Option<PathBuf>,
+}
+
+impl Default for HtmlOptions {
+    fn default() -> Self {
+        Self {
+            standalone: true,
+            theme: None,
+            allowed_schemes: ["http", "https", "mailto", "tel", "geo"]
+                .iter()
+                .map(|scheme| scheme.to_string())
+                .collect(),
+            embed_images: None,
+        }
+    }
+}
+
+/// Renders a document as semantic HTML
+///
+/// Sections become `<section>`, metadata a `<dl class="metadata">`, code
+/// `<pre><code class="language-…">` and quotes `<blockquote>`.
+#[derive(Debug, Clone, Default)]
+pub struct HtmlRenderer {
+    options: HtmlOptions,
+}
+
+impl HtmlRenderer {
+    /// Create a renderer with default options
+    pub fn new() -> Self {
+        Self::with_options(HtmlOptions::default())
+    }
+
+    /// Create a renderer with custom options
+    pub fn with_options(options: HtmlOptions) -> Self {
+        Self { options }
+    }
+
+    /// Whether a URL may be written as a link or image target
+    fn is_allowed(&self, url: &str) -> bool {
+        match url_scheme(url) {
+            Some(scheme) => self
+                .options
+                .allowed_schemes
+                .iter()
+                .any(|allowed| allowed.eq_ignore_ascii_case(&scheme)),
+            None => true,
+        }
+    }
+
+    /// Write the children of a document or section, grouping consecutive
+    /// metadata into a `<dl>` and consecutive links into a `<ul>`
+    fn write_children(&self, out: &mut dyn fmt::Write, node: &MMLNode) -> MMLResult<()> {
+        let mut group = None;
+
+        for child in node.children() {
+            let wanted = match child {
+                MMLNode::Metadata { .. } => Some("dl"),
+                MMLNode::Link(_) => Some("ul"),
+                _ => None,
+            };
+            if wanted != group {
+                if let Some(tag) = group {
+                    writeln!(out, "</{}>", tag)?;
+                }
+                match wanted {
+                    Some("dl") => out.write_str("<dl class=\"metadata\">\n")?,
+                    Some(_) => out.write_str("<ul class=\"links\">\n")?,
+                    None => {}
+                }
+                group = wanted;
+            }
+            self.write_node(out, &child)?;
+        }
+
+        if let Some(tag) = group {
+            writeln!(out, "</{}>", tag)?;
+        }
+        Ok(())
+    }
+
+    fn write_node(&self, out: &mut dyn fmt::Write, node: &MMLNode) -> MMLResult<()> {
+        match node {
+            MMLNode::Title(title) => writeln!(out, "<h1>{}</h1>", escape_html(title))?,
+            MMLNode::Section(section) => {
+                writeln!(out, "<section>\n<h2>{}</h2>", escape_html(&section.title))?;
+                self.write_children(out, node)?;
+                out.write_str("</section>\n")?;
+            }
+            MMLNode::Paragraph(paragraph) => writeln!(out, "<p>{}</p>", escape_html(paragraph))?,
+            MMLNode::Metadata { key, value } => {
+                writeln!(out, "<dt>{}</dt><dd>{}</dd>", escape_html(key), escape_html(value))?
+            }
+            MMLNode::Link(link) if self.is_allowed(&link.url) => writeln!(
+                out,
+                "<li><a href=\"{}\">{}</a></li>",
+                escape_html(&link.url),
+                escape_html(&link.text)
+            )?,
+            MMLNode::Link(link) => writeln!(out, "<li>{}</li>", escape_html(&link.text))?,
+            MMLNode::Image(image) => self.write_image(out, image)?,
+            MMLNode::Code(code) => write_code(out, code)?,
+            MMLNode::Quote(quote) => write_quote(out, quote)?,
+            MMLNode::Document(_) | MMLNode::Packet(_) => {}
+        }
+        Ok(())
+    }
+
+    fn write_image(&self, out: &mut dyn fmt::Write, image: &MMLImage) -> MMLResult<()> {
+        let description = escape_html(&image.description);
+        out.write_str("<figure>\n")?;
+
+        let source = match &self.options.embed_images {
+            Some(dir) if url_scheme(&image.url).is_none() => Some(data_uri(dir, image)?),
+            _ if image.url.starts_with("data:image/") || self.is_allowed(&image.url) => Some(image.url.clone()),
+            _ => None,
+        };
+        if let Some(source) = source {
+            writeln!(out, "<img src=\"{}\" alt=\"{}\">", escape_html(&source), description)?;
+        }
+
+        writeln!(out, "<figcaption>{}</figcaption>\n</figure>", description)?;
+        Ok(())
+    }
+}
+
+impl Renderer for HtmlRenderer {
+    fn render(&self, document: &MMLDocument, out: &mut dyn fmt::Write) -> MMLResult<()> {
+        if self.options.standalone {
+            out.write_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n")?;
+            out.write_str("<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n")?;
+            if let Some(title) = &document.title {
+                writeln!(out, "<title>{}</title>", escape_html(title))?;
+            }
+            if let Some(theme) = self.options.theme {
+                write!(out, "<style>\n{}</style>\n", theme.css())?;
+            }
+            out.write_str("</head>\n<body>\n")?;
+        }
+
+        out.write_str("<article>\n")?;
+        self.write_children(out, &MMLNode::Document(document))?;
+        out.write_str("</article>\n")?;
+
+        if self.options.standalone {
+            out.write_str("</body>\n</html>\n")?;
+        }
+        Ok(())
+    }
+}
+
+/// Escape text for use in HTML content and quoted attributes
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Lowercase scheme of a URL, `None` for relative URLs
+///
+/// Browsers ignore whitespace and control characters inside a scheme, so
+/// `java\tscript:` is read as `javascript:`; they are ignored here too.
+fn url_scheme(url: &str) -> Option<String> {
+    let mut scheme = String::new();
+    for c in url.chars() {
+        match c {
+            ':' => break,
+            '/' | '?' | '#' => return None,
+            c if c.is_ascii_whitespace() || c.is_control() => {}
+            c => scheme.push(c.to_ascii_lowercase()),
+        }
+    }
+    if !url.contains(':') || scheme.is_empty() {
+        return None;
+    }
+    Some(scheme)
+}
+
+fn write_code(out: &mut dyn fmt::Write, code: &MMLCode) -> fmt::Result {
+    // Only plain identifiers such as `c++` or `objective-c` become a class
+    let language = code.language.as_deref().filter(|language| {
+        language
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '#' | '-' | '_' | '.'))
+    });
+    match language {
+        Some(language) => writeln!(
+            out,
+            "<pre><code class=\"language-{}\">{}</code></pre>",
+            escape_html(language),
+            escape_html(&code.content)
+        ),
+        None => writeln!(out, "<pre><code>{}</code></pre>", escape_html(&code.content)),
+    }
+}
+
+fn write_quote(out: &mut dyn fmt::Write, quote: &MMLQuote) -> fmt::Result {
+    let content = escape_html(&quote.content);
+    match &quote.attribution {
+        Some(source) => writeln!(
+            out,
+            "<figure>\n<blockquote>\n<p>{}</p>\n</blockquote>\n<figcaption>— <cite>{}</cite></figcaption>\n</figure>",
+            content,
+            escape_html(source)
+        ),
+        None => writeln!(out, "<blockquote>\n<p>{}</p>\n</blockquote>", content),
+    }
+}
+
+/// Read a local image and encode it as a `data:` URI
+fn data_uri(dir: &std::path::Path, image: &MMLImage) -> MMLResult<String> {
+    let extension = image.url.rsplit('.').next().unwrap_or_default().to_ascii_lowercase();
+    let mime = match extension.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        _ => {
+            return Err(MMLError::ImageError {
+                description: image.description.clone(),
+                url: image.url.clone(),
+                error: "unknown image type".to_string(),
+                suggestion: "Use a .png, .jpg, .gif, .webp or .svg file".to_string(),
+            })
+        }
+    };
+
+    // Only files inside `dir`: a document must not pull in `../../.ssh/...`
+    let io_error = |path: &std::path::Path, e: std::io::Error| MMLError::IoError {
+        operation: "image embedding".to_string(),
+        path: path.display().to_string(),
+        error: e.to_string(),
+    };
+    let root = dir.canonicalize().map_err(|e| io_error(dir, e))?;
+    let path = dir.join(&image.url);
+    let path = path.canonicalize().map_err(|e| io_error(&path, e))?;
+    if std::path::Path::new(&image.url).is_absolute() || !path.starts_with(&root) {
+        return Err(MMLError::ImageError {
+            description: image.description.clone(),
+            url: image.url.clone(),
+            error: "outside the image directory".to_string(),
+            suggestion: format!("Use a relative path inside {}", dir.display()),
+        });
+    }
+
+    let bytes = fs::read(&path).map_err(|e| io_error(&path, e))?;
+    Ok(format!("data:{};base64,{}", mime, base64(&bytes)))
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_mml;
+
+    fn fragment(input: &str) -> String {
+        let options = HtmlOptions {
+            standalone: false,
+            ..HtmlOptions::default()
+        };
+        HtmlRenderer::with_options(options)
+            .render_to_string(&parse_mml(input).unwrap())
+            .unwrap()
+    }
+
+    #[test]
+    fn test_escaping_and_unsafe_urls() {
+        let html = fragment(
+            "T:<b>Rapport</b>\nH:Notes\nP:<script>alert(1)</script>\nL:x|javascript:alert(1)\nL:y|JaVa\tScript:alert(2)\nL:Carte|https://example.org/?a=1&b=\"2\"\nIMG:Plan|vbscript:x",
+        );
+
+        assert!(html.contains("<h1>&lt;b&gt;Rapport&lt;/b&gt;</h1>"));
+        assert!(html.contains("<p>&lt;script&gt;alert(1)&lt;/script&gt;</p>"));
+        assert!(html.contains("<li>x</li>\n<li>y</li>"));
+        assert!(html.contains("<a href=\"https://example.org/?a=1&amp;b=&quot;2&quot;\">Carte</a>"));
+        assert!(!html.contains("javascript") && !html.contains("<img"));
+    }
+
+    #[test]
+    fn test_semantic_output() {
+        let html = fragment(
+            "T:Rapport\nH:Situation\nM:Surface|450 ha\nP:Incendie\nC:rust|if a < b {}\nQ:Tenir|Chef\nIMG:Plan|plan.png",
+        );
+
+        assert!(html.starts_with("<article>\n<h1>Rapport</h1>\n<section>\n<h2>Situation</h2>\n"));
+        assert!(html.contains("<dl class=\"metadata\">\n<dt>Surface</dt><dd>450 ha</dd>\n</dl>"));
+        assert!(html.contains("<pre><code class=\"language-rust\">if a &lt; b {}</code></pre>"));
+        assert!(
+            html.contains("<blockquote>\n<p>Tenir</p>\n</blockquote>\n<figcaption>— <cite>Chef</cite></figcaption>")
+        );
+        assert!(html.contains("<img src=\"plan.png\" alt=\"Plan\">"));
+        assert!(html.ends_with("</section>\n</article>\n"));
+    }
+
+    #[test]
+    fn test_theme_and_embedded_images() {
+        let dir = std::env::temp_dir().join(format!("mml-html-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("point.gif"), b"GIF89a").unwrap();
+
+        let options = HtmlOptions {
+            theme: Some(HtmlTheme::Print),
+            embed_images: Some(dir.clone()),
+            ..HtmlOptions::default()
+        };
+        let document = parse_mml("T:Rapport\nH:Carte\nIMG:Point|point.gif\nIMG:Manquant|absent.png").unwrap();
+        let renderer = HtmlRenderer::with_options(options);
+        assert!(matches!(
+            renderer.render_to_string(&document),
+            Err(MMLError::IoError { .. })
+        ));
+
+        let document = parse_mml("T:Rapport\nH:Carte\nIMG:Point|point.gif").unwrap();
+        let html = renderer.render_to_string(&document).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(html.contains("<style>\n@page{margin:2cm}"));
+        assert!(html.contains("<img src=\"data:image/gif;base64,R0lGODlh\" alt=\"Point\">"));
+    }
+
+    #[test]
+    fn test_embedded_images_stay_in_directory() {
+        let dir = std::env::temp_dir().join(format!("mml-html-jail-{}", std::process::id()));
+        fs::create_dir_all(dir.join("doc")).unwrap();
+        fs::write(dir.join("secret.png"), b"secret").unwrap();
+
+        let renderer = HtmlRenderer::with_options(HtmlOptions {
+            embed_images: Some(dir.join("doc")),
+            ..HtmlOptions::default()
+        });
+        let absolute = dir.join("secret.png").display().to_string();
+        for url in ["../secret.png", "./../doc/../secret.png", absolute.as_str()] {
+            let document = parse_mml(&format!("T:Rapport\nH:Carte\nIMG:Clé|{}", url)).unwrap();
+            assert!(
+                matches!(renderer.render_to_string(&document), Err(MMLError::ImageError { .. })),
+                "{} should be refused",
+                url
+            );
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_base64() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+    }
+}
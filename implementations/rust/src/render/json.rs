@@ -0,0 +1,32 @@
+//! JSON renderer (annexe D DOM)
+
+use super::Renderer;
+use crate::error::MMLResult;
+use crate::json::{to_json, JsonOptions};
+use crate::types::*;
+use std::fmt;
+
+/// Renders a document as annexe D JSON, see [`crate::json`]
+#[derive(Debug, Clone, Default)]
+pub struct JsonRenderer {
+    options: JsonOptions,
+}
+
+impl JsonRenderer {
+    /// Create a renderer with default options
+    pub fn new() -> Self {
+        Self::with_options(JsonOptions::default())
+    }
+
+    /// Create a renderer with custom options
+    pub fn with_options(options: JsonOptions) -> Self {
+        Self { options }
+    }
+}
+
+impl Renderer for JsonRenderer {
+    fn render(&self, document: &MMLDocument, out: &mut dyn fmt::Write) -> MMLResult<()> {
+        out.write_str(&to_json(document, &self.options)?)?;
+        Ok(())
+    }
+}
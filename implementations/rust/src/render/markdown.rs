@@ -0,0 +1,283 @@
+//! CommonMark renderer
+//!
+//! `T:` becomes a `#` heading and `H:` a `##` heading, or deeper when the
+//! section carries `M:Niveau|2` (annexe B: level 2 is a sub-section). Text
+//! is escaped so that `*`, `_`, `[` or a leading `#` stay literal; tables
+//! use the GitHub pipe syntax understood by most wikis and chat tools.
+
+use super::Renderer;
+use crate::error::MMLResult;
+use crate::query::MMLNode;
+use crate::types::*;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Where metadata is written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetadataStyle {
+    /// Document metadata in a YAML front-matter block, section metadata in
+    /// a table under the heading
+    FrontMatter,
+    /// A `Clé | Valeur` table under every heading
+    Table,
+}
+
+/// Options for [`MarkdownRenderer`]
+#[derive(Debug, Clone)]
+pub struct MarkdownOptions {
+    /// Where metadata is written
+    pub metadata: MetadataStyle,
+}
+
+impl Default for MarkdownOptions {
+    fn default() -> Self {
+        Self {
+            metadata: MetadataStyle::FrontMatter,
+        }
+    }
+}
+
+/// Renders a document as CommonMark
+#[derive(Debug, Clone, Default)]
+pub struct MarkdownRenderer {
+    options: MarkdownOptions,
+}
+
+impl MarkdownRenderer {
+    /// Create a renderer with default options
+    pub fn new() -> Self {
+        Self::with_options(MarkdownOptions::default())
+    }
+
+    /// Create a renderer with custom options
+    pub fn with_options(options: MarkdownOptions) -> Self {
+        Self { options }
+    }
+
+    /// Append one block per child, with consecutive metadata in one table
+    /// and consecutive links in one list
+    fn push_children(&self, blocks: &mut Vec<String>, node: &MMLNode) {
+        let skip_metadata = matches!(node, MMLNode::Document(_)) && self.options.metadata == MetadataStyle::FrontMatter;
+        let mut metadata = Vec::new();
+        let mut links = Vec::new();
+
+        for child in node.children() {
+            if !matches!(child, MMLNode::Metadata { .. }) && !metadata.is_empty() {
+                blocks.push(table(&metadata));
+                metadata.clear();
+            }
+            if !matches!(child, MMLNode::Link(_)) && !links.is_empty() {
+                blocks.push(links.join("\n"));
+                links.clear();
+            }
+
+            match child {
+                MMLNode::Title(title) => blocks.push(format!("# {}", escape_inline(title))),
+                MMLNode::Section(section) => {
+                    blocks.push(format!(
+                        "{} {}",
+                        "#".repeat(heading_level(section)),
+                        escape_inline(&section.title)
+                    ));
+                    self.push_children(blocks, &child);
+                }
+                MMLNode::Paragraph(paragraph) => blocks.push(escape_inline(paragraph)),
+                MMLNode::Metadata { .. } if skip_metadata => {}
+                MMLNode::Metadata { key, value } => metadata.push((key, value)),
+                MMLNode::Link(link) => {
+                    links.push(format!("- [{}]({})", escape_inline(&link.text), escape_url(&link.url)))
+                }
+                MMLNode::Image(image) => blocks.push(format!(
+                    "![{}]({})",
+                    escape_inline(&image.description),
+                    escape_url(&image.url)
+                )),
+                MMLNode::Code(code) => blocks.push(fenced(code)),
+                MMLNode::Quote(quote) => {
+                    let mut block = format!("> {}", escape_inline(&quote.content));
+                    if let Some(source) = &quote.attribution {
+                        block.push_str(&format!("\n>\n> — {}", escape_inline(source)));
+                    }
+                    blocks.push(block);
+                }
+                MMLNode::Document(_) | MMLNode::Packet(_) => {}
+            }
+        }
+
+        if !metadata.is_empty() {
+            blocks.push(table(&metadata));
+        }
+        if !links.is_empty() {
+            blocks.push(links.join("\n"));
+        }
+    }
+}
+
+impl Renderer for MarkdownRenderer {
+    fn render(&self, document: &MMLDocument, out: &mut dyn fmt::Write) -> MMLResult<()> {
+        let mut blocks = Vec::new();
+
+        if self.options.metadata == MetadataStyle::FrontMatter && !document.metadata.is_empty() {
+            blocks.push(front_matter(&document.metadata));
+        }
+        self.push_children(&mut blocks, &MMLNode::Document(document));
+
+        if !blocks.is_empty() {
+            writeln!(out, "{}", blocks.join("\n\n"))?;
+        }
+        Ok(())
+    }
+}
+
+/// Number of `#` of a section heading: two, plus one per level below 1
+fn heading_level(section: &MMLSection) -> usize {
+    let level = section
+        .metadata
+        .get("Niveau")
+        .and_then(|level| level.trim().parse::<usize>().ok())
+        .unwrap_or(1);
+    (level + 1).clamp(2, 6)
+}
+
+/// Escape inline text so that it is read literally
+fn escape_inline(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    let mut previous = None;
+
+    for (i, c) in text.chars().enumerate() {
+        let at_start = i == 0;
+        let special = match c {
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '|' | '~' | '&' => true,
+            // Headings, list items and ordered list markers
+            '#' => at_start || previous == Some(' '),
+            '-' | '+' | '=' => at_start,
+            '.' | ')' => i > 0 && text.chars().take(i).all(|p| p.is_ascii_digit()),
+            _ => false,
+        };
+        if special {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+        previous = Some(c);
+    }
+    escaped
+}
+
+/// Percent-encode the characters that would end a link destination
+fn escape_url(url: &str) -> String {
+    let mut escaped = String::with_capacity(url.len());
+    for c in url.chars() {
+        match c {
+            ' ' => escaped.push_str("%20"),
+            '(' => escaped.push_str("%28"),
+            ')' => escaped.push_str("%29"),
+            '<' => escaped.push_str("%3C"),
+            '>' => escaped.push_str("%3E"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Code block fenced with more backticks than the longest run it contains
+fn fenced(code: &MMLCode) -> String {
+    let mut longest = 0;
+    let mut run = 0;
+    for c in code.content.chars() {
+        run = if c == '`' { run + 1 } else { 0 };
+        longest = longest.max(run);
+    }
+    let fence = "`".repeat((longest + 1).max(3));
+    let language = code
+        .language
+        .as_deref()
+        .filter(|language| !language.contains('`'))
+        .unwrap_or("");
+    format!("{}{}\n{}\n{}", fence, language, code.content, fence)
+}
+
+fn table(entries: &[(&str, &str)]) -> String {
+    let mut entries = entries.to_vec();
+    entries.sort();
+    let mut table = String::from("| Clé | Valeur |\n| --- | --- |");
+    for (key, value) in entries {
+        table.push_str(&format!("\n| {} | {} |", escape_inline(key), escape_inline(value)));
+    }
+    table
+}
+
+fn front_matter(metadata: &HashMap<String, String>) -> String {
+    let mut entries: Vec<_> = metadata.iter().collect();
+    entries.sort();
+    let mut yaml = String::from("---");
+    for (key, value) in entries {
+        yaml.push_str(&format!("\n{}: {}", yaml_string(key), yaml_string(value)));
+    }
+    yaml.push_str("\n---");
+    yaml
+}
+
+/// Double-quoted YAML scalar
+fn yaml_string(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04X}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_mml;
+
+    #[test]
+    fn test_markdown_document() {
+        let document = parse_mml(
+            "T:Rapport\nM:Auteur|Équipe \"A\"\nH:Situation\nM:Surface|450 ha\nP:Incendie\nL:Carte|https://example.org/a b\nL:Plan|plan.html\nIMG:Vue|vue.png\nC:sh|ls -la\nQ:Tenir|Chef\nH:Détails\nM:Niveau|2",
+        )
+        .unwrap();
+        let markdown = MarkdownRenderer::new().render_to_string(&document).unwrap();
+
+        assert_eq!(
+            markdown,
+            "---\n\"Auteur\": \"Équipe \\\"A\\\"\"\n---\n\n# Rapport\n\n## Situation\n\n| Clé | Valeur |\n| --- | --- |\n| Surface | 450 ha |\n\nIncendie\n\n- [Carte](https://example.org/a%20b)\n- [Plan](plan.html)\n\n![Vue](vue.png)\n\n```sh\nls -la\n```\n\n> Tenir\n>\n> — Chef\n\n### Détails\n\n| Clé | Valeur |\n| --- | --- |\n| Niveau | 2 |\n"
+        );
+    }
+
+    #[test]
+    fn test_metadata_table() {
+        let document = parse_mml("T:Rapport\nM:Auteur|A\\|B\nH:Situation").unwrap();
+        let options = MarkdownOptions {
+            metadata: MetadataStyle::Table,
+        };
+        let markdown = MarkdownRenderer::with_options(options)
+            .render_to_string(&document)
+            .unwrap();
+        assert!(
+            markdown.starts_with("# Rapport\n\n| Clé | Valeur |\n| --- | --- |\n| Auteur | A\\|B |\n\n## Situation")
+        );
+    }
+
+    #[test]
+    fn test_escaping() {
+        assert_eq!(escape_inline("*urgent* _à_ [lien]"), "\\*urgent\\* \\_à\\_ \\[lien\\]");
+        assert_eq!(escape_inline("# titre - 3 #4"), "\\# titre - 3 \\#4");
+        assert_eq!(escape_inline("- item"), "\\- item");
+        assert_eq!(escape_inline("12. point"), "12\\. point");
+        assert_eq!(escape_inline("v1.2"), "v1.2");
+
+        let code = MMLCode {
+            language: None,
+            content: "a ``` b".to_string(),
+        };
+        assert_eq!(fenced(&code), "````\na ``` b\n````");
+    }
+}
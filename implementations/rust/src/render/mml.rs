@@ -0,0 +1,104 @@
+//! MML and MMLC renderer
+
+use super::Renderer;
+use crate::error::MMLResult;
+use crate::ser::write_document;
+use crate::types::*;
+use std::fmt;
+
+/// Word codes of the basic MMLC dictionary
+const DICTIONARY: &[(&str, &str)] = &[
+    ("Rapport", "R"),
+    ("Urgent", "U"),
+    ("Critique", "C"),
+    ("Patient", "P1"),
+    ("Victime", "V"),
+    ("Secteur", "S"),
+    ("Évacuation", "E"),
+    ("Médical", "M"),
+    ("Stable", "S1"),
+    ("Alerte", "A"),
+];
+
+/// Options for [`MmlRenderer`]
+#[derive(Debug, Clone)]
+pub struct MmlOptions {
+    /// Write the canonical form (see [`MMLDocument::canonical_mml`])
+    pub canonical: bool,
+    /// Dictionary compression applied to the written lines
+    pub compression: CompressionLevel,
+}
+
+impl Default for MmlOptions {
+    fn default() -> Self {
+        Self {
+            canonical: false,
+            compression: CompressionLevel::None,
+        }
+    }
+}
+
+/// Writes a document back as MML, one line per node in DOM order
+#[derive(Debug, Clone, Default)]
+pub struct MmlRenderer {
+    options: MmlOptions,
+}
+
+impl MmlRenderer {
+    /// Create a renderer with default options
+    pub fn new() -> Self {
+        Self::with_options(MmlOptions::default())
+    }
+
+    /// Create a renderer with custom options
+    pub fn with_options(options: MmlOptions) -> Self {
+        Self { options }
+    }
+}
+
+impl Renderer for MmlRenderer {
+    fn render(&self, document: &MMLDocument, out: &mut dyn fmt::Write) -> MMLResult<()> {
+        let mml = if self.options.canonical {
+            document.canonical_mml()
+        } else {
+            write_document(document)
+        };
+        out.write_str(&compress(&mml, self.options.compression))?;
+        Ok(())
+    }
+}
+
+/// Replace common words of MML text with their MMLC codes
+///
+/// Works on raw text, so it can compress a file without parsing it.
+pub fn compress(content: &str, level: CompressionLevel) -> String {
+    let mut compressed = content.to_string();
+
+    if level != CompressionLevel::None {
+        for (word, code) in DICTIONARY {
+            compressed = compressed.replace(word, code);
+        }
+    }
+
+    compressed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_mml;
+
+    #[test]
+    fn test_round_trip() {
+        let input = "T:Rapport\nM:Auteur|Équipe\\|A\nH:Situation\nP:Incendie\nL:Carte|https://example.org\nC:rust|fn main() {}\nQ:Tenir|Chef\n";
+        let document = parse_mml(input).unwrap();
+        let mml = MmlRenderer::new().render_to_string(&document).unwrap();
+        assert_eq!(mml, input);
+    }
+
+    #[test]
+    fn test_compress() {
+        assert_eq!(compress("P:Patient Stable", CompressionLevel::Basic), "P:P1 S1");
+        assert_eq!(compress("P:Patient Stable", CompressionLevel::None), "P:Patient Stable");
+    }
+}
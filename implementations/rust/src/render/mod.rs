@@ -0,0 +1,151 @@
+//! Rendering documents to other formats
+//!
+//! Every output format is a [`Renderer`] with its own options struct.
+//! [`OutputFormat::renderer`] picks the built-in renderer with default
+//! options, which is what `mml-cli convert` does.
+//!
+//! # Example
+//!
+//! ```rust
+//! use mml_parser::render::{HtmlOptions, HtmlRenderer};
+//! use mml_parser::{parse_mml, OutputFormat, Renderer};
+//!
+//! let document = parse_mml("T:Rapport\nH:Situation\nP:Incendie en cours").unwrap();
+//!
+//! let fragment = HtmlRenderer::with_options(HtmlOptions {
+//!     standalone: false,
+//!     ..HtmlOptions::default()
+//! });
+//! assert_eq!(
+//!     fragment.render_to_string(&document).unwrap(),
+//!     "<article>\n<h1>Rapport</h1>\n<section>\n<h2>Situation</h2>\n<p>Incendie en cours</p>\n</section>\n</article>\n"
+//! );
+//!
+//! let mml = OutputFormat::MML.renderer().render_to_string(&document).unwrap();
+//! assert_eq!(mml, "T:Rapport\nH:Situation\nP:Incendie en cours\n");
+//! ```
+
+use crate::error::{MMLError, MMLResult};
+use crate::types::*;
+use std::fmt;
+use std::io;
+
+pub mod html;
+pub mod json;
+pub mod markdown;
+pub mod mml;
+pub mod text;
+
+pub use html::{escape_html, HtmlOptions, HtmlRenderer, HtmlTheme};
+pub use json::JsonRenderer;
+pub use markdown::{MarkdownOptions, MarkdownRenderer, MetadataStyle};
+pub use mml::{compress, MmlOptions, MmlRenderer};
+pub use text::{TextOptions, TextRenderer};
+
+/// Converts a document to an output format
+pub trait Renderer {
+    /// Write the rendered document to `out`
+    fn render(&self, document: &MMLDocument, out: &mut dyn fmt::Write) -> MMLResult<()>;
+
+    /// Render the document into a new string
+    fn render_to_string(&self, document: &MMLDocument) -> MMLResult<String> {
+        let mut output = String::new();
+        self.render(document, &mut output)?;
+        Ok(output)
+    }
+
+    /// Render the document into a byte sink such as a file or stdout
+    fn render_to_writer(&self, document: &MMLDocument, writer: &mut dyn io::Write) -> MMLResult<()> {
+        let output = self.render_to_string(document)?;
+        writer.write_all(output.as_bytes()).map_err(|e| MMLError::RenderError {
+            operation: "writing the output".to_string(),
+            error: e.to_string(),
+        })
+    }
+}
+
+impl OutputFormat {
+    /// Built-in renderer for this format, with default options
+    ///
+    /// MMLC is MML with [`CompressionLevel::Basic`].
+    pub fn renderer(self) -> Box<dyn Renderer> {
+        match self {
+            OutputFormat::MML => Box::new(MmlRenderer::new()),
+            OutputFormat::MMLC => Box::new(MmlRenderer::with_options(MmlOptions {
+                compression: CompressionLevel::Basic,
+                ..MmlOptions::default()
+            })),
+            OutputFormat::HTML => Box::new(HtmlRenderer::new()),
+            OutputFormat::JSON => Box::new(JsonRenderer::new()),
+            OutputFormat::Text => Box::new(TextRenderer::new()),
+            OutputFormat::Markdown => Box::new(MarkdownRenderer::new()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_mml;
+
+    #[test]
+    fn test_dispatch_by_format() {
+        let document = parse_mml("T:Rapport Urgent\nH:Situation\nP:Incendie").unwrap();
+
+        let html = OutputFormat::HTML.renderer().render_to_string(&document).unwrap();
+        assert!(html.starts_with("<!DOCTYPE html>"));
+
+        let json = OutputFormat::JSON.renderer().render_to_string(&document).unwrap();
+        assert!(json.contains("\"type\":\"document\""));
+
+        let mmlc = OutputFormat::MMLC.renderer().render_to_string(&document).unwrap();
+        assert_eq!(mmlc, "T:R U\nH:Situation\nP:Incendie\n");
+
+        let text = OutputFormat::Text.renderer().render_to_string(&document).unwrap();
+        assert!(text.starts_with("Rapport Urgent\n=============="));
+
+        let markdown = OutputFormat::Markdown.renderer().render_to_string(&document).unwrap();
+        assert!(markdown.starts_with("# Rapport Urgent\n\n## Situation"));
+    }
+
+    #[test]
+    fn test_render_to_writer() {
+        let document = parse_mml("T:Rapport\nH:Situation\nP:Incendie").unwrap();
+        let mut bytes = Vec::new();
+        MmlRenderer::new().render_to_writer(&document, &mut bytes).unwrap();
+        assert_eq!(bytes, b"T:Rapport\nH:Situation\nP:Incendie\n");
+    }
+
+    #[test]
+    fn test_refused_output() {
+        struct Full;
+
+        impl fmt::Write for Full {
+            fn write_str(&mut self, _: &str) -> fmt::Result {
+                Err(fmt::Error)
+            }
+        }
+
+        impl io::Write for Full {
+            fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+                Err(io::Error::other("disque plein"))
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let document = parse_mml("T:Rapport").unwrap();
+        let result = HtmlRenderer::new().render(&document, &mut Full);
+        assert!(matches!(result, Err(MMLError::RenderError { .. })));
+
+        match MmlRenderer::new().render_to_writer(&document, &mut Full) {
+            Err(MMLError::RenderError { operation, error }) => {
+                assert_eq!(operation, "writing the output");
+                assert_eq!(error, "disque plein");
+            }
+            other => panic!("Expected RenderError, got {:?}", other),
+        }
+    }
+}
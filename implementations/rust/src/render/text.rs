@@ -0,0 +1,253 @@
+//! Plain-text renderer
+
+use super::Renderer;
+use crate::error::MMLResult;
+use crate::types::*;
+use crate::unicode::to_ascii;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Options for [`TextRenderer`]
+#[derive(Debug, Clone)]
+pub struct TextOptions {
+    /// Write metadata as `key: value` lines
+    pub metadata: bool,
+    /// Wrap lines at this many characters; `None` keeps each paragraph on
+    /// one line. Words longer than the width, such as URLs, are not broken.
+    pub width: Option<usize>,
+    /// Transliterate to ASCII (`é` → `e`, `œ` → `oe`, `«` → `"`) for
+    /// thermal printers and SMS
+    pub ascii: bool,
+}
+
+impl Default for TextOptions {
+    fn default() -> Self {
+        Self {
+            metadata: true,
+            width: Some(72),
+            ascii: false,
+        }
+    }
+}
+
+/// Renders a document as readable plain text
+///
+/// The title is underlined with `=`, section titles with `-`; quotes and
+/// code are indented by four spaces. Code is never wrapped.
+#[derive(Debug, Clone, Default)]
+pub struct TextRenderer {
+    options: TextOptions,
+}
+
+impl TextRenderer {
+    /// Create a renderer with default options
+    pub fn new() -> Self {
+        Self::with_options(TextOptions::default())
+    }
+
+    /// Create a renderer with custom options
+    pub fn with_options(options: TextOptions) -> Self {
+        Self { options }
+    }
+
+    /// Text as it will be printed
+    fn printable(&self, text: &str) -> String {
+        if self.options.ascii {
+            to_ascii(text)
+        } else {
+            text.to_string()
+        }
+    }
+
+    /// Wrap `text`, starting the first line with `first` and the next ones
+    /// with `rest`
+    fn wrap(&self, text: &str, first: &str, rest: &str) -> String {
+        let text = self.printable(text);
+        let Some(width) = self.options.width else {
+            return format!("{}{}", first, text.split_whitespace().collect::<Vec<_>>().join(" "));
+        };
+
+        let mut lines = Vec::new();
+        let mut line = first.to_string();
+        let mut empty = true;
+        for word in text.split_whitespace() {
+            let length = line.chars().count() + usize::from(!empty) + word.chars().count();
+            if !empty && length > width {
+                lines.push(line);
+                line = rest.to_string();
+                empty = true;
+            }
+            if !empty {
+                line.push(' ');
+            }
+            line.push_str(word);
+            empty = false;
+        }
+        lines.push(line);
+        lines.join("\n")
+    }
+
+    fn heading(&self, title: &str, mark: char) -> String {
+        let heading = self.wrap(title, "", "");
+        let width = heading.lines().map(|line| line.chars().count()).max().unwrap_or(0);
+        format!("{}\n{}", heading, mark.to_string().repeat(width))
+    }
+
+    fn push_metadata(&self, blocks: &mut Vec<String>, metadata: &HashMap<String, String>) {
+        if !self.options.metadata || metadata.is_empty() {
+            return;
+        }
+        let mut entries: Vec<_> = metadata.iter().collect();
+        entries.sort();
+        blocks.push(
+            entries
+                .iter()
+                .map(|(key, value)| self.wrap(&format!("{}: {}", key, value), "", "  "))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        );
+    }
+
+    fn push_nodes(
+        &self,
+        blocks: &mut Vec<String>,
+        paragraphs: &[String],
+        links: &[MMLLink],
+        images: &[MMLImage],
+        code: &[MMLCode],
+        quotes: &[MMLQuote],
+    ) {
+        let (bullet, open, close, dash) = if self.options.ascii {
+            ("* ", "\"", "\"", "-- ")
+        } else {
+            ("• ", "« ", " »", "— ")
+        };
+
+        for paragraph in paragraphs {
+            blocks.push(self.wrap(paragraph, "", ""));
+        }
+        if !links.is_empty() {
+            blocks.push(
+                links
+                    .iter()
+                    .map(|link| self.wrap(&format!("{}: {}", link.text, link.url), bullet, "  "))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            );
+        }
+        for image in images {
+            blocks.push(self.wrap(&format!("[Image: {}] {}", image.description, image.url), "", "  "));
+        }
+        for block in code {
+            let content = self.printable(&block.content);
+            blocks.push(content.lines().map(|line| format!("    {}", line)).collect::<Vec<_>>().join("\n"));
+        }
+        for quote in quotes {
+            let mut block = self.wrap(&format!("{}{}{}", open, quote.content, close), "    ", "    ");
+            if let Some(source) = &quote.attribution {
+                block.push('\n');
+                block.push_str(&self.wrap(source, &format!("    {}", dash), "      "));
+            }
+            blocks.push(block);
+        }
+    }
+}
+
+impl Renderer for TextRenderer {
+    fn render(&self, document: &MMLDocument, out: &mut dyn fmt::Write) -> MMLResult<()> {
+        let mut blocks = Vec::new();
+
+        if let Some(title) = &document.title {
+            blocks.push(self.heading(title, '='));
+        }
+        self.push_metadata(&mut blocks, &document.metadata);
+        self.push_nodes(
+            &mut blocks,
+            &document.content,
+            &document.links,
+            &document.images,
+            &document.code,
+            &document.quotes,
+        );
+
+        for section in &document.sections {
+            blocks.push(self.heading(&section.title, '-'));
+            self.push_metadata(&mut blocks, &section.metadata);
+            self.push_nodes(
+                &mut blocks,
+                section.content.as_slice(),
+                &section.links,
+                &section.images,
+                &section.code,
+                &section.quotes,
+            );
+        }
+
+        if !blocks.is_empty() {
+            writeln!(out, "{}", blocks.join("\n\n"))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_mml;
+
+    #[test]
+    fn test_plain_text() {
+        let document =
+            parse_mml("T:Rapport\nM:Auteur|Équipe A\nH:Situation\nP:Incendie\nL:Carte|https://example.org").unwrap();
+        let text = TextRenderer::new().render_to_string(&document).unwrap();
+        assert_eq!(
+            text,
+            "Rapport\n=======\n\nAuteur: Équipe A\n\nSituation\n---------\n\nIncendie\n\n• Carte: https://example.org\n"
+        );
+    }
+
+    #[test]
+    fn test_without_metadata() {
+        let document = parse_mml("T:Rapport\nM:Auteur|Équipe A\nH:Situation\nC:ls -la").unwrap();
+        let renderer = TextRenderer::with_options(TextOptions {
+            metadata: false,
+            ..TextOptions::default()
+        });
+        let text = renderer.render_to_string(&document).unwrap();
+        assert!(!text.contains("Auteur"));
+        assert!(text.contains("\n    ls -la\n"));
+    }
+
+    #[test]
+    fn test_wrap_and_quotes() {
+        let document = parse_mml(
+            "H:Consignes\nP:Rejoindre le point de rassemblement https://example.org/points/nord sans courir\nQ:La rapidité sauve des vies|Chef de secteur",
+        )
+        .unwrap();
+        let renderer = TextRenderer::with_options(TextOptions {
+            width: Some(20),
+            ..TextOptions::default()
+        });
+        assert_eq!(
+            renderer.render_to_string(&document).unwrap(),
+            "Consignes\n---------\n\nRejoindre le point\nde rassemblement\nhttps://example.org/points/nord\nsans courir\n\n    « La rapidité\n    sauve des vies »\n    — Chef de\n      secteur\n"
+        );
+    }
+
+    #[test]
+    fn test_ascii_mode() {
+        let document = parse_mml("T:Évacuation\nH:Cœur\nP:Zone à risque\nQ:Tenir|Chef").unwrap();
+        let renderer = TextRenderer::with_options(TextOptions {
+            width: None,
+            ascii: true,
+            ..TextOptions::default()
+        });
+        let text = renderer.render_to_string(&document).unwrap();
+
+        assert!(text.is_ascii());
+        assert_eq!(
+            text,
+            "Evacuation\n==========\n\nCoeur\n-----\n\nZone a risque\n\n    \"Tenir\"\n    -- Chef\n"
+        );
+    }
+}
@@ -0,0 +1,737 @@
+//! Serialize user types to MML
+//!
+//! [`to_string`] is the mirror of [`crate::from_str`]: the value must be a
+//! struct or a map, whose fields become
+//!
+//! - `title`: the `T:` line (or the `H:` title, inside a section)
+//! - `content`: `P:` lines
+//! - `sections`: one `H:` block per element
+//! - `links`, `images`, `code`, `quotes`: `L:`, `IMG:`, `C:` and `Q:` lines,
+//!   from values shaped like [`MMLLink`], [`MMLImage`], [`MMLCode`] and
+//!   [`MMLQuote`]
+//! - any other text, number, boolean or unit variant: an `M:` line
+//! - any other struct or map: an `H:` block named after the field (a
+//!   sequence of them gives one block per element)
+//!
+//! `None` fields are left out. Sections cannot be nested, and line breaks
+//! are only allowed in code. Fields are escaped with the rules of
+//! [`crate::escape`].
+
+use crate::error::{MMLError, MMLResult};
+use crate::escape::is_safe_leading_field;
+use crate::query::MMLNode;
+use crate::types::*;
+use serde::ser::{self, Impossible, Serialize};
+use std::fmt;
+
+/// Serialize a value as MML text
+///
+/// # Example
+///
+/// ```rust
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct Telemetry {
+///     title: String,
+///     #[serde(rename = "Capteur")]
+///     sensor: String,
+///     #[serde(rename = "Batterie")]
+///     battery: u8,
+///     #[serde(rename = "Mesures")]
+///     readings: Readings,
+/// }
+///
+/// #[derive(Serialize)]
+/// struct Readings {
+///     #[serde(rename = "Température")]
+///     temperature: f64,
+/// }
+///
+/// let telemetry = Telemetry {
+///     title: "Relevé station 7".to_string(),
+///     sensor: "ST-07".to_string(),
+///     battery: 87,
+///     readings: Readings { temperature: 23.4 },
+/// };
+///
+/// assert_eq!(
+///     mml_parser::to_string(&telemetry).unwrap(),
+///     "T:Relevé station 7\nM:Batterie|87\nM:Capteur|ST-07\nH:Mesures\nM:Température|23.4\n"
+/// );
+/// ```
+pub fn to_string<T: Serialize + ?Sized>(value: &T) -> MMLResult<String> {
+    Ok(write_document(&to_document(value)?))
+}
+
+/// Write a document in DOM order, one line per node
+///
+/// Packets are transport envelopes and are not written. A `#` line goes
+/// before a code block that follows another one or a metadata line, which
+/// the parser would otherwise read as the same block or a language hint.
+pub(crate) fn write_document(document: &MMLDocument) -> String {
+    write_document_with(document, |_, _| {})
+}
+
+/// [`write_document`], calling `after` once the document-level nodes are
+/// written (with `None`) and after each section (with its title)
+pub(crate) fn write_document_with(
+    document: &MMLDocument,
+    mut after: impl FnMut(&mut Vec<String>, Option<&str>),
+) -> String {
+    let mut lines = Vec::new();
+    let mut previous = None;
+    let mut header_done = false;
+
+    for node in MMLNode::Document(document).children() {
+        match node {
+            MMLNode::Packet(_) => continue,
+            MMLNode::Section(section) => {
+                if !header_done {
+                    after(&mut lines, None);
+                    header_done = true;
+                }
+                lines.push(node.to_string());
+                previous = node.tag();
+                for child in node.children() {
+                    push_node(&mut lines, previous, child);
+                    previous = child.tag();
+                }
+                after(&mut lines, Some(&section.title));
+            }
+            _ => {
+                push_node(&mut lines, previous, node);
+                previous = node.tag();
+            }
+        }
+    }
+    if !header_done {
+        after(&mut lines, None);
+    }
+
+    let mut mml = lines.join("\n");
+    mml.push('\n');
+    mml
+}
+
+fn push_node(lines: &mut Vec<String>, previous: Option<MMLTag>, node: MMLNode<'_>) {
+    if node.tag() == Some(MMLTag::Code) && matches!(previous, Some(MMLTag::Code | MMLTag::Metadata)) {
+        lines.push("#".to_string());
+    }
+    lines.push(node.to_string());
+}
+
+/// Serialize a value into a document
+///
+/// Metadata is unordered in the DOM, so [`to_string`] writes it sorted by key.
+pub fn to_document<T: Serialize + ?Sized>(value: &T) -> MMLResult<MMLDocument> {
+    match value.serialize(ValueSerializer)? {
+        Value::Map(entries) => build_document(entries),
+        _ => Err(MMLError::StructureError {
+            message: "only structs and maps can be written as a document".to_string(),
+            suggestion: "Wrap the value in a struct".to_string(),
+        }),
+    }
+}
+
+impl ser::Error for MMLError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        MMLError::ParseError {
+            message: msg.to_string(),
+            context: "serialization".to_string(),
+        }
+    }
+}
+
+/// Serialized form of a value, before it is laid out as MML
+#[derive(Debug)]
+enum Value {
+    Text(String),
+    Absent,
+    Seq(Vec<Value>),
+    Map(Vec<(String, Value)>),
+}
+
+fn build_document(entries: Vec<(String, Value)>) -> MMLResult<MMLDocument> {
+    let mut document = MMLDocument::default();
+
+    for (key, value) in entries {
+        match (key.as_str(), value) {
+            (_, Value::Absent) => {}
+            ("title", Value::Text(title)) => document.title = Some(single_line(&key, title)?),
+            ("content", Value::Text(content)) => document.content.push(single_line(&key, content)?),
+            ("content", Value::Seq(items)) => {
+                for item in items {
+                    document.content.push(single_line(&key, text(&key, item)?)?);
+                }
+            }
+            ("sections", Value::Seq(items)) => {
+                for item in items {
+                    document.sections.push(build_section(None, item)?);
+                }
+            }
+            ("links", Value::Seq(items)) => document.links = nodes(items, link)?,
+            ("images", Value::Seq(items)) => document.images = nodes(items, image)?,
+            ("code", Value::Seq(items)) => document.code = nodes(items, code)?,
+            ("quotes", Value::Seq(items)) => document.quotes = nodes(items, quote)?,
+            (_, Value::Text(value)) => {
+                document
+                    .metadata
+                    .insert(leading_field(&key, key.clone())?, single_line(&key, value)?);
+            }
+            (_, value @ Value::Map(_)) => document.sections.push(build_section(Some(&key), value)?),
+            (_, Value::Seq(items)) => {
+                for item in items {
+                    document.sections.push(build_section(Some(&key), item)?);
+                }
+            }
+        }
+    }
+
+    Ok(document)
+}
+
+fn build_section(name: Option<&str>, value: Value) -> MMLResult<MMLSection> {
+    let Value::Map(mut entries) = value else {
+        return Err(structure_error(
+            name.unwrap_or("sections"),
+            "expected a struct for an H: block",
+        ));
+    };
+    let title = match take_text(&mut entries, "title") {
+        Some(title) => title,
+        None => match name {
+            Some(name) => name.to_string(),
+            None => return Err(structure_error("sections", "every section needs a title field")),
+        },
+    };
+    let mut section = MMLSection::new(single_line("title", title)?);
+
+    for (key, value) in entries {
+        match (key.as_str(), value) {
+            (_, Value::Absent) => {}
+            ("content", Value::Text(content)) => section.content = Some(single_line(&key, content)?),
+            ("links", Value::Seq(items)) => section.links = nodes(items, link)?,
+            ("images", Value::Seq(items)) => section.images = nodes(items, image)?,
+            ("code", Value::Seq(items)) => section.code = nodes(items, code)?,
+            ("quotes", Value::Seq(items)) => section.quotes = nodes(items, quote)?,
+            (_, Value::Text(value)) => {
+                section
+                    .metadata
+                    .insert(leading_field(&key, key.clone())?, single_line(&key, value)?);
+            }
+            (_, Value::Seq(_) | Value::Map(_)) => {
+                return Err(structure_error(&key, "sections cannot be nested"));
+            }
+        }
+    }
+
+    Ok(section)
+}
+
+fn nodes<T>(items: Vec<Value>, build: fn(Vec<(String, Value)>) -> MMLResult<T>) -> MMLResult<Vec<T>> {
+    items
+        .into_iter()
+        .map(|item| match item {
+            Value::Map(entries) => build(entries),
+            _ => Err(structure_error("node", "expected a struct")),
+        })
+        .collect()
+}
+
+fn link(mut entries: Vec<(String, Value)>) -> MMLResult<MMLLink> {
+    Ok(MMLLink::new(
+        leading_field("text", required(&mut entries, "text")?)?,
+        required(&mut entries, "url")?,
+    ))
+}
+
+fn image(mut entries: Vec<(String, Value)>) -> MMLResult<MMLImage> {
+    Ok(MMLImage::new(
+        leading_field("description", required(&mut entries, "description")?)?,
+        required(&mut entries, "url")?,
+    ))
+}
+
+fn code(mut entries: Vec<(String, Value)>) -> MMLResult<MMLCode> {
+    let content = take_text(&mut entries, "content").ok_or_else(|| structure_error("content", "missing field"))?;
+    let language = take_text(&mut entries, "language")
+        .map(|l| single_line("language", l))
+        .transpose()?;
+    Ok(MMLCode::new(content, language))
+}
+
+fn quote(mut entries: Vec<(String, Value)>) -> MMLResult<MMLQuote> {
+    let content = required(&mut entries, "content")?;
+    let attribution = take_text(&mut entries, "attribution")
+        .map(|a| single_line("attribution", a))
+        .transpose()?;
+    let content = match attribution {
+        Some(_) => leading_field("content", content)?,
+        None => content,
+    };
+    Ok(MMLQuote::new(content, attribution))
+}
+
+fn take_text(entries: &mut Vec<(String, Value)>, name: &str) -> Option<String> {
+    let index = entries
+        .iter()
+        .position(|(key, value)| key == name && matches!(value, Value::Text(_)))?;
+    match entries.remove(index).1 {
+        Value::Text(text) => Some(text),
+        _ => None,
+    }
+}
+
+fn required(entries: &mut Vec<(String, Value)>, name: &str) -> MMLResult<String> {
+    let value = take_text(entries, name).ok_or_else(|| structure_error(name, "missing field"))?;
+    single_line(name, value)
+}
+
+fn text(field: &str, value: Value) -> MMLResult<String> {
+    match value {
+        Value::Text(text) => Ok(text),
+        _ => Err(structure_error(field, "expected text")),
+    }
+}
+
+fn single_line(field: &str, value: String) -> MMLResult<String> {
+    if value.contains(['\n', '\r']) {
+        return Err(MMLError::ValidationError {
+            field: field.to_string(),
+            value,
+            reason: "line breaks can only be written in code blocks".to_string(),
+            suggestion: "Split the value or move it to a code field".to_string(),
+        });
+    }
+    Ok(value)
+}
+
+/// A field followed by `|` on its line, such as a metadata key
+fn leading_field(field: &str, value: String) -> MMLResult<String> {
+    let value = single_line(field, value)?;
+    if !is_safe_leading_field(&value) {
+        return Err(MMLError::ValidationError {
+            field: field.to_string(),
+            value,
+            reason: "a field followed by '|' cannot end with '\\'".to_string(),
+            suggestion: "Remove the trailing backslash".to_string(),
+        });
+    }
+    Ok(value)
+}
+
+fn structure_error(field: &str, message: &str) -> MMLError {
+    MMLError::StructureError {
+        message: format!("{}: {}", field, message),
+        suggestion: "Check the field layout described in the ser module".to_string(),
+    }
+}
+
+/// Serializer producing a [`Value`]
+struct ValueSerializer;
+
+impl ser::Serializer for ValueSerializer {
+    type Ok = Value;
+    type Error = MMLError;
+    type SerializeSeq = SeqSerializer;
+    type SerializeTuple = SeqSerializer;
+    type SerializeTupleStruct = SeqSerializer;
+    type SerializeTupleVariant = Impossible<Value, MMLError>;
+    type SerializeMap = MapSerializer;
+    type SerializeStruct = MapSerializer;
+    type SerializeStructVariant = Impossible<Value, MMLError>;
+
+    fn serialize_bool(self, v: bool) -> MMLResult<Value> {
+        Ok(Value::Text(v.to_string()))
+    }
+
+    fn serialize_i8(self, v: i8) -> MMLResult<Value> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_i16(self, v: i16) -> MMLResult<Value> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_i32(self, v: i32) -> MMLResult<Value> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_i64(self, v: i64) -> MMLResult<Value> {
+        Ok(Value::Text(v.to_string()))
+    }
+
+    fn serialize_u8(self, v: u8) -> MMLResult<Value> {
+        self.serialize_u64(v.into())
+    }
+
+    fn serialize_u16(self, v: u16) -> MMLResult<Value> {
+        self.serialize_u64(v.into())
+    }
+
+    fn serialize_u32(self, v: u32) -> MMLResult<Value> {
+        self.serialize_u64(v.into())
+    }
+
+    fn serialize_u64(self, v: u64) -> MMLResult<Value> {
+        Ok(Value::Text(v.to_string()))
+    }
+
+    fn serialize_f32(self, v: f32) -> MMLResult<Value> {
+        Ok(Value::Text(v.to_string()))
+    }
+
+    fn serialize_f64(self, v: f64) -> MMLResult<Value> {
+        Ok(Value::Text(v.to_string()))
+    }
+
+    fn serialize_char(self, v: char) -> MMLResult<Value> {
+        Ok(Value::Text(v.to_string()))
+    }
+
+    fn serialize_str(self, v: &str) -> MMLResult<Value> {
+        Ok(Value::Text(v.to_string()))
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> MMLResult<Value> {
+        Err(ser::Error::custom("bytes cannot be written as MML"))
+    }
+
+    fn serialize_none(self) -> MMLResult<Value> {
+        Ok(Value::Absent)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> MMLResult<Value> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> MMLResult<Value> {
+        Ok(Value::Absent)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> MMLResult<Value> {
+        Ok(Value::Absent)
+    }
+
+    fn serialize_unit_variant(self, _name: &'static str, _index: u32, variant: &'static str) -> MMLResult<Value> {
+        Ok(Value::Text(variant.to_string()))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _name: &'static str, value: &T) -> MMLResult<Value> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        value: &T,
+    ) -> MMLResult<Value> {
+        value.serialize(self)
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> MMLResult<SeqSerializer> {
+        Ok(SeqSerializer(Vec::with_capacity(len.unwrap_or_default())))
+    }
+
+    fn serialize_tuple(self, len: usize) -> MMLResult<SeqSerializer> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> MMLResult<SeqSerializer> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> MMLResult<Self::SerializeTupleVariant> {
+        Err(ser::Error::custom(format!(
+            "tuple variant `{}` cannot be written as MML",
+            variant
+        )))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> MMLResult<MapSerializer> {
+        Ok(MapSerializer::default())
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> MMLResult<MapSerializer> {
+        Ok(MapSerializer::default())
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> MMLResult<Self::SerializeStructVariant> {
+        Err(ser::Error::custom(format!(
+            "struct variant `{}` cannot be written as MML",
+            variant
+        )))
+    }
+}
+
+struct SeqSerializer(Vec<Value>);
+
+impl ser::SerializeSeq for SeqSerializer {
+    type Ok = Value;
+    type Error = MMLError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> MMLResult<()> {
+        self.0.push(value.serialize(ValueSerializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> MMLResult<Value> {
+        Ok(Value::Seq(self.0))
+    }
+}
+
+impl ser::SerializeTuple for SeqSerializer {
+    type Ok = Value;
+    type Error = MMLError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> MMLResult<()> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> MMLResult<Value> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl ser::SerializeTupleStruct for SeqSerializer {
+    type Ok = Value;
+    type Error = MMLError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> MMLResult<()> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> MMLResult<Value> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+#[derive(Default)]
+struct MapSerializer {
+    entries: Vec<(String, Value)>,
+    key: Option<String>,
+}
+
+impl ser::SerializeMap for MapSerializer {
+    type Ok = Value;
+    type Error = MMLError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> MMLResult<()> {
+        match key.serialize(ValueSerializer)? {
+            Value::Text(key) => {
+                self.key = Some(key);
+                Ok(())
+            }
+            _ => Err(ser::Error::custom("map keys must be text")),
+        }
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> MMLResult<()> {
+        let key = self
+            .key
+            .take()
+            .ok_or_else(|| <MMLError as ser::Error>::custom("value written before key"))?;
+        self.entries.push((key, value.serialize(ValueSerializer)?));
+        Ok(())
+    }
+
+    fn end(self) -> MMLResult<Value> {
+        Ok(Value::Map(self.entries))
+    }
+}
+
+impl ser::SerializeStruct for MapSerializer {
+    type Ok = Value;
+    type Error = MMLError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> MMLResult<()> {
+        self.entries.push((key.to_string(), value.serialize(ValueSerializer)?));
+        Ok(())
+    }
+
+    fn end(self) -> MMLResult<Value> {
+        Ok(Value::Map(self.entries))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::{Deserialize, Serialize};
+    use std::collections::BTreeMap;
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Telemetry {
+        title: String,
+        #[serde(rename = "Capteur")]
+        sensor: String,
+        #[serde(rename = "Mode")]
+        mode: Mode,
+        #[serde(rename = "Batterie")]
+        battery: u8,
+        #[serde(rename = "Alarme")]
+        alarm: bool,
+        #[serde(rename = "Note", skip_serializing_if = "Option::is_none")]
+        note: Option<String>,
+        sections: Vec<Reading>,
+    }
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    enum Mode {
+        #[serde(rename = "veille")]
+        Standby,
+        #[serde(rename = "émission")]
+        Transmit,
+    }
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Reading {
+        title: String,
+        #[serde(rename = "Valeur")]
+        value: f64,
+        #[serde(default)]
+        links: Vec<MMLLink>,
+    }
+
+    fn telemetry() -> Telemetry {
+        Telemetry {
+            title: "Station 7".to_string(),
+            sensor: "ST|07".to_string(),
+            mode: Mode::Transmit,
+            battery: 87,
+            alarm: false,
+            note: None,
+            sections: vec![
+                Reading {
+                    title: "Température".to_string(),
+                    value: 23.4,
+                    links: vec![MMLLink::new("Historique".to_string(), "data/t.csv".to_string())],
+                },
+                Reading {
+                    title: "Humidité".to_string(),
+                    value: 61.0,
+                    links: Vec::new(),
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_serialize_layout_and_escaping() {
+        let mml = to_string(&telemetry()).unwrap();
+        assert_eq!(
+            mml,
+            "T:Station 7\nM:Alarme|false\nM:Batterie|87\nM:Capteur|ST\\|07\nM:Mode|émission\n\
+             H:Température\nM:Valeur|23.4\nL:Historique|data/t.csv\nH:Humidité\nM:Valeur|61\n"
+        );
+    }
+
+    #[test]
+    fn test_round_trip_with_deserializer() {
+        let mml = to_string(&telemetry()).unwrap();
+        let back: Telemetry = crate::from_str(&mml).unwrap();
+        assert_eq!(back, telemetry());
+    }
+
+    #[test]
+    fn test_serialize_maps_and_errors() {
+        let mut values = BTreeMap::new();
+        values.insert("title", "Carte");
+        values.insert("Zone", "B7");
+        assert_eq!(to_string(&values).unwrap(), "T:Carte\nM:Zone|B7\n");
+
+        assert!(matches!(to_string(&42), Err(MMLError::StructureError { .. })));
+
+        values.insert("Zone", "B7\nB8");
+        assert!(matches!(to_string(&values), Err(MMLError::ValidationError { .. })));
+
+        #[derive(Serialize)]
+        struct Nested {
+            sections: Vec<Inner>,
+        }
+        #[derive(Serialize)]
+        struct Inner {
+            title: &'static str,
+            deeper: BTreeMap<&'static str, &'static str>,
+        }
+        let nested = Nested {
+            sections: vec![Inner {
+                title: "A",
+                deeper: BTreeMap::new(),
+            }],
+        };
+        assert!(matches!(to_string(&nested), Err(MMLError::StructureError { .. })));
+
+        let mut values = BTreeMap::new();
+        values.insert("clé\\", "valeur");
+        assert!(matches!(to_string(&values), Err(MMLError::ValidationError { .. })));
+    }
+
+    #[test]
+    fn test_written_documents_parse_back() {
+        let blocks = [
+            MMLCode::new("ls -la".to_string(), Some("bash".to_string())),
+            MMLCode::new("a|b\n    c".to_string(), None),
+            MMLCode::new("x = 1".to_string(), Some("python".to_string())),
+            MMLCode::new("plain".to_string(), None),
+        ];
+        let metadata = [("lang", "rust"), ("Auteur", "A|B")];
+
+        // Small xorshift generator: the same documents on every run
+        let mut state = 0x2545_f491_u32;
+        let mut next = |bound: usize| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as usize % bound
+        };
+
+        for _ in 0..300 {
+            let mut document = MMLDocument {
+                title: Some("Doc".to_string()),
+                ..MMLDocument::default()
+            };
+            if next(2) == 0 {
+                document.metadata.insert("lang".to_string(), "bash".to_string());
+            }
+            document.code = (0..next(3)).map(|_| blocks[next(blocks.len())].clone()).collect();
+
+            for s in 0..next(4) {
+                let mut section = MMLSection::new(format!("Section {}", s));
+                for (key, value) in metadata {
+                    if next(2) == 0 {
+                        section.metadata.insert(key.to_string(), value.to_string());
+                    }
+                }
+                if next(3) == 0 {
+                    section.content = Some("Texte".to_string());
+                }
+                section.code = (0..next(4)).map(|_| blocks[next(blocks.len())].clone()).collect();
+                if next(2) == 0 {
+                    section.quotes.push(MMLQuote::new("Tenir".to_string(), Some("Chef".to_string())));
+                }
+                document.sections.push(section);
+            }
+
+            let mml = write_document(&document);
+            let mut back = crate::parse_mml(&mml).unwrap();
+            back.stats = document.stats.clone();
+            assert_eq!(back, document, "{}", mml);
+        }
+    }
+}
@@ -16,6 +16,16 @@ pub struct MMLDocument {
     pub sections: Vec<MMLSection>,
     /// Global links
     pub links: Vec<MMLLink>,
+    /// Paragraphs that appear before the first section
+    pub content: Vec<String>,
+    /// Images that appear before the first section
+    pub images: Vec<MMLImage>,
+    /// Code blocks that appear before the first section
+    pub code: Vec<MMLCode>,
+    /// Quotes that appear before the first section
+    pub quotes: Vec<MMLQuote>,
+    /// DNF packets (`PKT:` ... `END`) found in the document
+    pub packets: Vec<MMLPacket>,
     /// Parsing statistics
     pub stats: MMLStats,
 }
@@ -27,6 +37,11 @@ impl Default for MMLDocument {
             metadata: HashMap::new(),
             sections: Vec::new(),
             links: Vec::new(),
+            content: Vec::new(),
+            images: Vec::new(),
+            code: Vec::new(),
+            quotes: Vec::new(),
+            packets: Vec::new(),
             stats: MMLStats::default(),
         }
     }
@@ -45,6 +60,10 @@ pub struct MMLSection {
     pub links: Vec<MMLLink>,
     /// Images within this section
     pub images: Vec<MMLImage>,
+    /// Code blocks within this section
+    pub code: Vec<MMLCode>,
+    /// Quotes within this section
+    pub quotes: Vec<MMLQuote>,
 }
 
 impl MMLSection {
@@ -56,6 +75,8 @@ impl MMLSection {
             metadata: HashMap::new(),
             links: Vec::new(),
             images: Vec::new(),
+            code: Vec::new(),
+            quotes: Vec::new(),
         }
     }
 }
@@ -92,6 +113,88 @@ impl MMLImage {
     }
 }
 
+/// Represents a code block in MML
+///
+/// Consecutive `C:` lines are merged into a single block, one line each.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MMLCode {
+    /// Code content, lines separated by `\n`
+    pub content: String,
+    /// Programming language, if announced
+    pub language: Option<String>,
+}
+
+impl MMLCode {
+    /// Create a new code block
+    pub fn new(content: String, language: Option<String>) -> Self {
+        Self { content, language }
+    }
+
+    /// Iterate over the lines of the block, one per `C:` line
+    pub fn lines(&self) -> std::str::Split<'_, char> {
+        self.content.split('\n')
+    }
+
+    /// Whether a `C:lang|` prefix naming `language` is read as a language
+    /// without an `M:lang|` hint
+    pub fn is_known_language(language: &str) -> bool {
+        CODE_LANGUAGES.contains(&language.to_lowercase().as_str())
+    }
+}
+
+/// Languages recognized in a `C:lang|` prefix: the annexe D list, then other
+/// common names
+///
+/// Any other prefix is part of the code (`C:ls|wc -l`), unless an `M:lang|`
+/// line just before announces it.
+pub const CODE_LANGUAGES: &[&str] = &[
+    "javascript", "python", "java", "cpp", "c", "bash", "sql", "json", "xml", "html",
+    "c++", "c#", "csharp", "css", "diff", "dockerfile", "go", "haskell", "ini", "js", "kotlin", "lua",
+    "makefile", "markdown", "ocaml", "perl", "php", "powershell", "py", "r", "ruby", "rust", "scala", "sh",
+    "shell", "swift", "toml", "ts", "typescript", "yaml", "zsh",
+];
+
+/// Represents a quote in MML
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MMLQuote {
+    /// Quoted text
+    pub content: String,
+    /// Source of the quote (`Q:text|source`), if given
+    pub attribution: Option<String>,
+}
+
+impl MMLQuote {
+    /// Create a new quote
+    pub fn new(content: String, attribution: Option<String>) -> Self {
+        Self { content, attribution }
+    }
+}
+
+/// Represents a DNF packet envelope (`PKT:ID[:KEY=VALUE...]` ... `END`)
+///
+/// Lines inside the packet are parsed into the document as usual; the
+/// packet only records the transport header.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MMLPacket {
+    /// Packet identifier
+    pub id: String,
+    /// Transport headers such as `TTL` or `PRIORITY`
+    pub headers: HashMap<String, String>,
+    /// Whether the closing `END` line was seen
+    pub terminated: bool,
+}
+
+impl MMLPacket {
+    /// Create a new, not yet terminated packet
+    pub fn new(id: String) -> Self {
+        Self {
+            id,
+            headers: HashMap::new(),
+            terminated: false,
+        }
+    }
+}
+
 /// Parsing statistics and metadata
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct MMLStats {
@@ -129,6 +232,11 @@ pub enum MMLTag {
     Quote,
     Config,
     Packet,
+    End,
+    /// Profile rule (`RULE:`), see [`crate::profile`]
+    Rule,
+    /// Integrity hash (`HASH:`), see [`crate::canonical`]
+    Hash,
     Unknown,
 }
 
@@ -146,6 +254,9 @@ impl MMLTag {
             MMLTag::Quote => "Q",
             MMLTag::Config => "CFG",
             MMLTag::Packet => "PKT",
+            MMLTag::End => "END",
+            MMLTag::Rule => "RULE",
+            MMLTag::Hash => "HASH",
             MMLTag::Unknown => "UNKNOWN",
         }
     }
@@ -163,6 +274,9 @@ impl MMLTag {
             "Q" => MMLTag::Quote,
             "CFG" => MMLTag::Config,
             "PKT" => MMLTag::Packet,
+            "END" => MMLTag::End,
+            "RULE" => MMLTag::Rule,
+            "HASH" => MMLTag::Hash,
             _ => MMLTag::Unknown,
         }
     }
@@ -196,6 +310,10 @@ pub enum OutputFormat {
     JSON,
     /// Compressed MMLC
     MMLC,
+    /// Plain text
+    Text,
+    /// CommonMark
+    Markdown,
 }
 
 impl Default for OutputFormat {
@@ -214,6 +332,8 @@ mod tests {
         assert!(doc.title.is_none());
         assert!(doc.metadata.is_empty());
         assert!(doc.sections.is_empty());
+        assert!(doc.content.is_empty());
+        assert!(doc.images.is_empty());
     }
 
     #[test]
@@ -231,6 +351,12 @@ mod tests {
         assert_eq!(link.url, "https://example.com");
     }
 
+    #[test]
+    fn test_code_lines() {
+        let code = MMLCode::new("2 + 3 = 5\n\n4 + 1 = 5".to_string(), None);
+        assert_eq!(code.lines().collect::<Vec<_>>(), vec!["2 + 3 = 5", "", "4 + 1 = 5"]);
+    }
+
     #[test]
     fn test_tag_conversion() {
         assert_eq!(MMLTag::Title.to_str(), "T");
@@ -0,0 +1,700 @@
+//! Unicode normalization for Latin scripts
+//!
+//! A full NFC implementation needs the Unicode database; this module covers
+//! the precomposed Latin letters (U+00C0..U+024F and U+1E00..U+1EFF) and
+//! their combining marks, which is what MML documents in French and other
+//! Latin-script languages contain. Other characters pass through unchanged.
+//! The same table drives [`to_ascii`], which drops accents for devices that
+//! only print ASCII.
+
+use std::collections::HashMap;
+
+/// Canonical composition of `s` (NFC, within the covered range)
+pub(crate) fn nfc(s: &str) -> String {
+    let mut chars = decompose(s);
+    compose(&mut chars);
+    chars.into_iter().collect()
+}
+
+/// Letters and punctuation without a decomposition to an ASCII base
+const ASCII_FALLBACKS: &[(char, &str)] = &[
+    ('\u{a0}', " "),
+    ('«', "\""),
+    ('»', "\""),
+    ('‘', "'"),
+    ('’', "'"),
+    ('“', "\""),
+    ('”', "\""),
+    ('–', "-"),
+    ('—', "-"),
+    ('…', "..."),
+    ('•', "*"),
+    ('°', "deg"),
+    ('€', "EUR"),
+    ('£', "GBP"),
+    ('×', "x"),
+    ('Æ', "AE"),
+    ('æ', "ae"),
+    ('Œ', "OE"),
+    ('œ', "oe"),
+    ('ß', "ss"),
+    ('Ø', "O"),
+    ('ø', "o"),
+    ('Đ', "D"),
+    ('đ', "d"),
+    ('Ł', "L"),
+    ('ł', "l"),
+    ('Þ', "Th"),
+    ('þ', "th"),
+];
+
+/// ASCII transliteration: accents dropped, ligatures spelled out, typographic
+/// punctuation replaced; anything else becomes `?`
+pub(crate) fn to_ascii(s: &str) -> String {
+    let mut ascii = String::with_capacity(s.len());
+    let mut decomposed = Vec::new();
+
+    for c in s.chars() {
+        if c.is_ascii() {
+            ascii.push(c);
+            continue;
+        }
+        if let Some((_, replacement)) = ASCII_FALLBACKS.iter().find(|(from, _)| *from == c) {
+            ascii.push_str(replacement);
+            continue;
+        }
+        decomposed.clear();
+        push_decomposed(&mut decomposed, c);
+        match decomposed.first() {
+            Some(&base) if base.is_ascii() => ascii.push(base),
+            _ if combining_class(c) > 0 => {}
+            _ => ascii.push('?'),
+        }
+    }
+    ascii
+}
+
+fn decompose(s: &str) -> Vec<char> {
+    let mut chars = Vec::with_capacity(s.len());
+    for c in s.chars() {
+        push_decomposed(&mut chars, c);
+    }
+
+    // Canonical ordering: stable sort of each run of marks by class
+    let mut start = 0;
+    while start < chars.len() {
+        if combining_class(chars[start]) == 0 {
+            start += 1;
+            continue;
+        }
+        let end = (start..chars.len())
+            .find(|&i| combining_class(chars[i]) == 0)
+            .unwrap_or(chars.len());
+        chars[start..end].sort_by_key(|&c| combining_class(c));
+        start = end;
+    }
+    chars
+}
+
+fn push_decomposed(chars: &mut Vec<char>, c: char) {
+    match COMPOSITIONS.binary_search_by_key(&c, |&(composed, _, _)| composed) {
+        Ok(index) => {
+            let (_, base, mark) = COMPOSITIONS[index];
+            push_decomposed(chars, base);
+            chars.push(mark);
+        }
+        Err(_) => chars.push(c),
+    }
+}
+
+fn compose(chars: &mut Vec<char>) {
+    lazy_static::lazy_static! {
+        static ref PAIRS: HashMap<(char, char), char> =
+            COMPOSITIONS.iter().map(|&(composed, base, mark)| ((base, mark), composed)).collect();
+    }
+
+    let mut starter: Option<usize> = None;
+    let mut last_class = 0;
+    let mut out = 0;
+
+    for i in 0..chars.len() {
+        let c = chars[i];
+        let class = combining_class(c);
+        if let Some(s) = starter {
+            let blocked = out > s + 1 && last_class >= class;
+            if !blocked || (last_class == 0 && out == s + 1) {
+                if let Some(&composed) = PAIRS.get(&(chars[s], c)) {
+                    chars[s] = composed;
+                    continue;
+                }
+            }
+        }
+        if class == 0 {
+            starter = Some(out);
+        }
+        last_class = class;
+        chars[out] = c;
+        out += 1;
+    }
+    chars.truncate(out);
+}
+
+fn combining_class(c: char) -> u8 {
+    COMBINING_CLASSES
+        .binary_search_by_key(&c, |&(mark, _)| mark)
+        .map_or(0, |index| COMBINING_CLASSES[index].1)
+}
+
+/// Combining marks used by [`COMPOSITIONS`], with their canonical class
+const COMBINING_CLASSES: &[(char, u8)] = &[
+    ('\u{300}', 230),
+    ('\u{301}', 230),
+    ('\u{302}', 230),
+    ('\u{303}', 230),
+    ('\u{304}', 230),
+    ('\u{306}', 230),
+    ('\u{307}', 230),
+    ('\u{308}', 230),
+    ('\u{309}', 230),
+    ('\u{30a}', 230),
+    ('\u{30b}', 230),
+    ('\u{30c}', 230),
+    ('\u{30f}', 230),
+    ('\u{311}', 230),
+    ('\u{31b}', 216),
+    ('\u{323}', 220),
+    ('\u{324}', 220),
+    ('\u{325}', 220),
+    ('\u{326}', 220),
+    ('\u{327}', 202),
+    ('\u{328}', 202),
+    ('\u{32d}', 220),
+    ('\u{32e}', 220),
+    ('\u{330}', 220),
+    ('\u{331}', 220),
+];
+
+/// `(composed, base, mark)`, sorted by composed character
+///
+/// Generated from UnicodeData.txt, excluding composition exclusions.
+const COMPOSITIONS: &[(char, char, char)] = &[
+    ('\u{c0}', 'A', '\u{300}'),
+    ('\u{c1}', 'A', '\u{301}'),
+    ('\u{c2}', 'A', '\u{302}'),
+    ('\u{c3}', 'A', '\u{303}'),
+    ('\u{c4}', 'A', '\u{308}'),
+    ('\u{c5}', 'A', '\u{30a}'),
+    ('\u{c7}', 'C', '\u{327}'),
+    ('\u{c8}', 'E', '\u{300}'),
+    ('\u{c9}', 'E', '\u{301}'),
+    ('\u{ca}', 'E', '\u{302}'),
+    ('\u{cb}', 'E', '\u{308}'),
+    ('\u{cc}', 'I', '\u{300}'),
+    ('\u{cd}', 'I', '\u{301}'),
+    ('\u{ce}', 'I', '\u{302}'),
+    ('\u{cf}', 'I', '\u{308}'),
+    ('\u{d1}', 'N', '\u{303}'),
+    ('\u{d2}', 'O', '\u{300}'),
+    ('\u{d3}', 'O', '\u{301}'),
+    ('\u{d4}', 'O', '\u{302}'),
+    ('\u{d5}', 'O', '\u{303}'),
+    ('\u{d6}', 'O', '\u{308}'),
+    ('\u{d9}', 'U', '\u{300}'),
+    ('\u{da}', 'U', '\u{301}'),
+    ('\u{db}', 'U', '\u{302}'),
+    ('\u{dc}', 'U', '\u{308}'),
+    ('\u{dd}', 'Y', '\u{301}'),
+    ('\u{e0}', 'a', '\u{300}'),
+    ('\u{e1}', 'a', '\u{301}'),
+    ('\u{e2}', 'a', '\u{302}'),
+    ('\u{e3}', 'a', '\u{303}'),
+    ('\u{e4}', 'a', '\u{308}'),
+    ('\u{e5}', 'a', '\u{30a}'),
+    ('\u{e7}', 'c', '\u{327}'),
+    ('\u{e8}', 'e', '\u{300}'),
+    ('\u{e9}', 'e', '\u{301}'),
+    ('\u{ea}', 'e', '\u{302}'),
+    ('\u{eb}', 'e', '\u{308}'),
+    ('\u{ec}', 'i', '\u{300}'),
+    ('\u{ed}', 'i', '\u{301}'),
+    ('\u{ee}', 'i', '\u{302}'),
+    ('\u{ef}', 'i', '\u{308}'),
+    ('\u{f1}', 'n', '\u{303}'),
+    ('\u{f2}', 'o', '\u{300}'),
+    ('\u{f3}', 'o', '\u{301}'),
+    ('\u{f4}', 'o', '\u{302}'),
+    ('\u{f5}', 'o', '\u{303}'),
+    ('\u{f6}', 'o', '\u{308}'),
+    ('\u{f9}', 'u', '\u{300}'),
+    ('\u{fa}', 'u', '\u{301}'),
+    ('\u{fb}', 'u', '\u{302}'),
+    ('\u{fc}', 'u', '\u{308}'),
+    ('\u{fd}', 'y', '\u{301}'),
+    ('\u{ff}', 'y', '\u{308}'),
+    ('\u{100}', 'A', '\u{304}'),
+    ('\u{101}', 'a', '\u{304}'),
+    ('\u{102}', 'A', '\u{306}'),
+    ('\u{103}', 'a', '\u{306}'),
+    ('\u{104}', 'A', '\u{328}'),
+    ('\u{105}', 'a', '\u{328}'),
+    ('\u{106}', 'C', '\u{301}'),
+    ('\u{107}', 'c', '\u{301}'),
+    ('\u{108}', 'C', '\u{302}'),
+    ('\u{109}', 'c', '\u{302}'),
+    ('\u{10a}', 'C', '\u{307}'),
+    ('\u{10b}', 'c', '\u{307}'),
+    ('\u{10c}', 'C', '\u{30c}'),
+    ('\u{10d}', 'c', '\u{30c}'),
+    ('\u{10e}', 'D', '\u{30c}'),
+    ('\u{10f}', 'd', '\u{30c}'),
+    ('\u{112}', 'E', '\u{304}'),
+    ('\u{113}', 'e', '\u{304}'),
+    ('\u{114}', 'E', '\u{306}'),
+    ('\u{115}', 'e', '\u{306}'),
+    ('\u{116}', 'E', '\u{307}'),
+    ('\u{117}', 'e', '\u{307}'),
+    ('\u{118}', 'E', '\u{328}'),
+    ('\u{119}', 'e', '\u{328}'),
+    ('\u{11a}', 'E', '\u{30c}'),
+    ('\u{11b}', 'e', '\u{30c}'),
+    ('\u{11c}', 'G', '\u{302}'),
+    ('\u{11d}', 'g', '\u{302}'),
+    ('\u{11e}', 'G', '\u{306}'),
+    ('\u{11f}', 'g', '\u{306}'),
+    ('\u{120}', 'G', '\u{307}'),
+    ('\u{121}', 'g', '\u{307}'),
+    ('\u{122}', 'G', '\u{327}'),
+    ('\u{123}', 'g', '\u{327}'),
+    ('\u{124}', 'H', '\u{302}'),
+    ('\u{125}', 'h', '\u{302}'),
+    ('\u{128}', 'I', '\u{303}'),
+    ('\u{129}', 'i', '\u{303}'),
+    ('\u{12a}', 'I', '\u{304}'),
+    ('\u{12b}', 'i', '\u{304}'),
+    ('\u{12c}', 'I', '\u{306}'),
+    ('\u{12d}', 'i', '\u{306}'),
+    ('\u{12e}', 'I', '\u{328}'),
+    ('\u{12f}', 'i', '\u{328}'),
+    ('\u{130}', 'I', '\u{307}'),
+    ('\u{134}', 'J', '\u{302}'),
+    ('\u{135}', 'j', '\u{302}'),
+    ('\u{136}', 'K', '\u{327}'),
+    ('\u{137}', 'k', '\u{327}'),
+    ('\u{139}', 'L', '\u{301}'),
+    ('\u{13a}', 'l', '\u{301}'),
+    ('\u{13b}', 'L', '\u{327}'),
+    ('\u{13c}', 'l', '\u{327}'),
+    ('\u{13d}', 'L', '\u{30c}'),
+    ('\u{13e}', 'l', '\u{30c}'),
+    ('\u{143}', 'N', '\u{301}'),
+    ('\u{144}', 'n', '\u{301}'),
+    ('\u{145}', 'N', '\u{327}'),
+    ('\u{146}', 'n', '\u{327}'),
+    ('\u{147}', 'N', '\u{30c}'),
+    ('\u{148}', 'n', '\u{30c}'),
+    ('\u{14c}', 'O', '\u{304}'),
+    ('\u{14d}', 'o', '\u{304}'),
+    ('\u{14e}', 'O', '\u{306}'),
+    ('\u{14f}', 'o', '\u{306}'),
+    ('\u{150}', 'O', '\u{30b}'),
+    ('\u{151}', 'o', '\u{30b}'),
+    ('\u{154}', 'R', '\u{301}'),
+    ('\u{155}', 'r', '\u{301}'),
+    ('\u{156}', 'R', '\u{327}'),
+    ('\u{157}', 'r', '\u{327}'),
+    ('\u{158}', 'R', '\u{30c}'),
+    ('\u{159}', 'r', '\u{30c}'),
+    ('\u{15a}', 'S', '\u{301}'),
+    ('\u{15b}', 's', '\u{301}'),
+    ('\u{15c}', 'S', '\u{302}'),
+    ('\u{15d}', 's', '\u{302}'),
+    ('\u{15e}', 'S', '\u{327}'),
+    ('\u{15f}', 's', '\u{327}'),
+    ('\u{160}', 'S', '\u{30c}'),
+    ('\u{161}', 's', '\u{30c}'),
+    ('\u{162}', 'T', '\u{327}'),
+    ('\u{163}', 't', '\u{327}'),
+    ('\u{164}', 'T', '\u{30c}'),
+    ('\u{165}', 't', '\u{30c}'),
+    ('\u{168}', 'U', '\u{303}'),
+    ('\u{169}', 'u', '\u{303}'),
+    ('\u{16a}', 'U', '\u{304}'),
+    ('\u{16b}', 'u', '\u{304}'),
+    ('\u{16c}', 'U', '\u{306}'),
+    ('\u{16d}', 'u', '\u{306}'),
+    ('\u{16e}', 'U', '\u{30a}'),
+    ('\u{16f}', 'u', '\u{30a}'),
+    ('\u{170}', 'U', '\u{30b}'),
+    ('\u{171}', 'u', '\u{30b}'),
+    ('\u{172}', 'U', '\u{328}'),
+    ('\u{173}', 'u', '\u{328}'),
+    ('\u{174}', 'W', '\u{302}'),
+    ('\u{175}', 'w', '\u{302}'),
+    ('\u{176}', 'Y', '\u{302}'),
+    ('\u{177}', 'y', '\u{302}'),
+    ('\u{178}', 'Y', '\u{308}'),
+    ('\u{179}', 'Z', '\u{301}'),
+    ('\u{17a}', 'z', '\u{301}'),
+    ('\u{17b}', 'Z', '\u{307}'),
+    ('\u{17c}', 'z', '\u{307}'),
+    ('\u{17d}', 'Z', '\u{30c}'),
+    ('\u{17e}', 'z', '\u{30c}'),
+    ('\u{1a0}', 'O', '\u{31b}'),
+    ('\u{1a1}', 'o', '\u{31b}'),
+    ('\u{1af}', 'U', '\u{31b}'),
+    ('\u{1b0}', 'u', '\u{31b}'),
+    ('\u{1cd}', 'A', '\u{30c}'),
+    ('\u{1ce}', 'a', '\u{30c}'),
+    ('\u{1cf}', 'I', '\u{30c}'),
+    ('\u{1d0}', 'i', '\u{30c}'),
+    ('\u{1d1}', 'O', '\u{30c}'),
+    ('\u{1d2}', 'o', '\u{30c}'),
+    ('\u{1d3}', 'U', '\u{30c}'),
+    ('\u{1d4}', 'u', '\u{30c}'),
+    ('\u{1d5}', '\u{dc}', '\u{304}'),
+    ('\u{1d6}', '\u{fc}', '\u{304}'),
+    ('\u{1d7}', '\u{dc}', '\u{301}'),
+    ('\u{1d8}', '\u{fc}', '\u{301}'),
+    ('\u{1d9}', '\u{dc}', '\u{30c}'),
+    ('\u{1da}', '\u{fc}', '\u{30c}'),
+    ('\u{1db}', '\u{dc}', '\u{300}'),
+    ('\u{1dc}', '\u{fc}', '\u{300}'),
+    ('\u{1de}', '\u{c4}', '\u{304}'),
+    ('\u{1df}', '\u{e4}', '\u{304}'),
+    ('\u{1e0}', '\u{226}', '\u{304}'),
+    ('\u{1e1}', '\u{227}', '\u{304}'),
+    ('\u{1e2}', '\u{c6}', '\u{304}'),
+    ('\u{1e3}', '\u{e6}', '\u{304}'),
+    ('\u{1e6}', 'G', '\u{30c}'),
+    ('\u{1e7}', 'g', '\u{30c}'),
+    ('\u{1e8}', 'K', '\u{30c}'),
+    ('\u{1e9}', 'k', '\u{30c}'),
+    ('\u{1ea}', 'O', '\u{328}'),
+    ('\u{1eb}', 'o', '\u{328}'),
+    ('\u{1ec}', '\u{1ea}', '\u{304}'),
+    ('\u{1ed}', '\u{1eb}', '\u{304}'),
+    ('\u{1ee}', '\u{1b7}', '\u{30c}'),
+    ('\u{1ef}', '\u{292}', '\u{30c}'),
+    ('\u{1f0}', 'j', '\u{30c}'),
+    ('\u{1f4}', 'G', '\u{301}'),
+    ('\u{1f5}', 'g', '\u{301}'),
+    ('\u{1f8}', 'N', '\u{300}'),
+    ('\u{1f9}', 'n', '\u{300}'),
+    ('\u{1fa}', '\u{c5}', '\u{301}'),
+    ('\u{1fb}', '\u{e5}', '\u{301}'),
+    ('\u{1fc}', '\u{c6}', '\u{301}'),
+    ('\u{1fd}', '\u{e6}', '\u{301}'),
+    ('\u{1fe}', '\u{d8}', '\u{301}'),
+    ('\u{1ff}', '\u{f8}', '\u{301}'),
+    ('\u{200}', 'A', '\u{30f}'),
+    ('\u{201}', 'a', '\u{30f}'),
+    ('\u{202}', 'A', '\u{311}'),
+    ('\u{203}', 'a', '\u{311}'),
+    ('\u{204}', 'E', '\u{30f}'),
+    ('\u{205}', 'e', '\u{30f}'),
+    ('\u{206}', 'E', '\u{311}'),
+    ('\u{207}', 'e', '\u{311}'),
+    ('\u{208}', 'I', '\u{30f}'),
+    ('\u{209}', 'i', '\u{30f}'),
+    ('\u{20a}', 'I', '\u{311}'),
+    ('\u{20b}', 'i', '\u{311}'),
+    ('\u{20c}', 'O', '\u{30f}'),
+    ('\u{20d}', 'o', '\u{30f}'),
+    ('\u{20e}', 'O', '\u{311}'),
+    ('\u{20f}', 'o', '\u{311}'),
+    ('\u{210}', 'R', '\u{30f}'),
+    ('\u{211}', 'r', '\u{30f}'),
+    ('\u{212}', 'R', '\u{311}'),
+    ('\u{213}', 'r', '\u{311}'),
+    ('\u{214}', 'U', '\u{30f}'),
+    ('\u{215}', 'u', '\u{30f}'),
+    ('\u{216}', 'U', '\u{311}'),
+    ('\u{217}', 'u', '\u{311}'),
+    ('\u{218}', 'S', '\u{326}'),
+    ('\u{219}', 's', '\u{326}'),
+    ('\u{21a}', 'T', '\u{326}'),
+    ('\u{21b}', 't', '\u{326}'),
+    ('\u{21e}', 'H', '\u{30c}'),
+    ('\u{21f}', 'h', '\u{30c}'),
+    ('\u{226}', 'A', '\u{307}'),
+    ('\u{227}', 'a', '\u{307}'),
+    ('\u{228}', 'E', '\u{327}'),
+    ('\u{229}', 'e', '\u{327}'),
+    ('\u{22a}', '\u{d6}', '\u{304}'),
+    ('\u{22b}', '\u{f6}', '\u{304}'),
+    ('\u{22c}', '\u{d5}', '\u{304}'),
+    ('\u{22d}', '\u{f5}', '\u{304}'),
+    ('\u{22e}', 'O', '\u{307}'),
+    ('\u{22f}', 'o', '\u{307}'),
+    ('\u{230}', '\u{22e}', '\u{304}'),
+    ('\u{231}', '\u{22f}', '\u{304}'),
+    ('\u{232}', 'Y', '\u{304}'),
+    ('\u{233}', 'y', '\u{304}'),
+    ('\u{1e00}', 'A', '\u{325}'),
+    ('\u{1e01}', 'a', '\u{325}'),
+    ('\u{1e02}', 'B', '\u{307}'),
+    ('\u{1e03}', 'b', '\u{307}'),
+    ('\u{1e04}', 'B', '\u{323}'),
+    ('\u{1e05}', 'b', '\u{323}'),
+    ('\u{1e06}', 'B', '\u{331}'),
+    ('\u{1e07}', 'b', '\u{331}'),
+    ('\u{1e08}', '\u{c7}', '\u{301}'),
+    ('\u{1e09}', '\u{e7}', '\u{301}'),
+    ('\u{1e0a}', 'D', '\u{307}'),
+    ('\u{1e0b}', 'd', '\u{307}'),
+    ('\u{1e0c}', 'D', '\u{323}'),
+    ('\u{1e0d}', 'd', '\u{323}'),
+    ('\u{1e0e}', 'D', '\u{331}'),
+    ('\u{1e0f}', 'd', '\u{331}'),
+    ('\u{1e10}', 'D', '\u{327}'),
+    ('\u{1e11}', 'd', '\u{327}'),
+    ('\u{1e12}', 'D', '\u{32d}'),
+    ('\u{1e13}', 'd', '\u{32d}'),
+    ('\u{1e14}', '\u{112}', '\u{300}'),
+    ('\u{1e15}', '\u{113}', '\u{300}'),
+    ('\u{1e16}', '\u{112}', '\u{301}'),
+    ('\u{1e17}', '\u{113}', '\u{301}'),
+    ('\u{1e18}', 'E', '\u{32d}'),
+    ('\u{1e19}', 'e', '\u{32d}'),
+    ('\u{1e1a}', 'E', '\u{330}'),
+    ('\u{1e1b}', 'e', '\u{330}'),
+    ('\u{1e1c}', '\u{228}', '\u{306}'),
+    ('\u{1e1d}', '\u{229}', '\u{306}'),
+    ('\u{1e1e}', 'F', '\u{307}'),
+    ('\u{1e1f}', 'f', '\u{307}'),
+    ('\u{1e20}', 'G', '\u{304}'),
+    ('\u{1e21}', 'g', '\u{304}'),
+    ('\u{1e22}', 'H', '\u{307}'),
+    ('\u{1e23}', 'h', '\u{307}'),
+    ('\u{1e24}', 'H', '\u{323}'),
+    ('\u{1e25}', 'h', '\u{323}'),
+    ('\u{1e26}', 'H', '\u{308}'),
+    ('\u{1e27}', 'h', '\u{308}'),
+    ('\u{1e28}', 'H', '\u{327}'),
+    ('\u{1e29}', 'h', '\u{327}'),
+    ('\u{1e2a}', 'H', '\u{32e}'),
+    ('\u{1e2b}', 'h', '\u{32e}'),
+    ('\u{1e2c}', 'I', '\u{330}'),
+    ('\u{1e2d}', 'i', '\u{330}'),
+    ('\u{1e2e}', '\u{cf}', '\u{301}'),
+    ('\u{1e2f}', '\u{ef}', '\u{301}'),
+    ('\u{1e30}', 'K', '\u{301}'),
+    ('\u{1e31}', 'k', '\u{301}'),
+    ('\u{1e32}', 'K', '\u{323}'),
+    ('\u{1e33}', 'k', '\u{323}'),
+    ('\u{1e34}', 'K', '\u{331}'),
+    ('\u{1e35}', 'k', '\u{331}'),
+    ('\u{1e36}', 'L', '\u{323}'),
+    ('\u{1e37}', 'l', '\u{323}'),
+    ('\u{1e38}', '\u{1e36}', '\u{304}'),
+    ('\u{1e39}', '\u{1e37}', '\u{304}'),
+    ('\u{1e3a}', 'L', '\u{331}'),
+    ('\u{1e3b}', 'l', '\u{331}'),
+    ('\u{1e3c}', 'L', '\u{32d}'),
+    ('\u{1e3d}', 'l', '\u{32d}'),
+    ('\u{1e3e}', 'M', '\u{301}'),
+    ('\u{1e3f}', 'm', '\u{301}'),
+    ('\u{1e40}', 'M', '\u{307}'),
+    ('\u{1e41}', 'm', '\u{307}'),
+    ('\u{1e42}', 'M', '\u{323}'),
+    ('\u{1e43}', 'm', '\u{323}'),
+    ('\u{1e44}', 'N', '\u{307}'),
+    ('\u{1e45}', 'n', '\u{307}'),
+    ('\u{1e46}', 'N', '\u{323}'),
+    ('\u{1e47}', 'n', '\u{323}'),
+    ('\u{1e48}', 'N', '\u{331}'),
+    ('\u{1e49}', 'n', '\u{331}'),
+    ('\u{1e4a}', 'N', '\u{32d}'),
+    ('\u{1e4b}', 'n', '\u{32d}'),
+    ('\u{1e4c}', '\u{d5}', '\u{301}'),
+    ('\u{1e4d}', '\u{f5}', '\u{301}'),
+    ('\u{1e4e}', '\u{d5}', '\u{308}'),
+    ('\u{1e4f}', '\u{f5}', '\u{308}'),
+    ('\u{1e50}', '\u{14c}', '\u{300}'),
+    ('\u{1e51}', '\u{14d}', '\u{300}'),
+    ('\u{1e52}', '\u{14c}', '\u{301}'),
+    ('\u{1e53}', '\u{14d}', '\u{301}'),
+    ('\u{1e54}', 'P', '\u{301}'),
+    ('\u{1e55}', 'p', '\u{301}'),
+    ('\u{1e56}', 'P', '\u{307}'),
+    ('\u{1e57}', 'p', '\u{307}'),
+    ('\u{1e58}', 'R', '\u{307}'),
+    ('\u{1e59}', 'r', '\u{307}'),
+    ('\u{1e5a}', 'R', '\u{323}'),
+    ('\u{1e5b}', 'r', '\u{323}'),
+    ('\u{1e5c}', '\u{1e5a}', '\u{304}'),
+    ('\u{1e5d}', '\u{1e5b}', '\u{304}'),
+    ('\u{1e5e}', 'R', '\u{331}'),
+    ('\u{1e5f}', 'r', '\u{331}'),
+    ('\u{1e60}', 'S', '\u{307}'),
+    ('\u{1e61}', 's', '\u{307}'),
+    ('\u{1e62}', 'S', '\u{323}'),
+    ('\u{1e63}', 's', '\u{323}'),
+    ('\u{1e64}', '\u{15a}', '\u{307}'),
+    ('\u{1e65}', '\u{15b}', '\u{307}'),
+    ('\u{1e66}', '\u{160}', '\u{307}'),
+    ('\u{1e67}', '\u{161}', '\u{307}'),
+    ('\u{1e68}', '\u{1e62}', '\u{307}'),
+    ('\u{1e69}', '\u{1e63}', '\u{307}'),
+    ('\u{1e6a}', 'T', '\u{307}'),
+    ('\u{1e6b}', 't', '\u{307}'),
+    ('\u{1e6c}', 'T', '\u{323}'),
+    ('\u{1e6d}', 't', '\u{323}'),
+    ('\u{1e6e}', 'T', '\u{331}'),
+    ('\u{1e6f}', 't', '\u{331}'),
+    ('\u{1e70}', 'T', '\u{32d}'),
+    ('\u{1e71}', 't', '\u{32d}'),
+    ('\u{1e72}', 'U', '\u{324}'),
+    ('\u{1e73}', 'u', '\u{324}'),
+    ('\u{1e74}', 'U', '\u{330}'),
+    ('\u{1e75}', 'u', '\u{330}'),
+    ('\u{1e76}', 'U', '\u{32d}'),
+    ('\u{1e77}', 'u', '\u{32d}'),
+    ('\u{1e78}', '\u{168}', '\u{301}'),
+    ('\u{1e79}', '\u{169}', '\u{301}'),
+    ('\u{1e7a}', '\u{16a}', '\u{308}'),
+    ('\u{1e7b}', '\u{16b}', '\u{308}'),
+    ('\u{1e7c}', 'V', '\u{303}'),
+    ('\u{1e7d}', 'v', '\u{303}'),
+    ('\u{1e7e}', 'V', '\u{323}'),
+    ('\u{1e7f}', 'v', '\u{323}'),
+    ('\u{1e80}', 'W', '\u{300}'),
+    ('\u{1e81}', 'w', '\u{300}'),
+    ('\u{1e82}', 'W', '\u{301}'),
+    ('\u{1e83}', 'w', '\u{301}'),
+    ('\u{1e84}', 'W', '\u{308}'),
+    ('\u{1e85}', 'w', '\u{308}'),
+    ('\u{1e86}', 'W', '\u{307}'),
+    ('\u{1e87}', 'w', '\u{307}'),
+    ('\u{1e88}', 'W', '\u{323}'),
+    ('\u{1e89}', 'w', '\u{323}'),
+    ('\u{1e8a}', 'X', '\u{307}'),
+    ('\u{1e8b}', 'x', '\u{307}'),
+    ('\u{1e8c}', 'X', '\u{308}'),
+    ('\u{1e8d}', 'x', '\u{308}'),
+    ('\u{1e8e}', 'Y', '\u{307}'),
+    ('\u{1e8f}', 'y', '\u{307}'),
+    ('\u{1e90}', 'Z', '\u{302}'),
+    ('\u{1e91}', 'z', '\u{302}'),
+    ('\u{1e92}', 'Z', '\u{323}'),
+    ('\u{1e93}', 'z', '\u{323}'),
+    ('\u{1e94}', 'Z', '\u{331}'),
+    ('\u{1e95}', 'z', '\u{331}'),
+    ('\u{1e96}', 'h', '\u{331}'),
+    ('\u{1e97}', 't', '\u{308}'),
+    ('\u{1e98}', 'w', '\u{30a}'),
+    ('\u{1e99}', 'y', '\u{30a}'),
+    ('\u{1e9b}', '\u{17f}', '\u{307}'),
+    ('\u{1ea0}', 'A', '\u{323}'),
+    ('\u{1ea1}', 'a', '\u{323}'),
+    ('\u{1ea2}', 'A', '\u{309}'),
+    ('\u{1ea3}', 'a', '\u{309}'),
+    ('\u{1ea4}', '\u{c2}', '\u{301}'),
+    ('\u{1ea5}', '\u{e2}', '\u{301}'),
+    ('\u{1ea6}', '\u{c2}', '\u{300}'),
+    ('\u{1ea7}', '\u{e2}', '\u{300}'),
+    ('\u{1ea8}', '\u{c2}', '\u{309}'),
+    ('\u{1ea9}', '\u{e2}', '\u{309}'),
+    ('\u{1eaa}', '\u{c2}', '\u{303}'),
+    ('\u{1eab}', '\u{e2}', '\u{303}'),
+    ('\u{1eac}', '\u{1ea0}', '\u{302}'),
+    ('\u{1ead}', '\u{1ea1}', '\u{302}'),
+    ('\u{1eae}', '\u{102}', '\u{301}'),
+    ('\u{1eaf}', '\u{103}', '\u{301}'),
+    ('\u{1eb0}', '\u{102}', '\u{300}'),
+    ('\u{1eb1}', '\u{103}', '\u{300}'),
+    ('\u{1eb2}', '\u{102}', '\u{309}'),
+    ('\u{1eb3}', '\u{103}', '\u{309}'),
+    ('\u{1eb4}', '\u{102}', '\u{303}'),
+    ('\u{1eb5}', '\u{103}', '\u{303}'),
+    ('\u{1eb6}', '\u{1ea0}', '\u{306}'),
+    ('\u{1eb7}', '\u{1ea1}', '\u{306}'),
+    ('\u{1eb8}', 'E', '\u{323}'),
+    ('\u{1eb9}', 'e', '\u{323}'),
+    ('\u{1eba}', 'E', '\u{309}'),
+    ('\u{1ebb}', 'e', '\u{309}'),
+    ('\u{1ebc}', 'E', '\u{303}'),
+    ('\u{1ebd}', 'e', '\u{303}'),
+    ('\u{1ebe}', '\u{ca}', '\u{301}'),
+    ('\u{1ebf}', '\u{ea}', '\u{301}'),
+    ('\u{1ec0}', '\u{ca}', '\u{300}'),
+    ('\u{1ec1}', '\u{ea}', '\u{300}'),
+    ('\u{1ec2}', '\u{ca}', '\u{309}'),
+    ('\u{1ec3}', '\u{ea}', '\u{309}'),
+    ('\u{1ec4}', '\u{ca}', '\u{303}'),
+    ('\u{1ec5}', '\u{ea}', '\u{303}'),
+    ('\u{1ec6}', '\u{1eb8}', '\u{302}'),
+    ('\u{1ec7}', '\u{1eb9}', '\u{302}'),
+    ('\u{1ec8}', 'I', '\u{309}'),
+    ('\u{1ec9}', 'i', '\u{309}'),
+    ('\u{1eca}', 'I', '\u{323}'),
+    ('\u{1ecb}', 'i', '\u{323}'),
+    ('\u{1ecc}', 'O', '\u{323}'),
+    ('\u{1ecd}', 'o', '\u{323}'),
+    ('\u{1ece}', 'O', '\u{309}'),
+    ('\u{1ecf}', 'o', '\u{309}'),
+    ('\u{1ed0}', '\u{d4}', '\u{301}'),
+    ('\u{1ed1}', '\u{f4}', '\u{301}'),
+    ('\u{1ed2}', '\u{d4}', '\u{300}'),
+    ('\u{1ed3}', '\u{f4}', '\u{300}'),
+    ('\u{1ed4}', '\u{d4}', '\u{309}'),
+    ('\u{1ed5}', '\u{f4}', '\u{309}'),
+    ('\u{1ed6}', '\u{d4}', '\u{303}'),
+    ('\u{1ed7}', '\u{f4}', '\u{303}'),
+    ('\u{1ed8}', '\u{1ecc}', '\u{302}'),
+    ('\u{1ed9}', '\u{1ecd}', '\u{302}'),
+    ('\u{1eda}', '\u{1a0}', '\u{301}'),
+    ('\u{1edb}', '\u{1a1}', '\u{301}'),
+    ('\u{1edc}', '\u{1a0}', '\u{300}'),
+    ('\u{1edd}', '\u{1a1}', '\u{300}'),
+    ('\u{1ede}', '\u{1a0}', '\u{309}'),
+    ('\u{1edf}', '\u{1a1}', '\u{309}'),
+    ('\u{1ee0}', '\u{1a0}', '\u{303}'),
+    ('\u{1ee1}', '\u{1a1}', '\u{303}'),
+    ('\u{1ee2}', '\u{1a0}', '\u{323}'),
+    ('\u{1ee3}', '\u{1a1}', '\u{323}'),
+    ('\u{1ee4}', 'U', '\u{323}'),
+    ('\u{1ee5}', 'u', '\u{323}'),
+    ('\u{1ee6}', 'U', '\u{309}'),
+    ('\u{1ee7}', 'u', '\u{309}'),
+    ('\u{1ee8}', '\u{1af}', '\u{301}'),
+    ('\u{1ee9}', '\u{1b0}', '\u{301}'),
+    ('\u{1eea}', '\u{1af}', '\u{300}'),
+    ('\u{1eeb}', '\u{1b0}', '\u{300}'),
+    ('\u{1eec}', '\u{1af}', '\u{309}'),
+    ('\u{1eed}', '\u{1b0}', '\u{309}'),
+    ('\u{1eee}', '\u{1af}', '\u{303}'),
+    ('\u{1eef}', '\u{1b0}', '\u{303}'),
+    ('\u{1ef0}', '\u{1af}', '\u{323}'),
+    ('\u{1ef1}', '\u{1b0}', '\u{323}'),
+    ('\u{1ef2}', 'Y', '\u{300}'),
+    ('\u{1ef3}', 'y', '\u{300}'),
+    ('\u{1ef4}', 'Y', '\u{323}'),
+    ('\u{1ef5}', 'y', '\u{323}'),
+    ('\u{1ef6}', 'Y', '\u{309}'),
+    ('\u{1ef7}', 'y', '\u{309}'),
+    ('\u{1ef8}', 'Y', '\u{303}'),
+    ('\u{1ef9}', 'y', '\u{303}'),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nfc() {
+        assert_eq!(nfc("e\u{301}te\u{301}"), "été");
+        assert_eq!(nfc("été"), "été");
+        assert_eq!(nfc("Vie\u{323}\u{302}t"), "Việt");
+        assert_eq!(nfc("Vie\u{302}\u{323}t"), "Việt");
+        assert_eq!(nfc("c\u{327}a"), "ça");
+        assert_eq!(nfc("日本"), "日本");
+    }
+
+    #[test]
+    fn test_to_ascii() {
+        assert_eq!(to_ascii("Évacuation à 14h — cœur « stable »"), "Evacuation a 14h - coeur \" stable \"");
+        assert_eq!(to_ascii("Việt Nam, Łódź, 5 °C"), "Viet Nam, Lodz, 5 degC");
+        assert_eq!(to_ascii("e\u{301}, 日本"), "e, ??");
+    }
+}
@@ -0,0 +1,99 @@
+//! Document validation
+//!
+//! Structured report produced by [`MMLParser::validate`](crate::MMLParser::validate).
+
+use crate::error::MMLError;
+
+/// A single problem found while validating a document
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationIssue {
+    /// 1-based line number, when the issue maps to a line
+    pub line: Option<usize>,
+    /// Underlying error, with its suggestion
+    pub error: MMLError,
+}
+
+impl ValidationIssue {
+    /// Create a new issue
+    pub fn new(line: Option<usize>, error: MMLError) -> Self {
+        Self { line, error }
+    }
+}
+
+/// Result of validating an MML document
+///
+/// Errors make `parse` fail or lose content; warnings flag documents that
+/// parse fine but are likely wrong.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ValidationReport {
+    /// Problems that prevent a correct parse
+    pub errors: Vec<ValidationIssue>,
+    /// Suspicious but parseable constructs
+    pub warnings: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+    /// Create an empty report
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// True when no error was found (warnings are allowed)
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// True when neither errors nor warnings were found
+    pub fn is_clean(&self) -> bool {
+        self.errors.is_empty() && self.warnings.is_empty()
+    }
+
+    /// Record an error
+    pub fn error(&mut self, line: Option<usize>, error: MMLError) {
+        self.errors.push(ValidationIssue::new(line, error));
+    }
+
+    /// Record a warning
+    pub fn warning(&mut self, line: Option<usize>, error: MMLError) {
+        self.warnings.push(ValidationIssue::new(line, error));
+    }
+
+    /// Convert into a `Result`, failing with the first error
+    pub fn into_result(self) -> Result<Self, MMLError> {
+        match self.errors.first() {
+            Some(issue) => Err(issue.error.clone()),
+            None => Ok(self),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_empty_report_is_valid() {
+        let report = ValidationReport::new();
+        assert!(report.is_valid());
+        assert!(report.is_clean());
+        assert!(report.into_result().is_ok());
+    }
+
+    #[test]
+    fn test_warnings_keep_report_valid() {
+        let mut report = ValidationReport::new();
+        report.warning(None, MMLError::StructureError {
+            message: "Document has no title".to_string(),
+            suggestion: "Add a 'T:' line.".to_string(),
+        });
+        assert!(report.is_valid());
+        assert!(!report.is_clean());
+
+        report.error(Some(3), MMLError::ParseError {
+            message: "Failed to parse line 3".to_string(),
+            context: "X".to_string(),
+        });
+        assert!(!report.is_valid());
+        assert!(report.into_result().is_err());
+    }
+}
@@ -0,0 +1,328 @@
+//! DOM traversal
+//!
+//! [`Visit`] and [`VisitMut`] have one method per node type. Each method
+//! defaults to visiting the node's children, so an implementation only
+//! overrides the nodes it cares about and keeps working when new node types
+//! are added.
+//!
+//! Traversal order is fixed: packets, metadata (sorted by key), paragraphs,
+//! links, images, code blocks, quotes, then sections, each section walking
+//! its own nodes in the same order.
+
+use crate::types::*;
+
+/// Read-only visitor over an [`MMLDocument`]
+///
+/// Only the document and section hooks recurse by default; the other
+/// hooks are leaves and do nothing unless overridden.
+///
+/// # Example
+///
+/// ```rust
+/// use mml_parser::visit::{walk, Visit};
+/// use mml_parser::{parse_mml, MMLLink};
+///
+/// struct LinkCounter(usize);
+///
+/// impl Visit for LinkCounter {
+///     fn visit_link(&mut self, _link: &MMLLink) {
+///         self.0 += 1;
+///     }
+/// }
+///
+/// let document = parse_mml("L:Accueil|/\nH:Liens\nL:Docs|/docs").unwrap();
+/// let mut counter = LinkCounter(0);
+/// walk(&document, &mut counter);
+/// assert_eq!(counter.0, 2);
+/// ```
+pub trait Visit {
+    /// Called once by [`walk`]; the default walks packets, metadata,
+    /// document-level nodes and sections with [`walk_document`]. An
+    /// override must call `walk_document` itself to keep recursing.
+    fn visit_document(&mut self, document: &MMLDocument) {
+        walk_document(self, document);
+    }
+
+    /// Called for each section, after the document-level nodes; the
+    /// default walks the section's nodes with [`walk_section`]. An
+    /// override must call `walk_section` itself to keep recursing.
+    fn visit_section(&mut self, section: &MMLSection) {
+        walk_section(self, section);
+    }
+
+    /// Called for each paragraph, document-level ones first, then the
+    /// paragraph of each section.
+    fn visit_paragraph(&mut self, _paragraph: &str) {}
+
+    /// Called for each metadata entry, sorted by key, before the other
+    /// nodes of the document or section.
+    fn visit_metadata(&mut self, _key: &str, _value: &str) {}
+
+    /// Called for each link, in source order.
+    fn visit_link(&mut self, _link: &MMLLink) {}
+
+    /// Called for each image, in source order.
+    fn visit_image(&mut self, _image: &MMLImage) {}
+
+    /// Called for each code block, in source order, with its lines
+    /// already merged.
+    fn visit_code(&mut self, _code: &MMLCode) {}
+
+    /// Called for each quote, in source order.
+    fn visit_quote(&mut self, _quote: &MMLQuote) {}
+
+    /// Called for each packet header, first of all, at document level
+    /// only.
+    fn visit_packet(&mut self, _packet: &MMLPacket) {}
+}
+
+/// Mutable visitor over an [`MMLDocument`]
+///
+/// Metadata keys are passed read-only since changing them would move the
+/// entry; values, paragraphs and the other nodes can be rewritten in place.
+/// As with [`Visit`], only the document and section hooks recurse by
+/// default.
+pub trait VisitMut {
+    /// Called once by [`walk_mut`]; the default recurses with
+    /// [`walk_document_mut`], which an override must call to keep going.
+    fn visit_document_mut(&mut self, document: &mut MMLDocument) {
+        walk_document_mut(self, document);
+    }
+
+    /// Called for each section; the default recurses with
+    /// [`walk_section_mut`], which an override must call to keep going.
+    fn visit_section_mut(&mut self, section: &mut MMLSection) {
+        walk_section_mut(self, section);
+    }
+
+    /// Called for each paragraph, in the same order as [`Visit`].
+    fn visit_paragraph_mut(&mut self, _paragraph: &mut String) {}
+
+    /// Called for each metadata entry, sorted by key; only the value can
+    /// be changed.
+    fn visit_metadata_mut(&mut self, _key: &str, _value: &mut String) {}
+
+    /// Called for each link.
+    fn visit_link_mut(&mut self, _link: &mut MMLLink) {}
+
+    /// Called for each image.
+    fn visit_image_mut(&mut self, _image: &mut MMLImage) {}
+
+    /// Called for each code block; its language can be changed too.
+    fn visit_code_mut(&mut self, _code: &mut MMLCode) {}
+
+    /// Called for each quote.
+    fn visit_quote_mut(&mut self, _quote: &mut MMLQuote) {}
+
+    /// Called for each packet header, at document level only.
+    fn visit_packet_mut(&mut self, _packet: &mut MMLPacket) {}
+}
+
+/// Walk a document with a visitor
+pub fn walk<V: Visit + ?Sized>(document: &MMLDocument, visitor: &mut V) {
+    visitor.visit_document(document);
+}
+
+/// Walk a document with a mutable visitor
+pub fn walk_mut<V: VisitMut + ?Sized>(document: &mut MMLDocument, visitor: &mut V) {
+    visitor.visit_document_mut(document);
+}
+
+/// Visit the children of a document
+pub fn walk_document<V: Visit + ?Sized>(visitor: &mut V, document: &MMLDocument) {
+    for packet in &document.packets {
+        visitor.visit_packet(packet);
+    }
+    for (key, value) in sorted_entries(&document.metadata) {
+        visitor.visit_metadata(key, value);
+    }
+    for paragraph in &document.content {
+        visitor.visit_paragraph(paragraph);
+    }
+    for link in &document.links {
+        visitor.visit_link(link);
+    }
+    for image in &document.images {
+        visitor.visit_image(image);
+    }
+    for code in &document.code {
+        visitor.visit_code(code);
+    }
+    for quote in &document.quotes {
+        visitor.visit_quote(quote);
+    }
+    for section in &document.sections {
+        visitor.visit_section(section);
+    }
+}
+
+/// Visit the children of a section
+pub fn walk_section<V: Visit + ?Sized>(visitor: &mut V, section: &MMLSection) {
+    for (key, value) in sorted_entries(&section.metadata) {
+        visitor.visit_metadata(key, value);
+    }
+    if let Some(paragraph) = &section.content {
+        visitor.visit_paragraph(paragraph);
+    }
+    for link in &section.links {
+        visitor.visit_link(link);
+    }
+    for image in &section.images {
+        visitor.visit_image(image);
+    }
+    for code in &section.code {
+        visitor.visit_code(code);
+    }
+    for quote in &section.quotes {
+        visitor.visit_quote(quote);
+    }
+}
+
+/// Visit the children of a document, mutably
+pub fn walk_document_mut<V: VisitMut + ?Sized>(visitor: &mut V, document: &mut MMLDocument) {
+    for packet in &mut document.packets {
+        visitor.visit_packet_mut(packet);
+    }
+    let mut metadata: Vec<_> = document.metadata.iter_mut().collect();
+    metadata.sort_by(|a, b| a.0.cmp(b.0));
+    for (key, value) in metadata {
+        visitor.visit_metadata_mut(key, value);
+    }
+    for paragraph in &mut document.content {
+        visitor.visit_paragraph_mut(paragraph);
+    }
+    for link in &mut document.links {
+        visitor.visit_link_mut(link);
+    }
+    for image in &mut document.images {
+        visitor.visit_image_mut(image);
+    }
+    for code in &mut document.code {
+        visitor.visit_code_mut(code);
+    }
+    for quote in &mut document.quotes {
+        visitor.visit_quote_mut(quote);
+    }
+    for section in &mut document.sections {
+        visitor.visit_section_mut(section);
+    }
+}
+
+/// Visit the children of a section, mutably
+pub fn walk_section_mut<V: VisitMut + ?Sized>(visitor: &mut V, section: &mut MMLSection) {
+    let mut metadata: Vec<_> = section.metadata.iter_mut().collect();
+    metadata.sort_by(|a, b| a.0.cmp(b.0));
+    for (key, value) in metadata {
+        visitor.visit_metadata_mut(key, value);
+    }
+    if let Some(paragraph) = &mut section.content {
+        visitor.visit_paragraph_mut(paragraph);
+    }
+    for link in &mut section.links {
+        visitor.visit_link_mut(link);
+    }
+    for image in &mut section.images {
+        visitor.visit_image_mut(image);
+    }
+    for code in &mut section.code {
+        visitor.visit_code_mut(code);
+    }
+    for quote in &mut section.quotes {
+        visitor.visit_quote_mut(quote);
+    }
+}
+
+/// Metadata entries in key order, for a deterministic traversal
+fn sorted_entries(metadata: &std::collections::HashMap<String, String>) -> Vec<(&String, &String)> {
+    let mut entries: Vec<_> = metadata.iter().collect();
+    entries.sort_by(|a, b| a.0.cmp(b.0));
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_mml;
+
+    const REPORT: &str = "T:Rapport\nM:Priorité|CRITIQUE\nM:ID|INC-001\nP:Évacuer\n\
+                          PKT:MSG-1\nH:Situation\nP:Incendie\nM:Surface|450 hectares\n\
+                          L:Carte|carte.png\nIMG:Photo|photo.jpg\nC:x = 1\nQ:Vite|Chef\nEND";
+
+    #[derive(Default)]
+    struct Recorder(Vec<String>);
+
+    impl Visit for Recorder {
+        fn visit_section(&mut self, section: &MMLSection) {
+            self.0.push(format!("section:{}", section.title));
+            walk_section(self, section);
+        }
+        fn visit_paragraph(&mut self, paragraph: &str) {
+            self.0.push(format!("paragraph:{}", paragraph));
+        }
+        fn visit_metadata(&mut self, key: &str, _value: &str) {
+            self.0.push(format!("metadata:{}", key));
+        }
+        fn visit_link(&mut self, link: &MMLLink) {
+            self.0.push(format!("link:{}", link.text));
+        }
+        fn visit_image(&mut self, image: &MMLImage) {
+            self.0.push(format!("image:{}", image.description));
+        }
+        fn visit_code(&mut self, code: &MMLCode) {
+            self.0.push(format!("code:{}", code.content));
+        }
+        fn visit_quote(&mut self, quote: &MMLQuote) {
+            self.0.push(format!("quote:{}", quote.content));
+        }
+        fn visit_packet(&mut self, packet: &MMLPacket) {
+            self.0.push(format!("packet:{}", packet.id));
+        }
+    }
+
+    #[test]
+    fn test_walk_order() {
+        let document = parse_mml(REPORT).unwrap();
+        let mut recorder = Recorder::default();
+        walk(&document, &mut recorder);
+
+        assert_eq!(
+            recorder.0,
+            vec![
+                "packet:MSG-1",
+                "metadata:ID",
+                "metadata:Priorité",
+                "paragraph:Évacuer",
+                "section:Situation",
+                "metadata:Surface",
+                "paragraph:Incendie",
+                "link:Carte",
+                "image:Photo",
+                "code:x = 1",
+                "quote:Vite",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_walk_mut_redaction() {
+        struct Redact;
+
+        impl VisitMut for Redact {
+            fn visit_metadata_mut(&mut self, key: &str, value: &mut String) {
+                if key == "ID" {
+                    *value = "[REDACTED]".to_string();
+                }
+            }
+            fn visit_paragraph_mut(&mut self, paragraph: &mut String) {
+                *paragraph = paragraph.to_uppercase();
+            }
+        }
+
+        let mut document = parse_mml(REPORT).unwrap();
+        walk_mut(&mut document, &mut Redact);
+
+        assert_eq!(document.metadata.get("ID"), Some(&"[REDACTED]".to_string()));
+        assert_eq!(document.content, vec!["ÉVACUER".to_string()]);
+        assert_eq!(document.sections[0].content.as_deref(), Some("INCENDIE"));
+    }
+}